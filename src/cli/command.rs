@@ -1,6 +1,7 @@
 //! Contains the code for the CLI command generation: arguments, flags, etc.
 
 use clap::{command, Arg, ArgAction, Command};
+use crate::cli::format;
 
 pub fn get_command() -> Command {
     command!()
@@ -10,6 +11,7 @@ pub fn get_command() -> Command {
             .long("debug")
             .action(ArgAction::SetTrue)
             .help("Turns on extensive debug output information")
+            .global(true)
         )
         .arg(Arg::new("VERBOSE")
             .short('v')
@@ -17,6 +19,7 @@ pub fn get_command() -> Command {
             .help("Use verbose output (extra information)")
             .action(ArgAction::SetTrue)
             .conflicts_with("QUIET")
+            .global(true)
         )
         .arg(Arg::new("QUIET")
             .short('q')
@@ -24,6 +27,26 @@ pub fn get_command() -> Command {
             .help("Do not print any log messages")
             .action(ArgAction::SetTrue)
             .conflicts_with("VERBOSE")
+            .global(true)
+        )
+        .arg(Arg::new("CONFIG")
+            .short('c')
+            .long("config")
+            .help("Use the specified config file instead of the default one")
+            .action(ArgAction::Set)
+            .global(true)
+        )
+        .arg(Arg::new("DATA_DIR")
+            .long("data-dir")
+            .help("Use the specified directory for profiles and other program data instead of the default one")
+            .action(ArgAction::Set)
+            .global(true)
+        )
+        .arg(Arg::new("JSON")
+            .long("json")
+            .help("Print the result as a single JSON document. Log messages are printed to the standard error")
+            .action(ArgAction::SetTrue)
+            .global(true)
         )
         /* BOX SUBCOMMAND */
         .subcommand(Command::new("box")
@@ -33,24 +56,33 @@ pub fn get_command() -> Command {
                 .default_value(".")
                 .action(ArgAction::Append)
             )
-            .arg(Arg::new("PASSWORD")
-                .short('p')
-                .long("password")
-                .help("Specify the password used for authentication")
-                .action(ArgAction::Set)
-            )
+            .args(password_args())
             .arg(Arg::new("RECURSIVE")
                 .short('R')
                 .long("recursive")
                 .help("Recursively encrypt directory")
                 .action(ArgAction::SetTrue)
             )
+            .arg(Arg::new("JOBS")
+                .short('j')
+                .long("jobs")
+                .help("Specify the number of files to encrypt at the same time (0 uses all available cores)")
+                .value_parser(clap::value_parser!(usize))
+                .action(ArgAction::Set)
+            )
             .arg(Arg::new("KEEP_NAME")
                 .short('k')
                 .long("keep-name")
                 .help("Keep original file name for the encrypted file")
                 .action(ArgAction::SetTrue)
                 .conflicts_with("OUTPUT")
+                .overrides_with("NO_KEEP_NAME")
+            )
+            .arg(Arg::new("NO_KEEP_NAME")
+                .long("no-keep-name")
+                .help("Replace the file name with a random UUID even if keep_name is set in the config")
+                .action(ArgAction::SetTrue)
+                .overrides_with("KEEP_NAME")
             )
             .arg(Arg::new("OUTPUT")
                 .short('o')
@@ -65,19 +97,42 @@ pub fn get_command() -> Command {
                 .help("Output the full relative path to the encrypted file")
                 .action(ArgAction::SetTrue)
             )
-            // .arg(Arg::new("overwrite") // TODO
-            //     .short('w')
-            //     .long("overwrite")
-            //     .help("Automatically overwrite existing files without prompting the user")
-            //     .action(ArgAction::SetTrue)
-            // )
-            // .arg(Arg::new("compression") // TODO
-            //     .short('z')
-            //     .long("compression")
-            //     .help("Compresses the file(s) before encryption")
-            //     .action(ArgAction::Set)
-            //     .default_value("none")
-            // )
+            .arg(Arg::new("KEEP_ORIGINAL")
+                .long("keep-original")
+                .help("Keep the original file after encryption")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["VERIFY", "SECURE_DELETE"])
+            )
+            .arg(Arg::new("VERIFY")
+                .long("verify")
+                .help("Delete the original file only after the output was read back and verified")
+                .action(ArgAction::SetTrue)
+            )
+            .arg(Arg::new("SECURE_DELETE")
+                .long("secure-delete")
+                .help("Verify the output and overwrite the original file with random data before deleting it")
+                .action(ArgAction::SetTrue)
+            )
+            .arg(Arg::new("OVERWRITE")
+                .short('w')
+                .long("overwrite")
+                .help("Automatically overwrite existing files without prompting the user")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("CONFLICT")
+            )
+            .arg(Arg::new("CONFLICT")
+                .long("conflict")
+                .help("Specify what to do if the output file already exists")
+                .value_parser(["ask", "overwrite", "skip", "rename-with-suffix"])
+                .action(ArgAction::Set)
+            )
+            .arg(Arg::new("COMPRESSION")
+                .short('z')
+                .long("compression")
+                .help("Compress the file(s) before encryption. Defaults to the compression from the config")
+                .value_parser(["none", "deflate"])
+                .action(ArgAction::Set)
+            )
             // .arg(Arg::new("exclude") // TODO
             //     .short('e')
             //     .long("exclude")
@@ -98,18 +153,20 @@ pub fn get_command() -> Command {
                 .default_value(".")
                 .action(ArgAction::Append)
             )
-            .arg(Arg::new("PASSWORD")
-                .short('p')
-                .long("password")
-                .help("Specify the password used for authentication")
-                .action(ArgAction::Set)
-            )
+            .args(password_args())
             .arg(Arg::new("RECURSIVE")
                 .short('R')
                 .long("recursive")
                 .help("Recursively decrypt directory")
                 .action(ArgAction::SetTrue)
             )
+            .arg(Arg::new("JOBS")
+                .short('j')
+                .long("jobs")
+                .help("Specify the number of files to decrypt at the same time (0 uses all available cores)")
+                .value_parser(clap::value_parser!(usize))
+                .action(ArgAction::Set)
+            )
             .arg(Arg::new("OUTPUT")
                 .short('o')
                 .long("output")
@@ -122,23 +179,65 @@ pub fn get_command() -> Command {
                 .help("Output the full relative path to the decrypted file")
                 .action(ArgAction::SetTrue)
             )
-            // .arg(Arg::new("overwrite") // TODO
-            //     .short('w')
-            //     .long("overwrite")
-            //     .help("Automatically overwrite existing files without prompting the user")
-            //     .action(ArgAction::SetTrue)
-            // )
+            .arg(Arg::new("KEEP_ORIGINAL")
+                .long("keep-original")
+                .help("Keep the encrypted file after decryption")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["VERIFY", "SECURE_DELETE"])
+            )
+            .arg(Arg::new("VERIFY")
+                .long("verify")
+                .help("Delete the encrypted file only after the output was read back and verified")
+                .action(ArgAction::SetTrue)
+            )
+            .arg(Arg::new("SECURE_DELETE")
+                .long("secure-delete")
+                .help("Verify the output and overwrite the encrypted file with random data before deleting it")
+                .action(ArgAction::SetTrue)
+            )
+            .arg(Arg::new("OVERWRITE")
+                .short('w')
+                .long("overwrite")
+                .help("Automatically overwrite existing files without prompting the user")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("CONFLICT")
+            )
+            .arg(Arg::new("CONFLICT")
+                .long("conflict")
+                .help("Specify what to do if the output file already exists")
+                .value_parser(["ask", "overwrite", "skip", "rename-with-suffix"])
+                .action(ArgAction::Set)
+            )
             // .arg(Arg::new("check-integrity") // TODO
             //     .short('i')
             //     .long("check-integrity")
             //     .help("Validates the integrity of the decrypted file by comparing it with an original checksum (if available)")
             //     .action(ArgAction::SetTrue)
             // )
-            // .arg(Arg::new("preserve-attributes") // TODO
-            //     .long("preserve-attributes")
-            //     .help("Preserves original file attributes (e.g., permissions, timestamps) when decrypting")
-            //     .action(ArgAction::SetTrue)
-            // )
+            .arg(Arg::new("PRESERVE_ATTRIBUTES")
+                .long("preserve-attributes")
+                .help("Restore original file attributes (modify and access times, permissions, extended attributes and ACLs) when decrypting")
+                .action(ArgAction::SetTrue)
+                .overrides_with("NO_PRESERVE_ATTRIBUTES")
+            )
+            .arg(Arg::new("NO_PRESERVE_ATTRIBUTES")
+                .long("no-preserve-attributes")
+                .help("Don't restore original file attributes even if preserve_attributes is set in the config")
+                .action(ArgAction::SetTrue)
+                .overrides_with("PRESERVE_ATTRIBUTES")
+            )
+            .arg(Arg::new("PRESERVE_OWNER")
+                .long("preserve-owner")
+                .help("Restore the original file owner when decrypting (usually requires elevated privileges)")
+                .action(ArgAction::SetTrue)
+                .overrides_with("NO_PRESERVE_OWNER")
+            )
+            .arg(Arg::new("NO_PRESERVE_OWNER")
+                .long("no-preserve-owner")
+                .help("Don't restore the original file owner even if preserve_owner is set in the config")
+                .action(ArgAction::SetTrue)
+                .overrides_with("PRESERVE_OWNER")
+            )
         )
         .subcommand(Command::new("information")
             .about("Parse and get original file information from a \".box\" file")
@@ -154,6 +253,61 @@ pub fn get_command() -> Command {
                 .help("Show the unknown metadata")
                 .action(ArgAction::SetTrue)
             )
+            .arg(Arg::new("TIME_FORMAT")
+                .long("time-format")
+                .help("Format of the times: \"rfc3339\", \"unix\" or a custom one like \"%Y-%m-%d %H:%M\". Defaults to \"%d.%m.%Y %T\" (RFC 3339 for JSON)")
+                .value_parser(format::parse_time_format)
+                .action(ArgAction::Set)
+            )
+        )
+        /* FIND SUBCOMMAND */
+        .subcommand(Command::new("find")
+            .about("Find \".box\" files by the original name of the encrypted file")
+            .arg(Arg::new("PATTERN")
+                .help("Glob pattern (or regular expression with --regex) matching the original name including the extension")
+                .required(true)
+            )
+            .arg(Arg::new("PATH")
+                .help("Specify the directories to search recursively. Defaults to the current directory")
+                .action(ArgAction::Append)
+            )
+            .arg(Arg::new("REGEX")
+                .short('e')
+                .long("regex")
+                .help("Treat the pattern as a regular expression matching any part of the name")
+                .action(ArgAction::SetTrue)
+            )
+            .arg(Arg::new("IGNORE_CASE")
+                .short('i')
+                .long("ignore-case")
+                .help("Match the original names regardless of their case")
+                .action(ArgAction::SetTrue)
+            )
+        )
+        /* AGENT SUBCOMMAND */
+        .subcommand(Command::new("agent")
+            .about("Run a key agent in the foreground which keeps the current profile unlocked for other commands")
+            .args(password_args())
+            .arg(Arg::new("SOCKET")
+                .short('s')
+                .long("socket")
+                .help("Specify the path to the agent socket. Defaults to DATABOXER_AGENT_SOCK or the socket in the data directory")
+                .action(ArgAction::Set)
+            )
+            .arg(Arg::new("TIMEOUT")
+                .short('t')
+                .long("timeout")
+                .help("Specify after how many idle seconds the agent stops")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("900")
+                .action(ArgAction::Set)
+            )
+            .arg(Arg::new("STOP")
+                .long("stop")
+                .help("Stop the running agent")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["PASSWORD", "PASSWORD_FILE", "PASSWORD_FD", "TIMEOUT"])
+            )
         )
         /* PROFILE SUBCOMMAND */
         .subcommand(Command::new("profile")
@@ -166,11 +320,11 @@ pub fn get_command() -> Command {
                     .help("A unique name for the profile")
                     .required(true)
                 )
-                .arg(Arg::new("PASSWORD")
-                    .short('p')
-                    .long("password")
-                    .help("Specify the password used for authentication")
-                    .action(ArgAction::Set)
+                .args(password_args())
+                .arg(Arg::new("FORCE_WEAK")
+                    .long("force-weak")
+                    .help("Create the profile even if the password is too weak")
+                    .action(ArgAction::SetTrue)
                 )
             )
             /* DELETE PROFILE SUBCOMMAND */
@@ -181,12 +335,7 @@ pub fn get_command() -> Command {
                     .help("Name of the profile to delete")
                     .required(true)
                 )
-                .arg(Arg::new("PASSWORD")
-                    .short('p')
-                    .long("password")
-                    .help("Specify the password used for authentication")
-                    .action(ArgAction::Set)
-                )
+                .args(password_args())
             )
             /* SELECT PROFILE SUBCOMMAND */
             .subcommand(Command::new("set")
@@ -196,23 +345,75 @@ pub fn get_command() -> Command {
                     .help("Name of the profile to switch to")
                     .required(true)
                 )
-                .arg(Arg::new("PASSWORD")
-                    .short('p')
-                    .long("password")
-                    .help("Specify the password used for authentication")
-                    .action(ArgAction::Set)
-                )
+                .args(password_args())
             )
             /* GET PROFILE SUBCOMMAND */
             .subcommand(Command::new("get")
                 .about("Get current profile's name")
                 .alias("current")
             )
+            /* SHOW PROFILE SUBCOMMAND */
+            .subcommand(Command::new("show")
+                .about("Show information about a profile and its recent failed authentication attempts")
+                .alias("info")
+                .arg(Arg::new("NAME")
+                    .help("Name of the profile to show, the current one if not specified")
+                )
+            )
             /* LIST PROFILE SUBCOMMAND */
             .subcommand(Command::new("list")
                 .about("List all available profiles (names)")
             )
         )
+        /* MIGRATE STORE SUBCOMMAND */
+        .subcommand(Command::new("migrate-store")
+            .about("Convert the JSON profile store into an SQLite database. The JSON file is kept as a backup")
+        )
+        /* STORE SUBCOMMAND */
+        .subcommand(Command::new("store")
+            .about("Seal the profile store with a master password or restore it")
+            /* LOCK STORE SUBCOMMAND */
+            .subcommand(Command::new("lock")
+                .about("Encrypt the whole profile store with a master password")
+                .alias("seal")
+                .args(password_args())
+                .arg(Arg::new("FORCE_WEAK")
+                    .long("force-weak")
+                    .help("Seal the store even if the master password is too weak")
+                    .action(ArgAction::SetTrue)
+                )
+            )
+            /* UNLOCK STORE SUBCOMMAND */
+            .subcommand(Command::new("unlock")
+                .about("Decrypt the profile store, so the master password is no longer needed")
+                .alias("unseal")
+                .args(password_args())
+            )
+        )
+        /* TRACKED SUBCOMMAND */
+        .subcommand(Command::new("tracked")
+            .about("Control the registry of boxfiles produced by the current profile")
+            /* LIST TRACKED SUBCOMMAND */
+            .subcommand(Command::new("list")
+                .about("List all boxfiles produced by the current profile")
+            )
+            /* FIND TRACKED SUBCOMMAND */
+            .subcommand(Command::new("find")
+                .about("Find the boxfiles encrypted from a file with the original name")
+                .arg(Arg::new("NAME")
+                    .help("Original name of the file including its extension")
+                    .required(true)
+                )
+            )
+            /* PRUNE TRACKED SUBCOMMAND */
+            .subcommand(Command::new("prune")
+                .about("Remove the boxfiles which no longer exist from the registry, updating the ones which were moved")
+                .arg(Arg::new("SCAN")
+                    .help("Paths to search (recursively) for the boxfiles missing from their recorded paths")
+                    .action(ArgAction::Append)
+                )
+            )
+        )
         /* KEY SUBCOMMAND */
         .subcommand(Command::new("key")
             .about("Control profile\'s encryption key")
@@ -220,22 +421,12 @@ pub fn get_command() -> Command {
             .subcommand(Command::new("new")
                 .about("Generate a new encryption key for the current profile")
                 .alias("generate")
-                .arg(Arg::new("PASSWORD")
-                    .short('p')
-                    .long("password")
-                    .help("Specify the password used for authentication")
-                    .action(ArgAction::Set)
-                )
+                .args(password_args())
             )
             /* GET KEY SUBCOMMAND */
             .subcommand(Command::new("get")
                 .about("Get current profile\'s encryption key")
-                .arg(Arg::new("PASSWORD")
-                    .short('p')
-                    .long("password")
-                    .help("Specify the password used for authentication")
-                    .action(ArgAction::Set)
-                )
+                .args(password_args())
                 .arg(Arg::new("AS_BYTE_ARRAY")
                     .help("Output key as an array of bytes")
                     .short('b')
@@ -250,12 +441,74 @@ pub fn get_command() -> Command {
                     .help("A 32-byte encryption key represented by hex values (e.g.: DA495EFCF25904AC2FF438BE380FF660E150E65B03AC543398C43AD4FC617962)")
                     .required(true)
                 )
-                .arg(Arg::new("PASSWORD")
-                    .short('p')
-                    .long("password")
-                    .help("Specify the password used for authentication")
-                    .action(ArgAction::Set)
+                .args(password_args())
+            )
+        )
+        /* CONFIG SUBCOMMAND */
+        .subcommand(Command::new("config")
+            .about("View and change the configuration")
+            /* GET CONFIG SUBCOMMAND */
+            .subcommand(Command::new("get")
+                .about("Get the value of a setting")
+                .arg(Arg::new("KEY")
+                    .help("Key of the setting as the section and the name (e.g. encryption.compression)")
+                    .required(true)
+                )
+            )
+            /* SET CONFIG SUBCOMMAND */
+            .subcommand(Command::new("set")
+                .about("Set the value of a setting")
+                .arg(Arg::new("KEY")
+                    .help("Key of the setting as the section and the name (e.g. encryption.compression)")
+                    .required(true)
+                )
+                .arg(Arg::new("VALUE")
+                    .help("New value of the setting")
+                    .required(true)
+                )
+            )
+            /* LIST CONFIG SUBCOMMAND */
+            .subcommand(Command::new("list")
+                .about("List all settings with their current values")
+                .arg(Arg::new("ORIGIN")
+                    .long("origin")
+                    .help("Show the layer each value comes from (default, global or project file, environment variable)")
+                    .action(ArgAction::SetTrue)
+                )
+            )
+            /* RESET CONFIG SUBCOMMAND */
+            .subcommand(Command::new("reset")
+                .about("Reset a setting or the whole config to the default values")
+                .arg(Arg::new("KEY")
+                    .help("Key of the setting to reset. Resets the whole config if not specified")
                 )
             )
+            /* PATH CONFIG SUBCOMMAND */
+            .subcommand(Command::new("path")
+                .about("Get the path to the config file in use")
+            )
         )
-}
\ No newline at end of file
+}
+
+/// Arguments for passing the profile password non-interactively. Without any of them the password is
+/// taken from the `DATABOXER_PASSWORD` environment variable or asked for
+fn password_args() -> [Arg; 3] {
+    [
+        Arg::new("PASSWORD")
+            .short('p')
+            .long("password")
+            .help("Specify the password used for authentication")
+            .action(ArgAction::Set)
+            .conflicts_with_all(["PASSWORD_FILE", "PASSWORD_FD"]),
+        Arg::new("PASSWORD_FILE")
+            .long("password-file")
+            .help("Read the password from the first line of the specified file")
+            .action(ArgAction::Set)
+            .conflicts_with("PASSWORD_FD"),
+        Arg::new("PASSWORD_FD")
+            .long("password-fd")
+            .help("Read the password from the specified open file descriptor (0 for the standard input)")
+            .value_parser(clap::value_parser!(i32))
+            .action(ArgAction::Set),
+    ]
+}
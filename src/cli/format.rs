@@ -0,0 +1,65 @@
+//! Contains helpers for formatting the values shown in the CLI output
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, Local};
+use chrono::format::{Item, StrftimeItems};
+
+/// Format the times are shown in unless specified otherwise
+pub const DEFAULT_TIME_FORMAT: &str = "%d.%m.%Y %T";
+
+/// Format of the times shown in the output
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeFormat {
+    /// RFC 3339 timestamp in the local timezone
+    Rfc3339,
+    /// Seconds since the Unix epoch
+    Unix,
+    /// Custom `strftime`-like format in the local timezone
+    Custom(String),
+}
+
+impl TimeFormat {
+    /// Formats the time
+    pub fn format(&self, system_time: SystemTime) -> String {
+        let time: DateTime<Local> = system_time.into();
+        match self {
+            TimeFormat::Rfc3339 => time.to_rfc3339(),
+            TimeFormat::Unix => system_time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs().to_string(),
+            TimeFormat::Custom(format) => time.format(format).to_string(),
+        }
+    }
+}
+
+impl TimeFormat {
+    /// Formats the time as a JSON value, which is a number for the Unix timestamps
+    pub fn format_json(&self, system_time: SystemTime) -> serde_json::Value {
+        match self {
+            TimeFormat::Unix => system_time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs().into(),
+            format => format.format(system_time).into()
+        }
+    }
+}
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        TimeFormat::Custom(DEFAULT_TIME_FORMAT.to_string())
+    }
+}
+
+/// Parses the time format passed as an argument: `rfc3339`, `unix` or a custom `strftime`-like
+/// format (e.g. `%Y-%m-%d %H:%M`). Errors if the custom format contains invalid specifiers
+pub fn parse_time_format(format: &str) -> Result<TimeFormat, String> {
+    match format {
+        "rfc3339" => Ok(TimeFormat::Rfc3339),
+        "unix" => Ok(TimeFormat::Unix),
+        format if StrftimeItems::new(format).any(|item| item == Item::Error) => {
+            Err(format!("invalid time format \"{}\"", format))
+        },
+        format => Ok(TimeFormat::Custom(format.to_string()))
+    }
+}
+
+/// Formats the time with the default format
+pub fn format_time(system_time: SystemTime) -> String {
+    TimeFormat::default().format(system_time)
+}
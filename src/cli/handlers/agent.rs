@@ -0,0 +1,70 @@
+//! Contains handlers for the agent subcommand
+
+use clap::ArgMatches;
+use crate::log_error;
+#[cfg(unix)]
+use {
+    std::path::PathBuf,
+    std::time::Duration,
+    serde_json::json,
+    crate::cli::{handlers, output, password},
+    crate::core::agent::{self, AGENT_SOCKET_VAR},
+    crate::{exits_on, log_info, log_success},
+};
+
+#[cfg(unix)]
+pub fn handle_agent(args: &ArgMatches) {
+    let socket_path = get_socket_path(args);
+
+    if args.get_flag("STOP") {
+        match crate::stop_agent(&socket_path) {
+            Ok(_) => {
+                log_success!("Successfully stopped the agent");
+                output::print_if_json(json!({ "stopped": output::path_json(&socket_path) }));
+            },
+            Err(err) => {
+                log_error!("Unable to stop the agent");
+                exits_on!(err; all);
+            }
+        }
+        return
+    }
+
+    let password = password::get_password(args, "Please enter the password for the current profile:");
+
+    let session = crate::unlock_current(&password).unwrap_or_else(|err| {
+        log_error!("Unable to unlock the current profile");
+        exits_on!(err; all);
+    });
+    let timeout = *args.get_one::<u64>("TIMEOUT").expect("Timeout has a default value");
+
+    log_info!("Set {}=\"{}\" to use the agent", AGENT_SOCKET_VAR, socket_path.display());
+    // printed before the agent starts serving, as it only returns once stopped
+    output::print_if_json(json!({ "socket": output::path_json(&socket_path) }));
+    if let Err(err) = crate::run_agent(session, Some(&socket_path), Duration::from_secs(timeout)) {
+        log_error!("Agent has failed");
+        exits_on!(err; all);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn handle_agent(_args: &ArgMatches) {
+    log_error!("The agent is only supported on Unix systems");
+    std::process::exit(1);
+}
+
+/// Returns the agent socket from the passed flag, the environment or the default location
+#[cfg(unix)]
+fn get_socket_path(args: &ArgMatches) -> PathBuf {
+    if let Some(path) = args.get_one::<String>("SOCKET") {
+        return PathBuf::from(path)
+    }
+    if let Some(path) = handlers::get_agent_socket() {
+        return path
+    }
+
+    agent::get_default_socket().unwrap_or_else(|err| {
+        log_error!("Unable to get the default agent socket");
+        exits_on!(err; all);
+    })
+}
@@ -1,14 +1,15 @@
 //! Contains handlers for subcommands under the base `databoxer` command
 
 use clap::ArgMatches;
-use std::path::PathBuf;
-use std::ffi::OsStr;
-use crate::cli::{handlers, prompts};
+use std::path::{Path, PathBuf};
+use std::ffi::{OsStr, OsString};
+use crate::cli::handlers;
+use crate::cli::format::{self, TimeFormat};
+use crate::cli::output;
 use crate::core::utils::path;
-use crate::{exits_on, log_error, log_info, log_success, options};
+use crate::{exits_on, log_error, log_success, log_warn, options, BoxfileInfo};
 
 pub fn handle_box(args: &ArgMatches) -> (u32, u32) {
-    let mut total_files: u32 = 0;
     let mut error_files: u32 = 0;
 
     let file_paths: Vec<PathBuf> = {
@@ -17,28 +18,43 @@ pub fn handle_box(args: &ArgMatches) -> (u32, u32) {
 
         path::parse_paths(input_paths, recursive)
     };
+    if file_paths.is_empty() {
+        if output::is_json() {
+            output::print_json(&output::batch_json(Vec::new()));
+        }
+        return (0, 0)
+    }
 
+    let config = handlers::get_config();
     let mut options = options::EncryptionOptions {
-        keep_original_name: args.get_flag("KEEP_NAME"),
-        output_paths: handlers::get_path_deque(args, "OUTPUT")
+        keep_original_name: handlers::get_switch(args, "KEEP_NAME", "NO_KEEP_NAME")
+            .unwrap_or(config.encryption.keep_name),
+        output_paths: handlers::get_path_deque(args, "OUTPUT"),
+        output_dir: config.storage.output_dir.clone(),
+        cipher: config.encryption.cipher,
+        compression: match args.get_one::<String>("COMPRESSION").map(|s| s.as_str()) {
+            Some("none") => options::Compression::None,
+            Some("deflate") => options::Compression::Deflate,
+            _ => config.encryption.compression
+        },
+        padding: config.encryption.padding,
+        delete_policy: handlers::get_delete_policy(args, &config),
+        conflict_policy: handlers::get_conflict_policy(args, &config),
     };
+    let threads = args.get_one::<usize>("JOBS").copied().unwrap_or(config.general.threads);
 
-    let password = match args.get_one::<String>("PASSWORD") {
-        None => prompts::prompt_password("Please enter the password for the current profile:"),
-        Some(password) => password.to_string()
-    };
+    let session = handlers::get_session(args);
+    let results = session.encrypt_batch(&file_paths, &mut options, threads);
 
-    // encrypt each file and handle errors accordingly
-    for path in file_paths {
-        total_files += 1;
-        let file_name = match args.get_flag("SHOW_FULL_PATH") {
-            true => path.as_os_str().to_os_string(),
-            false => path.file_name().unwrap_or(OsStr::new("<unknown file name>")).to_os_string()
-        };
-
-        log_info!("Encrypting {:?}", file_name);
-        match crate::encrypt(path.as_path(), &password, &mut options) {
-            Ok(_) => log_success!("Successfully encrypted {:?}", file_name),
+    // handle the result of each file accordingly
+    let mut file_results = Vec::new();
+    for result in results {
+        let file_name = get_file_name(&result.input_path, args.get_flag("SHOW_FULL_PATH"));
+        file_results.push(output::batch_result_json(&result));
+
+        match result.output {
+            Ok(Some(_)) => log_success!("Successfully encrypted {:?}", file_name),
+            Ok(None) => log_warn!("Skipped {:?}", file_name),
             Err(err) => {
                 log_error!("Unable to encrypt \"{}\"", file_name.to_string_lossy());
                 exits_on!(err; IOError false; InvalidInput false);
@@ -47,11 +63,13 @@ pub fn handle_box(args: &ArgMatches) -> (u32, u32) {
         }
     }
 
-    (total_files, error_files)
+    if output::is_json() {
+        output::print_json(&output::batch_json(file_results));
+    }
+    (file_paths.len() as u32, error_files)
 }
 
 pub fn handle_unbox(args: &ArgMatches) -> (u32, u32) {
-    let mut total_files: u32 = 0;
     let mut error_files: u32 = 0;
 
     let file_paths: Vec<PathBuf> = {
@@ -60,28 +78,39 @@ pub fn handle_unbox(args: &ArgMatches) -> (u32, u32) {
 
         path::parse_paths(input_paths, recursive)
     };
+    if file_paths.is_empty() {
+        if output::is_json() {
+            output::print_json(&output::batch_json(Vec::new()));
+        }
+        return (0, 0)
+    }
 
     // options for decryption
+    let config = handlers::get_config();
     let mut options = options::DecryptionOptions {
-        output_paths: handlers::get_path_deque(args, "OUTPUT")
+        output_paths: handlers::get_path_deque(args, "OUTPUT"),
+        output_dir: config.storage.output_dir.clone(),
+        delete_policy: handlers::get_delete_policy(args, &config),
+        conflict_policy: handlers::get_conflict_policy(args, &config),
+        preserve_attributes: handlers::get_switch(args, "PRESERVE_ATTRIBUTES", "NO_PRESERVE_ATTRIBUTES")
+            .unwrap_or(config.storage.preserve_attributes),
+        preserve_owner: handlers::get_switch(args, "PRESERVE_OWNER", "NO_PRESERVE_OWNER")
+            .unwrap_or(config.storage.preserve_owner),
     };
+    let threads = args.get_one::<usize>("JOBS").copied().unwrap_or(config.general.threads);
 
-    let password = match args.get_one::<String>("PASSWORD") {
-        None => prompts::prompt_password("Please enter the password for the current profile:"),
-        Some(password) => password.to_string()
-    };
+    let session = handlers::get_session(args);
+    let results = session.decrypt_batch(&file_paths, &mut options, threads);
 
-    // decrypt each file and handle errors accordingly
-    for path in file_paths {
-        total_files += 1;
-        let file_name = match args.get_flag("SHOW_FULL_PATH") {
-            true => path.as_os_str().to_os_string(),
-            false => path.file_name().unwrap_or(OsStr::new("<unknown file name>")).to_os_string()
-        };
-
-        log_info!("Decrypting {:?}", file_name);
-        match crate::decrypt(path.as_path(), &password, &mut options) {
-            Ok(_) => log_success!("Successfully decrypted {:?}", path.file_name().unwrap().to_os_string()),
+    // handle the result of each file accordingly
+    let mut file_results = Vec::new();
+    for result in results {
+        let file_name = get_file_name(&result.input_path, args.get_flag("SHOW_FULL_PATH"));
+        file_results.push(output::batch_result_json(&result));
+
+        match result.output {
+            Ok(Some(_)) => log_success!("Successfully decrypted {:?}", file_name),
+            Ok(None) => log_warn!("Skipped {:?}", file_name),
             Err(err) => {
                 log_error!("Unable to decrypt \"{}\"", file_name.to_string_lossy());
                 exits_on!(err; IOError false; InvalidInput false);
@@ -90,7 +119,18 @@ pub fn handle_unbox(args: &ArgMatches) -> (u32, u32) {
         }
     }
 
-    (total_files, error_files)
+    if output::is_json() {
+        output::print_json(&output::batch_json(file_results));
+    }
+    (file_paths.len() as u32, error_files)
+}
+
+/// Returns the name of the file to display in the output. Shows the full relative path if needed
+fn get_file_name(path: &Path, show_full_path: bool) -> OsString {
+    match show_full_path {
+        true => path.as_os_str().to_os_string(),
+        false => path.file_name().unwrap_or(OsStr::new("<unknown file name>")).to_os_string()
+    }
 }
 
 pub fn handle_information(args: &ArgMatches) {
@@ -103,22 +143,115 @@ pub fn handle_information(args: &ArgMatches) {
             paths[0].clone()
         }
     };
-    
-    let options = options::InformationOptions {
-        show_unknown: args.get_flag("SHOW_UNKNOWN")
-    };
-    
-    let file_info = crate::information(&file_path, options);
-    match file_info {
-        Ok(info_lines) => {
-            log_success!("Displaying information about \"{}\":", file_path.display());
-            for line in info_lines {
-                println!(" - {}", line);
-            }
-        }
-        Err(err) => {
-            log_error!("Unable to get information about \"{}\"", file_path.display());
-            exits_on!(err; all);
+
+    let info = crate::information(&file_path).unwrap_or_else(|err| {
+        log_error!("Unable to get information about \"{}\"", file_path.display());
+        exits_on!(err; all);
+    });
+
+    if output::is_json() {
+        let time_format = args.get_one::<TimeFormat>("TIME_FORMAT").cloned().unwrap_or(TimeFormat::Rfc3339);
+        output::print_json(&get_information_json(&info, &time_format));
+        return
+    }
+
+    let time_format = args.get_one::<TimeFormat>("TIME_FORMAT").cloned().unwrap_or_default();
+    log_success!("Displaying information about \"{}\":", file_path.display());
+    for line in get_information_lines(&info, &time_format, args.get_flag("SHOW_UNKNOWN")) {
+        println!(" - {}", line);
+    }
+}
+
+/// Formats the boxfile information as lines of text, skipping the unknown metadata unless
+/// specified not to
+fn get_information_lines(info: &BoxfileInfo, time_format: &TimeFormat, show_unknown: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut push_optional = |label: &str, value: Option<String>| {
+        match value {
+            Some(value) => lines.push(format!("{}: {}", label, value)),
+            None if show_unknown => lines.push(format!("{}: Unknown", label)),
+            None => {}
         }
+    };
+
+    push_optional("Name", Some(format!("{:?}", info.name)));
+    push_optional("Extension", Some(info.extension.as_ref().map_or("None".to_string(), |ext| format!("{:?}", ext))));
+    push_optional("Create time", info.create_time.map(|time| time_format.format(time)));
+    push_optional("Modify time", info.modify_time.map(|time| time_format.format(time)));
+    push_optional("Access time", info.access_time.map(|time| time_format.format(time)));
+    push_optional("Permissions", info.permissions.map(|mode| format!("{:o}", mode & 0o7777)));
+    push_optional("Owner", info.owner.map(|(uid, gid)| format!("{}:{}", uid, gid)));
+    push_optional("Size", Some(format!(
+        "{} bytes (header {}, body {}, padding {})",
+        info.file_size, info.header_size, info.body_size, info.padding_len
+    )));
+    push_optional("Format version", Some(info.format_version.to_string()));
+    push_optional("Cipher", Some(to_name(&info.cipher)));
+    push_optional("Compression", Some(to_name(&info.compression)));
+    push_optional("Padding", Some(to_name(&info.padding)));
+    push_optional("Key fingerprint", info.key_fingerprint.clone());
+    lines
+}
+
+/// Formats the boxfile information as a JSON document with the times in the provided format
+fn get_information_json(info: &BoxfileInfo, time_format: &TimeFormat) -> serde_json::Value {
+    let mut json = serde_json::to_value(info).expect("Boxfile information is serializable");
+    for (key, time) in [
+        ("create_time", info.create_time),
+        ("modify_time", info.modify_time),
+        ("access_time", info.access_time),
+    ] {
+        json[key] = time.map(|time| time_format.format_json(time)).into();
+    }
+    json
+}
+
+/// Returns the name of the option value as used in the config
+fn to_name(value: &impl serde::Serialize) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => "unknown".to_string()
     }
-}
\ No newline at end of file
+}
+
+pub fn handle_find(args: &ArgMatches) {
+    let pattern = args.get_one::<String>("PATTERN").expect("Pattern is required");
+    let search_paths = handlers::get_path_vec(args, "PATH").unwrap_or_else(|| vec![PathBuf::from(".")]);
+    let options = options::FindOptions {
+        regex: args.get_flag("REGEX"),
+        ignore_case: args.get_flag("IGNORE_CASE"),
+    };
+
+    let found = crate::find(pattern, &search_paths, options).unwrap_or_else(|err| {
+        log_error!("Unable to search for \"{}\"", pattern);
+        exits_on!(err; all);
+    });
+
+    if output::is_json() {
+        let boxfiles = found.iter()
+            .map(|boxfile| serde_json::json!({
+                "path": output::path_json(&boxfile.path),
+                "original_name": boxfile.header.original_name().to_string_lossy(),
+                "modify_time": boxfile.header.modify_time.map(output::time_json),
+            }))
+            .collect::<Vec<_>>();
+        output::print_json(&serde_json::json!({ "pattern": pattern, "boxfiles": boxfiles }));
+        return
+    }
+    if found.is_empty() {
+        log_error!("No boxfiles matching \"{}\" found", pattern);
+        std::process::exit(1);
+    }
+    log_success!("Found {} boxfiles matching \"{}\":", found.len(), pattern);
+    for boxfile in found {
+        let modify_time = boxfile.header.modify_time
+            .map(format::format_time)
+            .unwrap_or_else(|| "unknown".to_string());
+        println!(
+            "\t- {} ({:?}, modified {})",
+            boxfile.path.display(),
+            boxfile.header.original_name(),
+            modify_time
+        );
+    }
+}
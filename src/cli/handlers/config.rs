@@ -0,0 +1,97 @@
+//! Contains handlers for the config subcommand
+
+use clap::ArgMatches;
+use serde_json::json;
+use crate::cli::output;
+use crate::{exits_on, log_error, log_success, log_warn};
+
+pub fn handle_config_get(args: &ArgMatches) {
+    let key = args.get_one::<String>("KEY").expect("Setting key is required");
+
+    match crate::get_config_value(key) {
+        Ok(value) if output::is_json() => output::print_json(&json!({ "key": key, "value": value })),
+        Ok(Some(value)) => println!("{}", value),
+        Ok(None) => log_warn!("Setting \"{}\" has no value", key),
+        Err(err) => {
+            log_error!("Unable to get the value of \"{}\"", key);
+            exits_on!(err; all);
+        }
+    }
+}
+
+pub fn handle_config_set(args: &ArgMatches) {
+    let key = args.get_one::<String>("KEY").expect("Setting key is required");
+    let value = args.get_one::<String>("VALUE").expect("Setting value is required");
+
+    match crate::set_config_value(key, value) {
+        Ok(_) => {
+            log_success!("Successfully set \"{}\" to {}", key, value);
+            output::print_if_json(json!({ "key": key, "value": value }));
+        },
+        Err(err) => {
+            log_error!("Unable to set \"{}\"", key);
+            exits_on!(err; all);
+        }
+    }
+}
+
+pub fn handle_config_list(args: &ArgMatches) {
+    let show_origin = args.get_flag("ORIGIN");
+    let settings = crate::list_config().unwrap_or_else(|err| {
+        log_error!("Unable to list the settings");
+        exits_on!(err; all);
+    });
+
+    if output::is_json() {
+        let settings = settings.iter()
+            .map(|setting| json!({
+                "key": setting.key,
+                "value": setting.value,
+                "origin": setting.origin.to_string(),
+            }))
+            .collect::<Vec<_>>();
+        output::print_json(&json!({ "settings": settings }));
+        return
+    }
+
+    log_success!("Current settings:");
+    for setting in settings {
+        let origin = match show_origin {
+            true => format!(" ({})", setting.origin),
+            false => String::new()
+        };
+        match setting.value {
+            Some(value) => println!("\t{} = {}{}", setting.key, value, origin),
+            None => println!("\t{} (not set){}", setting.key, origin)
+        }
+    }
+}
+
+pub fn handle_config_reset(args: &ArgMatches) {
+    let key = args.get_one::<String>("KEY").map(String::as_str);
+
+    match crate::reset_config(key) {
+        Ok(_) => {
+            match key {
+                Some(key) => log_success!("Successfully reset \"{}\" to the default value", key),
+                None => log_success!("Successfully reset the config to the default values")
+            }
+            output::print_if_json(json!({ "reset": key }));
+        },
+        Err(err) => {
+            log_error!("Unable to reset the config");
+            exits_on!(err; all);
+        }
+    }
+}
+
+pub fn handle_config_path(_args: &ArgMatches) {
+    match crate::get_config_path() {
+        Ok(path) if output::is_json() => output::print_json(&json!({ "path": output::path_json(&path) })),
+        Ok(path) => println!("{}", path.display()),
+        Err(err) => {
+            log_error!("Unable to get the path to the config file");
+            exits_on!(err; all);
+        }
+    }
+}
@@ -1,17 +1,18 @@
 //! Contains handlers for the key subcommand
 
 use clap::ArgMatches;
-use crate::cli::prompts;
+use serde_json::json;
+use crate::cli::{output, password};
 use crate::{exits_on, log_error, log_success, options};
 
 pub fn handle_key_new(args: &ArgMatches) {
-    let password = match args.get_one::<String>("PASSWORD") {
-        None => prompts::prompt_password("Please enter the password for the current profile:"),
-        Some(password) => password.to_string()
-    };
+    let password = password::get_password(args, "Please enter the password for the current profile:");
 
     match crate::new_key(&password) {
-        Ok(_) => log_success!("Successfully generated new encryption key for the current profile"),
+        Ok(_) => {
+            log_success!("Successfully generated new encryption key for the current profile");
+            output::print_if_json(json!({ "generated": true }));
+        },
         Err(err) => {
             log_error!("Unable to generate a new encryption key");
             exits_on!(err; all);
@@ -20,19 +21,17 @@ pub fn handle_key_new(args: &ArgMatches) {
 }
 
 pub fn handle_key_get(args: &ArgMatches) {
-    let password = match args.get_one::<String>("PASSWORD") {
-        None => prompts::prompt_password("Please enter the password for the current profile:"),
-        Some(password) => password.to_string()
-    };
+    let password = password::get_password(args, "Please enter the password for the current profile:");
 
     let options = options::KeyGetOptions {
-        as_byte_array: args.get_flag("BYTE-FORMAT"),
+        as_byte_array: args.get_flag("AS_BYTE_ARRAY"),
     };
 
     match crate::get_key(&password, options) {
         Ok(key) => {
             // TODO: add current profile name
             log_success!("Encryption key for the current profile:\n    {}", key);
+            output::print_if_json(json!({ "key": key }));
         }
         Err(err) => {
             log_error!("Unable to get an encryption key for the current profile");
@@ -42,15 +41,15 @@ pub fn handle_key_get(args: &ArgMatches) {
 }
 
 pub fn handle_key_set(args: &ArgMatches) {
-	let password = match args.get_one::<String>("PASSWORD") {
-        None => prompts::prompt_password("Please enter the password for the current profile:"),
-        Some(password) => password.to_string()
-    };
+	let password = password::get_password(args, "Please enter the password for the current profile:");
 
 	let new_key = args.get_one::<String>("KEY").expect("Key is required");
 
-    match crate::set_key(&new_key, &password) {
-        Ok(_) => log_success!("Successfully set a new encryption key for the current profile"),
+    match crate::set_key(new_key, &password) {
+        Ok(_) => {
+            log_success!("Successfully set a new encryption key for the current profile");
+            output::print_if_json(json!({ "set": true }));
+        },
         Err(err) => {
             log_error!("Unable to set an encryption key for the current profile");
             exits_on!(err; all);
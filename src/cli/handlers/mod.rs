@@ -4,20 +4,32 @@
 use clap::ArgMatches;
 use std::collections::VecDeque;
 use std::path::PathBuf;
+use crate::cli::password;
+use crate::core::data::{self, config::DataboxerConfig};
+use crate::{exits_on, log_error, Session};
+use crate::options::{ConflictPolicy, DeletePolicy};
 
 mod base;
+mod agent;
 mod profile;
 mod key;
+mod config;
+mod store;
+mod tracked;
 
 pub use base::*;
+pub use agent::*;
 pub use profile::*;
 pub use key::*;
+pub use config::*;
+pub use store::*;
+pub use tracked::*;
 
 /// Converts from the passed arguments strings to vector of paths
 pub fn get_path_vec(args: &ArgMatches, arg_id: &str) -> Option<Vec<PathBuf>> {
     if let Some(strings) = args.get_many::<String>(arg_id) {
         return Some(strings
-            .map(|s| PathBuf::from(s))
+            .map(PathBuf::from)
             .collect::<Vec<PathBuf>>()
         )
     }
@@ -36,4 +48,86 @@ pub fn get_path_deque(args: &ArgMatches, arg_id: &str) -> Option<VecDeque<PathBu
         return Some(deque)
     }
     None
-}
\ No newline at end of file
+}
+
+/// Fetches the Databoxer config used for the default values of the flags. Exits if the config
+/// cannot be loaded
+pub fn get_config() -> DataboxerConfig {
+    data::get_config().unwrap_or_else(|err| {
+        log_error!("Unable to load the config");
+        exits_on!(err; all);
+    })
+}
+
+/// Returns the delete policy selected with the passed flags. Falls back to the default policy from
+/// the config if none of the flags were passed
+pub fn get_delete_policy(args: &ArgMatches, config: &DataboxerConfig) -> DeletePolicy {
+    if args.get_flag("KEEP_ORIGINAL") {
+        DeletePolicy::Keep
+    } else if args.get_flag("SECURE_DELETE") {
+        DeletePolicy::Secure
+    } else if args.get_flag("VERIFY") {
+        DeletePolicy::Verify
+    } else {
+        config.storage.delete_policy
+    }
+}
+
+/// Returns the conflict policy selected with the passed flags. Falls back to the default policy
+/// from the config if none of the flags were passed
+pub fn get_conflict_policy(args: &ArgMatches, config: &DataboxerConfig) -> ConflictPolicy {
+    if args.get_flag("OVERWRITE") {
+        return ConflictPolicy::Overwrite
+    }
+
+    match args.get_one::<String>("CONFLICT").map(|s| s.as_str()) {
+        Some("ask") => ConflictPolicy::Ask,
+        Some("overwrite") => ConflictPolicy::Overwrite,
+        Some("skip") => ConflictPolicy::Skip,
+        Some("rename-with-suffix") => ConflictPolicy::RenameWithSuffix,
+        _ => config.storage.conflict_policy
+    }
+}
+
+/// Returns the value of a setting which is turned on with one flag and off with the other, or
+/// `None` if neither was passed and the config value should be used. Only the last of the two
+/// flags is kept by the parser
+pub fn get_switch(args: &ArgMatches, on_arg_id: &str, off_arg_id: &str) -> Option<bool> {
+    if args.get_flag(on_arg_id) {
+        Some(true)
+    } else if args.get_flag(off_arg_id) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Returns the session used to encrypt and decrypt files. Uses the key agent if its socket is set
+/// in the environment and no password was passed with the flags, falling back to unlocking the
+/// current profile with the password. Exits if the profile cannot be unlocked
+pub fn get_session(args: &ArgMatches) -> Session {
+    #[cfg(unix)]
+    if !password::is_password_passed(args) {
+        if let Some(socket_path) = get_agent_socket() {
+            match crate::connect_agent(&socket_path) {
+                Ok(session) => return session,
+                Err(err) => crate::log_warn!("{}. Falling back to the password", err)
+            }
+        }
+    }
+
+    let password = password::get_password(args, "Please enter the password for the current profile:");
+
+    crate::unlock_current(&password).unwrap_or_else(|err| {
+        log_error!("Unable to unlock the current profile");
+        exits_on!(err; all);
+    })
+}
+
+/// Returns the agent socket set in the environment, if any
+#[cfg(unix)]
+pub fn get_agent_socket() -> Option<PathBuf> {
+    std::env::var_os(crate::core::agent::AGENT_SOCKET_VAR)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
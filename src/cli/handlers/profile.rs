@@ -1,19 +1,23 @@
 //! Contains handlers for the profile subcommand
 
 use clap::ArgMatches;
-use crate::cli::prompts;
-use crate::{exits_on, log_error, log_success, log_warn};
+use serde_json::json;
+use crate::cli::{format, output, password};
+use crate::{exits_on, log_error, log_success, log_warn, options, ProfileInfo};
 
 pub fn handle_profile_create(args: &ArgMatches) {
-    let password = match args.get_one::<String>("PASSWORD") {
-        None => prompts::prompt_password("Please enter a password for the new profile:"),
-        Some(password) => password.to_string()
-    };
+    let password = password::get_new_password(args, "Please enter a password for the new profile:");
 
     let name = args.get_one::<String>("NAME").expect("Profile name is required");
+    let options = options::ProfileCreateOptions {
+        force_weak: args.get_flag("FORCE_WEAK"),
+    };
 
-    match crate::create_profile(name, &password) {
-        Ok(_) => log_success!("Successfully created new profile \"{}\"", name),
+    match crate::create_profile(name, &password, options) {
+        Ok(_) => {
+            log_success!("Successfully created new profile \"{}\"", name);
+            output::print_if_json(json!({ "created": name }));
+        },
         Err(err) => {
             log_error!("Unable to create a new profile named \"{}\"", name);
             exits_on!(err; all);
@@ -24,13 +28,13 @@ pub fn handle_profile_create(args: &ArgMatches) {
 pub fn handle_profile_delete(args: &ArgMatches) {
     let name = args.get_one::<String>("NAME").expect("Profile name is required");
 
-    let password = match args.get_one::<String>("PASSWORD") {
-        None => prompts::prompt_password(&format!("Please enter the password for {}", name)),
-        Some(password) => password.to_string()
-    };
+    let password = password::get_password(args, &format!("Please enter the password for {}", name));
 
     match crate::delete_profile(name, &password) {
-        Ok(_) => log_success!("Successfully deleted profile \"{}\"", name),
+        Ok(_) => {
+            log_success!("Successfully deleted profile \"{}\"", name);
+            output::print_if_json(json!({ "deleted": name }));
+        },
         Err(err) => {
             log_error!("Unable to delete profile \"{}\"", name);
             exits_on!(err; all);
@@ -41,13 +45,13 @@ pub fn handle_profile_delete(args: &ArgMatches) {
 pub fn handle_profile_set(args: &ArgMatches) {
     let name = args.get_one::<String>("NAME").expect("Profile name is required");
 
-    let password = match args.get_one::<String>("PASSWORD") {
-        None => prompts::prompt_password(&format!("Please enter the password for {}", name)),
-        Some(password) => password.to_string()
-    };
+    let password = password::get_password(args, &format!("Please enter the password for {}", name));
 
     match crate::select_profile(name, &password) {
-        Ok(_) => log_success!("Successfully set current profile to \"{}\"", name),
+        Ok(_) => {
+            log_success!("Successfully set current profile to \"{}\"", name);
+            output::print_if_json(json!({ "current_profile": name }));
+        },
         Err(err) => {
             log_error!("Unable to switch to profile \"{}\"", name);
             exits_on!(err; all);
@@ -57,7 +61,10 @@ pub fn handle_profile_set(args: &ArgMatches) {
 
 pub fn handle_profile_get(_args: &ArgMatches) {
     match crate::get_profile() {
-        Ok(name) => log_success!("Currently selected profile: {}", name),
+        Ok(name) => {
+            log_success!("Currently selected profile: {}", name);
+            output::print_if_json(json!({ "current_profile": name }));
+        },
         Err(err) => {
             log_error!("Unable to get currently selected profile");
             exits_on!(err; all);
@@ -65,6 +72,27 @@ pub fn handle_profile_get(_args: &ArgMatches) {
     }
 }
 
+pub fn handle_profile_show(args: &ArgMatches) {
+    let name = args.get_one::<String>("NAME").map(String::as_str);
+
+    match crate::show_profile(name) {
+        Ok(info) => {
+            if output::is_json() {
+                output::print_json(&get_profile_json(&info));
+                return
+            }
+            log_success!("Displaying information about the profile:");
+            for line in get_profile_lines(&info) {
+                println!(" - {}", line);
+            }
+        },
+        Err(err) => {
+            log_error!("Unable to get information about the profile");
+            exits_on!(err; all);
+        }
+    }
+}
+
 pub fn handle_profile_list(_args: &ArgMatches) {
     let profiles = crate::get_profiles();
 
@@ -73,6 +101,10 @@ pub fn handle_profile_list(_args: &ArgMatches) {
         exits_on!(err; all);
     });
     let count = profiles.len();
+    if output::is_json() {
+        output::print_json(&json!({ "profiles": profiles }));
+        return
+    }
 
     if count == 0 {
         log_warn!("No profiles found");
@@ -84,4 +116,44 @@ pub fn handle_profile_list(_args: &ArgMatches) {
             println!("\t- {}", name)
         }
     }
-}
\ No newline at end of file
+}
+
+/// Formats the profile information as lines of text
+fn get_profile_lines(info: &ProfileInfo) -> Vec<String> {
+    let mut lines = vec![
+        format!("Name: {}", info.name),
+        format!("Current: {}", if info.current { "yes" } else { "no" }),
+        format!("Failed attempts: {}", info.failed_attempts),
+    ];
+    if let Some(time) = info.last_failed_attempt {
+        lines.push(format!("Last failed attempt: {}", format::format_time(time)));
+    }
+    if let Some(wait) = info.locked_for {
+        lines.push(format!("Locked for: {} seconds", wait.as_secs() + 1));
+    }
+    lines
+}
+
+/// Formats the profile information as a JSON document
+fn get_profile_json(info: &ProfileInfo) -> serde_json::Value {
+    json!({
+        "name": info.name,
+        "current": info.current,
+        "failed_attempts": info.failed_attempts,
+        "last_failed_attempt": info.last_failed_attempt.map(output::time_json),
+        "locked_for": info.locked_for.map(|wait| wait.as_secs() + 1),
+    })
+}
+
+pub fn handle_migrate_store(_args: &ArgMatches) {
+    match crate::migrate_profile_store() {
+        Ok(count) => {
+            log_success!("Successfully migrated {} profile(s) to the SQLite store", count);
+            output::print_if_json(json!({ "migrated": count }));
+        },
+        Err(err) => {
+            log_error!("Unable to migrate the profile store");
+            exits_on!(err; all);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Contains handlers for the store subcommand
+
+use clap::ArgMatches;
+use serde_json::json;
+use crate::cli::{output, password};
+use crate::{exits_on, log_error, log_success, options};
+
+pub fn handle_store_lock(args: &ArgMatches) {
+    let master_password = password::get_new_master_password(args, "Please enter a master password for the profile store:");
+    let options = options::StoreLockOptions {
+        force_weak: args.get_flag("FORCE_WEAK"),
+    };
+
+    match crate::lock_profile_store(&master_password, options) {
+        Ok(_) => {
+            log_success!("Successfully sealed the profile store");
+            output::print_if_json(json!({ "sealed": true }));
+        },
+        Err(err) => {
+            log_error!("Unable to seal the profile store");
+            exits_on!(err; all);
+        }
+    }
+}
+
+pub fn handle_store_unlock(args: &ArgMatches) {
+    let master_password = password::get_master_password(args, "Please enter the master password of the profile store:");
+
+    match crate::unlock_profile_store(&master_password) {
+        Ok(_) => {
+            log_success!("Successfully unsealed the profile store");
+            output::print_if_json(json!({ "sealed": false }));
+        },
+        Err(err) => {
+            log_error!("Unable to unseal the profile store");
+            exits_on!(err; all);
+        }
+    }
+}
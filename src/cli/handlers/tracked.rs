@@ -0,0 +1,104 @@
+//! Contains handlers for the tracked subcommand
+
+use clap::ArgMatches;
+use serde_json::json;
+use crate::cli::{format, output};
+use crate::{exits_on, log_error, log_success, log_warn, TrackedFile};
+use super::get_path_vec;
+
+pub fn handle_tracked_list(_args: &ArgMatches) {
+    let tracked_files = crate::get_tracked_files().unwrap_or_else(|err| {
+        log_error!("Unable to get the tracked files");
+        exits_on!(err; all);
+    });
+
+    if output::is_json() {
+        output::print_json(&json!({ "tracked_files": get_tracked_json(&tracked_files) }));
+        return
+    }
+    if tracked_files.is_empty() {
+        log_warn!("No tracked files found");
+    } else {
+        log_success!("Tracked files of the current profile ({}):", tracked_files.len());
+        print_tracked_files(&tracked_files);
+    }
+}
+
+pub fn handle_tracked_find(args: &ArgMatches) {
+    let name = args.get_one::<String>("NAME").expect("Original name is required");
+
+    let tracked_files = crate::find_tracked_files(name).unwrap_or_else(|err| {
+        log_error!("Unable to search the tracked files");
+        exits_on!(err; all);
+    });
+
+    if output::is_json() {
+        output::print_json(&json!({ "tracked_files": get_tracked_json(&tracked_files) }));
+        return
+    }
+    if tracked_files.is_empty() {
+        log_error!("No tracked files encrypted from \"{}\" found", name);
+        std::process::exit(1);
+    }
+    log_success!("Found {} tracked files encrypted from \"{}\":", tracked_files.len(), name);
+    print_tracked_files(&tracked_files);
+}
+
+pub fn handle_tracked_prune(args: &ArgMatches) {
+    let scan_paths = get_path_vec(args, "SCAN").unwrap_or_default();
+
+    match crate::prune_tracked_files(&scan_paths) {
+        Ok(reconciliation) if output::is_json() => output::print_json(&json!({
+            "moved": get_tracked_json(&reconciliation.moved),
+            "removed": get_tracked_json(&reconciliation.removed),
+        })),
+        Ok(reconciliation) => {
+            for file in &reconciliation.moved {
+                println!("\t- moved: {} -> {}", file.original_name, file.path.display());
+            }
+            for file in &reconciliation.removed {
+                println!("\t- removed: {} ({})", file.original_name, file.path.display());
+            }
+            log_success!(
+                "Updated {} moved and removed {} missing tracked files",
+                reconciliation.moved.len(),
+                reconciliation.removed.len()
+            );
+        },
+        Err(err) => {
+            log_error!("Unable to prune the tracked files");
+            exits_on!(err; all);
+        }
+    }
+}
+
+/// Prints the tracked files one per line, marking the ones missing from their recorded paths
+fn print_tracked_files(tracked_files: &[TrackedFile]) {
+    for file in tracked_files {
+        let missing = if file.exists() { "" } else { " [missing]" };
+        println!(
+            "\t- {} -> {} ({} bytes, encrypted {}, key {}){}",
+            file.original_name,
+            file.path.display(),
+            file.size,
+            format::format_time(file.encrypt_time),
+            file.key_id,
+            missing
+        );
+    }
+}
+
+/// Formats the tracked files as JSON objects, marking the ones missing from their recorded paths
+fn get_tracked_json(tracked_files: &[TrackedFile]) -> Vec<serde_json::Value> {
+    tracked_files.iter()
+        .map(|file| json!({
+            "id": file.id,
+            "path": output::path_json(&file.path),
+            "original_name": file.original_name,
+            "size": file.size,
+            "encrypt_time": output::time_json(file.encrypt_time),
+            "key_id": file.key_id,
+            "exists": file.exists(),
+        }))
+        .collect()
+}
@@ -4,12 +4,15 @@ use std::fmt;
 use std::sync::{Arc, Mutex};
 use clap::ArgMatches;
 use lazy_static::lazy_static;
-use crate::core::logs::LogType;
+use crate::cli::output;
+use crate::core::data;
+use crate::core::logs::{LogLevel, LogType};
 
 lazy_static! {
     pub static ref LOGGER: Arc<Mutex<Logger>> = Arc::new(Mutex::new(Logger::new()));
 }
 
+#[allow(clippy::upper_case_acronyms)]
 enum LoggerMode {
     QUIET,
     NORMAL,
@@ -21,6 +24,12 @@ pub struct Logger {
     mode: LoggerMode
 }
 
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Logger {
     pub fn new() -> Self {
         Logger {
@@ -34,42 +43,52 @@ impl Logger {
             if !self.debug {
                 return;
             }
-            println!("[{}] {}", log_type.icon(), message);
+            print_log(log_type, message);
             return;
         }
 
         match self.mode {
-            LoggerMode::QUIET => {
-                return;
-            },
+            LoggerMode::QUIET => {},
             LoggerMode::NORMAL => {
-                if log_type == LogType::ERROR || log_type == LogType::WARN {
-                    eprintln!("[{}] {}", log_type.icon(), message);
-                } else if log_type == LogType::SUCCESS || log_type == LogType::STATUS {
-                    println!("[{}] {}", log_type.icon(), message);
-                }
-            },
-            LoggerMode::VERBOSE => {
-                if log_type == LogType::ERROR || log_type == LogType::WARN {
-                    eprintln!("[{}] {}", log_type.icon(), message);
-                } else {
-                    println!("[{}] {}", log_type.icon(), message);
+                if log_type != LogType::INFO {
+                    print_log(log_type, message);
                 }
             },
+            LoggerMode::VERBOSE => print_log(log_type, message),
         }
     }
 }
 
+/// Prints the log message. Errors and warnings go to the standard error, as does everything else
+/// in the JSON output mode, so the standard output only contains the JSON document
+fn print_log(log_type: LogType, message: fmt::Arguments<'_>) {
+    if output::is_json() || log_type == LogType::ERROR || log_type == LogType::WARN {
+        eprintln!("[{}] {}", log_type.icon(), message);
+    } else {
+        println!("[{}] {}", log_type.icon(), message);
+    }
+}
+
+/// Configures the logger with the passed flags, falling back to the log level from the config. An
+/// invalid config is ignored here, as it is reported once the command loads it
 pub fn configure_logger(args: &ArgMatches) {
+    let log_level = data::get_config()
+        .map(|config| config.general.log_level)
+        .unwrap_or_default();
+
     let mut logger = LOGGER.lock().unwrap();
-    logger.debug = args.get_flag("DEBUG");
+    logger.debug = args.get_flag("DEBUG") || log_level == LogLevel::Debug;
     logger.mode = {
         if args.get_flag("QUIET") {
             LoggerMode::QUIET
         } else if args.get_flag("VERBOSE") {
             LoggerMode::VERBOSE
         } else {
-            LoggerMode::NORMAL
+            match log_level {
+                LogLevel::Quiet => LoggerMode::QUIET,
+                LogLevel::Normal => LoggerMode::NORMAL,
+                LogLevel::Verbose | LogLevel::Debug => LoggerMode::VERBOSE,
+            }
         }
     };
 }
\ No newline at end of file
@@ -1,8 +1,8 @@
 //! CLI entry point
 
-use std::{io, time::Instant};
+use std::{io, path::Path, time::Instant};
 use databoxer::app::AppMode;
-use databoxer::cli::{logger, command, handlers};
+use databoxer::cli::{logger, command, handlers, output};
 
 fn main() -> io::Result<()> {
     databoxer::app::set_app_mode(AppMode::CLI);
@@ -10,13 +10,22 @@ fn main() -> io::Result<()> {
     let start_time = Instant::now();
     let global_args = &command::get_command().get_matches();
 
-    logger::configure_logger(&global_args);
+    if let Some(config_file) = global_args.get_one::<String>("CONFIG") {
+        databoxer::set_config_file(Path::new(config_file));
+    }
+    if let Some(data_dir) = global_args.get_one::<String>("DATA_DIR") {
+        databoxer::set_data_dir(Path::new(data_dir));
+    }
+    output::configure_output(global_args);
+    logger::configure_logger(global_args);
 
     /* BOX */
     if let Some(args) = global_args.subcommand_matches("box") {
         let (total, error) = handlers::handle_box(args);
 
-        println!("[{}/{}] files encrypted", total - error, total);
+        if !output::is_json() {
+            println!("[{}/{}] files encrypted", total - error, total);
+        }
         if total == error {
             std::process::exit(1);
         }
@@ -26,7 +35,9 @@ fn main() -> io::Result<()> {
     if let Some(args) = global_args.subcommand_matches("unbox") {
         let (total, error) = handlers::handle_unbox(args);
 
-        println!("[{}/{}] files decrypted", total - error, total);
+        if !output::is_json() {
+            println!("[{}/{}] files decrypted", total - error, total);
+        }
         if total == error {
             std::process::exit(1);
         }
@@ -37,6 +48,16 @@ fn main() -> io::Result<()> {
         handlers::handle_information(args);
     }
     
+    /* FIND */
+    if let Some(args) = global_args.subcommand_matches("find") {
+        handlers::handle_find(args);
+    }
+
+    /* AGENT */
+    if let Some(args) = global_args.subcommand_matches("agent") {
+        handlers::handle_agent(args);
+    }
+
     /* PROFILE */
     if let Some(args) = global_args.subcommand_matches("profile") {
         /* PROFILE CREATE */
@@ -55,12 +76,49 @@ fn main() -> io::Result<()> {
         if let Some(args) = args.subcommand_matches("get") {
             handlers::handle_profile_get(args);
         }
+        /* PROFILE SHOW */
+        if let Some(args) = args.subcommand_matches("show") {
+            handlers::handle_profile_show(args);
+        }
         /* PROFILE LIST */
         if let Some(args) = args.subcommand_matches("list") {
             handlers::handle_profile_list(args);
         }
     }
 
+    /* MIGRATE STORE */
+    if let Some(args) = global_args.subcommand_matches("migrate-store") {
+        handlers::handle_migrate_store(args);
+    }
+
+    /* STORE */
+    if let Some(args) = global_args.subcommand_matches("store") {
+        /* STORE LOCK */
+        if let Some(args) = args.subcommand_matches("lock") {
+            handlers::handle_store_lock(args);
+        }
+        /* STORE UNLOCK */
+        if let Some(args) = args.subcommand_matches("unlock") {
+            handlers::handle_store_unlock(args);
+        }
+    }
+
+    /* TRACKED */
+    if let Some(args) = global_args.subcommand_matches("tracked") {
+        /* TRACKED LIST */
+        if let Some(args) = args.subcommand_matches("list") {
+            handlers::handle_tracked_list(args);
+        }
+        /* TRACKED FIND */
+        if let Some(args) = args.subcommand_matches("find") {
+            handlers::handle_tracked_find(args);
+        }
+        /* TRACKED PRUNE */
+        if let Some(args) = args.subcommand_matches("prune") {
+            handlers::handle_tracked_prune(args);
+        }
+    }
+
     /* KEY */
     if let Some(args) = global_args.subcommand_matches("key") {
         /* KEY NEW */
@@ -77,7 +135,33 @@ fn main() -> io::Result<()> {
         }
     }
 
+    /* CONFIG */
+    if let Some(args) = global_args.subcommand_matches("config") {
+        /* CONFIG GET */
+        if let Some(args) = args.subcommand_matches("get") {
+            handlers::handle_config_get(args);
+        }
+        /* CONFIG SET */
+        if let Some(args) = args.subcommand_matches("set") {
+            handlers::handle_config_set(args);
+        }
+        /* CONFIG LIST */
+        if let Some(args) = args.subcommand_matches("list") {
+            handlers::handle_config_list(args);
+        }
+        /* CONFIG RESET */
+        if let Some(args) = args.subcommand_matches("reset") {
+            handlers::handle_config_reset(args);
+        }
+        /* CONFIG PATH */
+        if let Some(args) = args.subcommand_matches("path") {
+            handlers::handle_config_path(args);
+        }
+    }
+
     let duration = start_time.elapsed();
-    println!("Time taken: {:.2?}", duration);
+    if !output::is_json() {
+        println!("Time taken: {:.2?}", duration);
+    }
     Ok(())
 }
\ No newline at end of file
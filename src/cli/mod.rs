@@ -3,4 +3,8 @@
 pub mod logger;
 pub mod prompts;
 pub mod handlers;
-pub mod command;
\ No newline at end of file
+pub mod command;
+pub mod password;
+pub mod format;
+
+pub mod output;
@@ -0,0 +1,84 @@
+//! Contains the machine-readable output mode of the CLI
+//!
+//! In the JSON mode every command prints a single JSON document to the standard output, while the
+//! log messages and prompts are moved to the standard error. Fatal errors are printed as an
+//! `{"error": {...}}` document before exiting
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+use clap::ArgMatches;
+use serde_json::{json, Value};
+use crate::cli::format::TimeFormat;
+use crate::{BatchResult, Error};
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Configures the output mode with the passed flags
+pub fn configure_output(args: &ArgMatches) {
+    JSON_OUTPUT.store(args.get_flag("JSON"), Ordering::Relaxed);
+}
+
+/// Returns whether the commands should print a JSON document instead of text
+pub fn is_json() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// Prints the document of the command to the standard output
+pub fn print_json(document: &Value) {
+    println!("{}", serde_json::to_string_pretty(document).expect("JSON value is serializable"));
+}
+
+/// Prints the document of the command if the JSON output mode is on. Used by the commands which
+/// only log a message in the text mode
+pub fn print_if_json(document: Value) {
+    if is_json() {
+        print_json(&document);
+    }
+}
+
+/// Formats the error as a JSON object with its kind and message
+pub fn error_json(err: &Error) -> Value {
+    json!({
+        "kind": err.kind_name(),
+        "message": err.to_string(),
+    })
+}
+
+/// Formats the path as a JSON string, replacing invalid UTF-8
+pub fn path_json(path: &Path) -> Value {
+    path.to_string_lossy().into()
+}
+
+/// Formats the time as an RFC 3339 JSON string
+pub fn time_json(time: SystemTime) -> Value {
+    TimeFormat::Rfc3339.format_json(time)
+}
+
+/// Formats the result of a single file of a batch as a JSON object with its status: `success`,
+/// `skipped` or `failed`
+pub fn batch_result_json(result: &BatchResult) -> Value {
+    let (status, output, error) = match &result.output {
+        Ok(Some(path)) => ("success", path_json(path), Value::Null),
+        Ok(None) => ("skipped", Value::Null, Value::Null),
+        Err(err) => ("failed", Value::Null, error_json(err)),
+    };
+    json!({
+        "input": path_json(&result.input_path),
+        "output": output,
+        "status": status,
+        "error": error,
+    })
+}
+
+/// Formats the results of a batch as a JSON document with the number of files in each status
+pub fn batch_json(files: Vec<Value>) -> Value {
+    let count = |status: &str| files.iter().filter(|file| file["status"] == status).count();
+    json!({
+        "total": files.len(),
+        "succeeded": count("success"),
+        "skipped": count("skipped"),
+        "failed": count("failed"),
+        "files": files,
+    })
+}
@@ -0,0 +1,119 @@
+//! Contains logic for getting the profile password from all the supported sources. Every handler
+//! which needs a password uses the same order of precedence:
+//!
+//! 1. `--password`, `--password-file` or `--password-fd` flag (only one of them can be passed)
+//! 2. `DATABOXER_PASSWORD` environment variable
+//! 3. Interactive prompt, which hides the input when reading from a terminal
+//!
+//! The master password of the profile store is taken the same way, but from the
+//! `DATABOXER_MASTER_PASSWORD` environment variable
+
+use clap::ArgMatches;
+use std::fs;
+use zeroize::Zeroizing;
+use crate::cli::prompts;
+use crate::core::data::MASTER_PASSWORD_VAR;
+use crate::{exits_on, log_error, Result, Secret};
+
+/// Name of the environment variable which can hold the profile password
+pub const PASSWORD_VAR: &str = "DATABOXER_PASSWORD";
+
+/// Returns the password from the first available source. Prompts with the provided message if no
+/// password was supplied. Exits if the password cannot be read
+pub fn get_password(args: &ArgMatches, msg: &str) -> Secret<String> {
+    get_supplied_password(args, PASSWORD_VAR)
+        .unwrap_or_else(|| prompts::prompt_password(msg))
+}
+
+/// Returns the password for a new profile from the first available source. The password has to be
+/// entered twice if it is prompted for. Exits if the password cannot be read or doesn't match
+pub fn get_new_password(args: &ArgMatches, msg: &str) -> Secret<String> {
+    confirm_password(get_supplied_password(args, PASSWORD_VAR), msg)
+}
+
+/// Works like `get_password` for the master password of the profile store
+pub fn get_master_password(args: &ArgMatches, msg: &str) -> Secret<String> {
+    get_supplied_password(args, MASTER_PASSWORD_VAR)
+        .unwrap_or_else(|| prompts::prompt_password(msg))
+}
+
+/// Works like `get_new_password` for the master password of the profile store
+pub fn get_new_master_password(args: &ArgMatches, msg: &str) -> Secret<String> {
+    confirm_password(get_supplied_password(args, MASTER_PASSWORD_VAR), msg)
+}
+
+/// Returns the supplied password or prompts for it twice. Exits if the passwords don't match
+fn confirm_password(supplied: Option<Secret<String>>, msg: &str) -> Secret<String> {
+    if let Some(password) = supplied {
+        return password
+    }
+
+    let password = prompts::prompt_password(msg);
+    let confirmation = prompts::prompt_password("Please enter the password again to confirm:");
+    if *password != *confirmation {
+        log_error!("Passwords do not match");
+        std::process::exit(1);
+    }
+    password
+}
+
+/// Returns whether the password was explicitly passed with one of the flags
+pub fn is_password_passed(args: &ArgMatches) -> bool {
+    ["PASSWORD", "PASSWORD_FILE", "PASSWORD_FD"].iter()
+        .any(|id| args.contains_id(id))
+}
+
+/// Returns the password from the flags or the provided environment variable, without prompting.
+/// Exits if the password file or descriptor cannot be read
+fn get_supplied_password(args: &ArgMatches, env_var: &str) -> Option<Secret<String>> {
+    let password = if let Some(password) = args.get_one::<String>("PASSWORD") {
+        Ok(Secret::new(password.to_string()))
+    } else if let Some(path) = args.get_one::<String>("PASSWORD_FILE") {
+        fs::read_to_string(path)
+            .map(|contents| first_line(&Zeroizing::new(contents)))
+            .map_err(|err| err.into())
+    } else if let Some(fd) = args.get_one::<i32>("PASSWORD_FD") {
+        read_fd(*fd)
+    } else {
+        return std::env::var(env_var).ok().map(|password| first_line(&Zeroizing::new(password)))
+    };
+
+    Some(password.unwrap_or_else(|err| {
+        log_error!("Unable to read the password");
+        exits_on!(err; all);
+    }))
+}
+
+/// Reads the password from the first line of the already open file descriptor. The descriptor is
+/// duplicated, so the original one stays open. Descriptor 0 reads the standard input, while the
+/// standard output and error are rejected
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<Secret<String>> {
+    use std::io::Read;
+    use std::os::fd::BorrowedFd;
+
+    if fd == 1 || fd == 2 || fd < 0 {
+        return Err(crate::new_err!(InvalidInput: InvalidFile, format!("Password cannot be read from descriptor {}", fd)))
+    }
+    // SAFETY: fcntl only queries the flags of the descriptor and fails if it isn't open
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(crate::new_err!(InvalidInput: InvalidFile, format!("Descriptor {} is not open", fd)))
+    }
+
+    // SAFETY: the descriptor was checked to be open and is only borrowed until it's duplicated
+    let borrowed_fd = unsafe { BorrowedFd::borrow_raw(fd) };
+    let mut file = fs::File::from(borrowed_fd.try_clone_to_owned()?);
+    let mut contents = Zeroizing::new(String::new());
+    file.read_to_string(&mut contents)?;
+    Ok(first_line(&contents))
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<Secret<String>> {
+    Err(crate::new_err!(InvalidInput: InvalidFile, "Password descriptors are only supported on Unix systems"))
+}
+
+/// Returns the first line of the provided text without the line ending
+fn first_line(text: &str) -> Secret<String> {
+    Secret::new(text.lines().next().unwrap_or_default().to_string())
+}
@@ -1,9 +1,46 @@
-use std::io;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use zeroize::Zeroize;
+use crate::cli::output;
+use crate::options::ConflictPolicy;
+use crate::Secret;
 
-// TODO: hide user input
-pub fn prompt_password(msg: &str) -> String {
-    println!("{}", msg);
-    get_input().unwrap_or_else(|err| panic!("Unable to prompt password: {}", err)).to_string()
+/// Asks the user for a password. The input is hidden when reading from a terminal, otherwise the
+/// password is read from the first line of the standard input
+pub fn prompt_password(msg: &str) -> Secret<String> {
+    if !io::stdin().is_terminal() {
+        print_prompt(format_args!("{}", msg));
+        return Secret::new(get_input().unwrap_or_else(|err| panic!("Unable to prompt password: {}", err)))
+    }
+
+    let password = rpassword::prompt_password(format!("{} ", msg))
+        .unwrap_or_else(|err| panic!("Unable to prompt password: {}", err));
+    Secret::new(password)
+}
+
+/// Asks the user what to do with an already existing output file until a valid answer is given
+pub fn prompt_conflict(path: &Path) -> ConflictPolicy {
+    loop {
+        print_prompt(format_args!("File \"{}\" already exists. [o]verwrite, [s]kip or [r]ename?", path.display()));
+        let input = get_input().unwrap_or_else(|err| panic!("Unable to prompt for input: {}", err));
+
+        match input.to_lowercase().as_str() {
+            "o" | "overwrite" => return ConflictPolicy::Overwrite,
+            "s" | "skip" => return ConflictPolicy::Skip,
+            "r" | "rename" => return ConflictPolicy::RenameWithSuffix,
+            _ => print_prompt(format_args!("Invalid option \"{}\"", input))
+        }
+    }
+}
+
+/// Prints the prompt message. Goes to the standard error in the JSON output mode, so it doesn't
+/// mix with the JSON document
+fn print_prompt(message: fmt::Arguments<'_>) {
+    match output::is_json() {
+        true => eprintln!("{}", message),
+        false => println!("{}", message)
+    }
 }
 
 fn get_input() -> io::Result<String> {
@@ -11,5 +48,6 @@ fn get_input() -> io::Result<String> {
     io::stdin().read_line(&mut buffer)?;
 
     let input = buffer.trim_end().to_string();
+    buffer.zeroize();
     Ok(input)
 }
\ No newline at end of file
@@ -0,0 +1,307 @@
+//! Contains the key agent, which runs in the foreground, keeps an unlocked profile in memory and
+//! serves key operations to other Databoxer processes over a Unix domain socket. The profile key
+//! itself never leaves the agent, as clients can only ask it to wrap and unwrap the data keys of
+//! boxfiles
+//!
+//! Every request is sent over a new connection as a single bincode-serialized message, which is
+//! answered with a single response. Each connection is handled on its own thread and only
+//! processes of the same user are served. The agent exits once it has been idle for the configured
+//! time or when asked to stop
+
+use std::io::{self, ErrorKind};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use std::{fs, thread};
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+use crate::core::data::os;
+use crate::core::encryption::cipher::KeyWrapper;
+use crate::core::encryption::secret::Secret;
+use crate::core::session::Session;
+use crate::{log_debug, log_info, log_warn, new_err, Key, Result};
+
+/// Name of the environment variable which holds the path to the agent socket
+pub const AGENT_SOCKET_VAR: &str = "DATABOXER_AGENT_SOCK";
+/// Name of the default agent socket file in the program's data directory
+const AGENT_SOCKET_NAME: &str = "agent.sock";
+/// How long to wait between checks for new connections
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How long to wait for a connected client to send or receive a message
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum accepted length of a serialized message, which is far more than any request or
+/// response needs
+const MAX_MESSAGE_LEN: u64 = 4096;
+
+/// Request sent by a client to the agent. Doesn't implement `Debug`, as it may hold a data key
+#[derive(Serialize, Deserialize)]
+enum AgentRequest {
+    /// Returns the name of the unlocked profile
+    Status,
+    /// Wraps the provided data key with the profile key
+    WrapKey(Key),
+    /// Unwraps the provided wrapped data key with the profile key
+    UnwrapKey(Vec<u8>),
+    /// Stops the agent
+    Stop,
+}
+
+/// Response sent back by the agent. Doesn't implement `Debug`, as it may hold a data key
+#[derive(Serialize, Deserialize)]
+enum AgentResponse {
+    Status(String),
+    WrappedKey(Vec<u8>),
+    Key(Key),
+    Stopped,
+    Error(String),
+}
+
+/// Returns the path to the default agent socket in the program's data directory
+pub fn get_default_socket() -> Result<PathBuf> {
+    Ok(os::get_data_dir()?.join(AGENT_SOCKET_NAME))
+}
+
+/// Serves the key operations of the unlocked session over a socket at the provided path until the
+/// agent is stopped or has been idle for longer than the timeout. The socket is only accessible by
+/// the current user, connections of other users are refused, and the socket is removed on exit
+pub fn serve(session: Session, socket_path: &Path, idle_timeout: Duration) -> Result<()> {
+    if socket_path.exists() {
+        if UnixStream::connect(socket_path).is_ok() {
+            return Err(new_err!(EncryptionError: AgentError, format!("Agent is already running at \"{}\"", socket_path.display())))
+        }
+        log_debug!("Removing stale agent socket {:?}", socket_path);
+        fs::remove_file(socket_path)?;
+    }
+
+    let listener = bind_private(socket_path)?;
+    listener.set_nonblocking(true)?;
+    log_info!("Agent for profile \"{}\" is listening on \"{}\"", session.profile_name(), socket_path.display());
+
+    let stopped = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let mut last_activity = Instant::now();
+        while !stopped.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    last_activity = Instant::now();
+                    if let Err(err) = check_peer(&stream) {
+                        log_warn!("Refused agent connection: {}", err);
+                        continue
+                    }
+                    let (session, stopped) = (&session, &stopped);
+                    scope.spawn(move || match handle_connection(session, stream) {
+                        Ok(true) => {
+                            log_info!("Agent was stopped");
+                            stopped.store(true, Ordering::Relaxed);
+                        },
+                        Ok(false) => {},
+                        Err(err) => log_warn!("Unable to handle agent request: {}", err)
+                    });
+                },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    if last_activity.elapsed() >= idle_timeout {
+                        log_info!("Agent has been idle for {:?}, stopping", idle_timeout);
+                        break
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                },
+                Err(err) => log_warn!("Unable to accept agent connection: {}", err)
+            }
+        }
+    });
+
+    fs::remove_file(socket_path)?;
+    Ok(())
+}
+
+/// Binds the socket while the file mode creation mask only lets the current user access it, so
+/// the socket is never accessible by other users, not even briefly
+fn bind_private(socket_path: &Path) -> Result<UnixListener> {
+    // SAFETY: umask only replaces the mask of the process and cannot fail
+    let previous_mask = unsafe { libc::umask(0o177) };
+    let listener = UnixListener::bind(socket_path);
+    // SAFETY: same as above, restoring the previous mask
+    unsafe { libc::umask(previous_mask) };
+    Ok(listener?)
+}
+
+/// Ensures the process on the other end of the connection runs as the same user as the agent
+fn check_peer(stream: &UnixStream) -> Result<()> {
+    let peer_uid = peer_uid(stream)?;
+    // SAFETY: geteuid has no preconditions and cannot fail
+    let uid = unsafe { libc::geteuid() };
+    if peer_uid != uid {
+        return Err(new_err!(EncryptionError: AgentError, format!("Connection is from user {}, not {}", peer_uid, uid)))
+    }
+    Ok(())
+}
+
+/// Returns the user id of the process on the other end of the connection
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let mut credentials = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: the pointer and the length describe the `ucred` struct which SO_PEERCRED fills
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut credentials as *mut libc::ucred as *mut libc::c_void,
+            &mut len
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error())
+    }
+    Ok(credentials.uid)
+}
+
+/// Returns the user id of the process on the other end of the connection
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let (mut uid, mut gid) = (0, 0);
+    // SAFETY: both pointers are valid for writes of the ids
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(io::Error::last_os_error())
+    }
+    Ok(uid)
+}
+
+/// Returns the bincode options of the agent messages. They match `bincode::serialize`, but limit
+/// the length of a message, so a client cannot make the agent allocate huge buffers
+fn message_options() -> impl Options {
+    bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(MAX_MESSAGE_LEN)
+}
+
+/// Reads a single request from the client and answers it. Returns whether the agent should stop
+fn handle_connection(session: &Session, mut stream: UnixStream) -> Result<bool> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let mut request: AgentRequest = message_options().deserialize_from(&mut stream)
+        .map_err(|err| new_err!(EncryptionError: AgentError, err))?;
+    log_debug!("Received agent request: {}", match request {
+        AgentRequest::Status => "status",
+        AgentRequest::WrapKey(_) => "wrap key",
+        AgentRequest::UnwrapKey(_) => "unwrap key",
+        AgentRequest::Stop => "stop",
+    });
+
+    let mut stop = false;
+    let mut response = match &mut request {
+        AgentRequest::Status => AgentResponse::Status(session.profile_name().to_string()),
+        AgentRequest::WrapKey(data_key) => {
+            let wrapped_key = session.wrap_key(data_key);
+            data_key.zeroize();
+            match wrapped_key {
+                Ok(wrapped_key) => AgentResponse::WrappedKey(wrapped_key),
+                Err(err) => AgentResponse::Error(err.to_string())
+            }
+        },
+        AgentRequest::UnwrapKey(wrapped_key) => match session.unwrap_key(wrapped_key) {
+            Ok(data_key) => AgentResponse::Key(*data_key),
+            Err(err) => AgentResponse::Error(err.to_string())
+        },
+        AgentRequest::Stop => {
+            stop = true;
+            AgentResponse::Stopped
+        }
+    };
+
+    let result = message_options().serialize_into(&mut stream, &response);
+    if let AgentResponse::Key(data_key) = &mut response {
+        data_key.zeroize();
+    }
+
+    result.map_err(|err| new_err!(EncryptionError: AgentError, err))?;
+    Ok(stop)
+}
+
+/// Client used to send key operations to a running agent
+#[derive(Debug)]
+pub struct AgentClient {
+    socket_path: PathBuf,
+}
+
+impl AgentClient {
+    /// Connects to the agent listening at the provided socket. Returns the client together with
+    /// the name of the profile unlocked by the agent
+    pub fn connect(socket_path: &Path) -> Result<(Self, String)> {
+        log_debug!("Connecting to the agent at {:?}", socket_path);
+        let client = AgentClient { socket_path: socket_path.to_path_buf() };
+
+        match client.request(&AgentRequest::Status)? {
+            AgentResponse::Status(profile_name) => Ok((client, profile_name)),
+            response => Err(unexpected_response(&response))
+        }
+    }
+
+    /// Asks the agent to stop
+    pub fn stop(&self) -> Result<()> {
+        match self.request(&AgentRequest::Stop)? {
+            AgentResponse::Stopped => Ok(()),
+            response => Err(unexpected_response(&response))
+        }
+    }
+
+    /// Sends a single request to the agent over a new connection and returns its response
+    fn request(&self, request: &AgentRequest) -> Result<AgentResponse> {
+        let mut stream = UnixStream::connect(&self.socket_path)
+            .map_err(|err| new_err!(EncryptionError: AgentError, format!("Unable to connect to \"{}\": {}", self.socket_path.display(), err)))?;
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        message_options().serialize_into(&mut stream, request)
+            .map_err(|err| new_err!(EncryptionError: AgentError, err))?;
+        message_options().deserialize_from(&mut stream)
+            .map_err(|err| new_err!(EncryptionError: AgentError, err))
+    }
+}
+
+impl KeyWrapper for AgentClient {
+    fn wrap_key(&self, data_key: &Key) -> Result<Vec<u8>> {
+        let mut request = AgentRequest::WrapKey(*data_key);
+        let response = self.request(&request);
+        if let AgentRequest::WrapKey(data_key) = &mut request {
+            data_key.zeroize();
+        }
+
+        match response? {
+            AgentResponse::WrappedKey(wrapped_key) => Ok(wrapped_key),
+            response => Err(unexpected_response(&response))
+        }
+    }
+
+    fn unwrap_key(&self, wrapped_key: &[u8]) -> Result<Secret<Key>> {
+        let mut response = self.request(&AgentRequest::UnwrapKey(wrapped_key.to_vec()))?;
+        match &mut response {
+            AgentResponse::Key(data_key) => {
+                let secret = Secret::new(*data_key);
+                data_key.zeroize();
+                Ok(secret)
+            },
+            response => Err(unexpected_response(response))
+        }
+    }
+
+    /// The agent never exposes the profile key, so version 1 boxfiles need the password instead
+    fn legacy_key(&self) -> Result<Secret<Key>> {
+        Err(new_err!(EncryptionError: AgentError, "Version 1 boxfiles cannot be decrypted through the agent, use the password instead"))
+    }
+}
+
+/// Converts a response which doesn't match the request into an error
+fn unexpected_response(response: &AgentResponse) -> crate::Error {
+    match response {
+        AgentResponse::Error(message) => new_err!(EncryptionError: AgentError, message),
+        _ => new_err!(EncryptionError: AgentError, "Unexpected response")
+    }
+}
@@ -0,0 +1,182 @@
+//! Contains logic for encrypting and decrypting multiple files at once. The files are processed
+//! concurrently by a pool of worker threads using the key of an already unlocked session or agent
+//!
+//! Output paths are planned sequentially in the order of the input before any work starts, so
+//! custom output paths and conflict resolutions are always assigned to the same files
+//!
+//! The changes to the registry of tracked files are collected from all workers, so they can be
+//! recorded at once after the batch is done
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use crate::core::data::tracking::TrackedFile;
+use crate::core::encryption::boxfile::Boxfile;
+use crate::core::options::{DecryptionOptions, EncryptionOptions};
+use crate::core::encryption::cipher::KeyWrapper;
+use crate::{log_debug, log_info, Result};
+
+/// Result of processing a single file from the batch
+pub struct BatchResult {
+    /// Path to the input file
+    pub input_path: PathBuf,
+    /// Path to the written output file, `None` if the file was skipped, or the error which
+    /// occurred while processing the file
+    pub output: Result<Option<PathBuf>>,
+}
+
+/// Encrypts all provided files using the provided key. Files are encrypted concurrently using the
+/// specified number of threads (`0` uses all available cores). Returns the results for each file
+/// in the order of the input, with the errors of the individual files contained in their results,
+/// and the registry entries of the written boxfiles
+pub fn encrypt_batch(
+    input_paths: &[PathBuf],
+    key: &(impl KeyWrapper + Sync),
+    options: &mut EncryptionOptions,
+    threads: usize,
+) -> (Vec<BatchResult>, Vec<TrackedFile>) {
+    if input_paths.is_empty() {
+        return (Vec::new(), Vec::new())
+    }
+
+    log_info!("Starting encryption of {} files...", input_paths.len());
+
+    let mut reserved = HashSet::new();
+    let plans = input_paths.iter()
+        .map(|input_path| {
+            let output_path = super::plan_encryption(input_path, options, &reserved)?;
+            if let Some(path) = &output_path {
+                reserved.insert(path.clone());
+            }
+            Ok(output_path)
+        })
+        .collect::<Vec<Result<Option<PathBuf>>>>();
+
+    let options = &*options;
+    let tracked_files = Mutex::new(Vec::new());
+    let outputs = run_jobs(plans, threads, |i, output_path| {
+        let input_path = &input_paths[i];
+        log_info!("Encrypting {:?}", input_path);
+        let tracked_file = super::encrypt_file(input_path, &output_path, key, options)?;
+        tracked_files.lock().expect("Tracked files lock poisoned").push(tracked_file);
+        Ok(Some(output_path))
+    });
+
+    let tracked_files = tracked_files.into_inner().expect("Tracked files lock poisoned");
+    (collect_results(input_paths, outputs), tracked_files)
+}
+
+/// Decrypts all provided files using the provided key. Files are decrypted concurrently using the
+/// specified number of threads (`0` uses all available cores). Returns the results for each file
+/// in the order of the input, with the errors of the individual files contained in their results,
+/// and the ids of the boxfiles which were removed after decryption
+pub fn decrypt_batch(
+    input_paths: &[PathBuf],
+    key: &(impl KeyWrapper + Sync),
+    options: &mut DecryptionOptions,
+    threads: usize,
+) -> (Vec<BatchResult>, Vec<String>) {
+    if input_paths.is_empty() {
+        return (Vec::new(), Vec::new())
+    }
+
+    log_info!("Starting decryption of {} files...", input_paths.len());
+
+    let mut reserved = HashSet::new();
+    let plans = input_paths.iter()
+        .map(|input_path| {
+            let header = Boxfile::parse_header(input_path)?;
+            let output_path = super::plan_decryption(input_path, &header, options, &reserved)?;
+            if let Some(path) = &output_path {
+                reserved.insert(path.clone());
+            }
+            Ok(output_path)
+        })
+        .collect::<Vec<Result<Option<PathBuf>>>>();
+
+    let options = &*options;
+    let removed_ids = Mutex::new(Vec::new());
+    let outputs = run_jobs(plans, threads, |i, output_path| {
+        let input_path = &input_paths[i];
+        log_info!("Decrypting {:?}", input_path);
+        let boxfile = Boxfile::parse(input_path)?;
+        let file_id = boxfile.header.file_id();
+        super::decrypt_boxfile(boxfile, input_path, &output_path, key, options)?;
+        if !input_path.exists() {
+            removed_ids.lock().expect("Tracked files lock poisoned").push(file_id);
+        }
+        Ok(Some(output_path))
+    });
+
+    let removed_ids = removed_ids.into_inner().expect("Tracked files lock poisoned");
+    (collect_results(input_paths, outputs), removed_ids)
+}
+
+/// Runs the work for every planned output path on a pool of worker threads. Plans which failed or
+/// were skipped are passed through untouched. Returns the outputs in the order of the plans
+fn run_jobs(
+    plans: Vec<Result<Option<PathBuf>>>,
+    threads: usize,
+    work: impl Fn(usize, PathBuf) -> Result<Option<PathBuf>> + Sync,
+) -> Vec<Result<Option<PathBuf>>> {
+    let mut outputs = Vec::with_capacity(plans.len());
+    let mut jobs = Vec::new();
+
+    for (i, plan) in plans.into_iter().enumerate() {
+        match plan {
+            Ok(Some(output_path)) => {
+                jobs.push((i, output_path));
+                outputs.push(Ok(None));
+            },
+            plan => outputs.push(plan)
+        }
+    }
+
+    let threads = get_thread_count(threads).min(jobs.len()).max(1);
+    log_debug!("Processing {} files using {} threads", jobs.len(), threads);
+
+    let next_job = AtomicUsize::new(0);
+    let finished_jobs = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| scope.spawn(|| {
+                let mut finished = Vec::new();
+                loop {
+                    let job = next_job.fetch_add(1, Ordering::Relaxed);
+                    let Some((i, output_path)) = jobs.get(job) else { break };
+                    finished.push((*i, work(*i, output_path.clone())));
+                }
+                finished
+            }))
+            .collect::<Vec<_>>();
+
+        workers.into_iter()
+            .flat_map(|worker| worker.join().expect("Worker thread panicked"))
+            .collect::<Vec<_>>()
+    });
+
+    for (i, output) in finished_jobs {
+        outputs[i] = output;
+    }
+    outputs
+}
+
+/// Pairs the outputs with the input paths they belong to
+fn collect_results(input_paths: &[PathBuf], outputs: Vec<Result<Option<PathBuf>>>) -> Vec<BatchResult> {
+    input_paths.iter()
+        .zip(outputs)
+        .map(|(input_path, output)| BatchResult {
+            input_path: input_path.clone(),
+            output,
+        })
+        .collect()
+}
+
+/// Returns the number of threads to use. Zero means using all available cores
+fn get_thread_count(threads: usize) -> usize {
+    if threads > 0 {
+        return threads
+    }
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
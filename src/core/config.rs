@@ -0,0 +1,184 @@
+//! Contains core logic for config manipulation subcommands
+//!
+//! Settings are addressed with dotted keys made of the section and the setting name (e.g.
+//! `encryption.compression`). Changes are made directly to the TOML document of the config file,
+//! so the comments and formatting of the user are preserved. Changes always go to the global
+//! config file, as the project files and the environment variables are only read
+
+use std::io;
+use std::path::PathBuf;
+use toml_edit::{DocumentMut, Item, Value};
+use crate::core::data::{self, config::{ConfigOrigin, DataboxerConfig}};
+use crate::core::data::io::{read_file, write_private_file};
+use crate::{log_debug, log_info, new_err, Result};
+
+/// Setting of the config in effect
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSetting {
+    /// Dotted key of the setting (e.g. `encryption.compression`)
+    pub key: String,
+    /// Value formatted as a TOML value. `None` means the setting has no value
+    pub value: Option<String>,
+    /// Layer of the configuration the value comes from
+    pub origin: ConfigOrigin,
+}
+
+/// Returns the value of the setting with the provided key. `None` means the setting has no value
+pub fn get(key: &str) -> Result<Option<String>> {
+    log_info!("Getting the value of \"{}\"", key);
+    list()?.into_iter()
+        .find(|setting| setting.key == key)
+        .map(|setting| setting.value)
+        .ok_or_else(|| new_err!(ConfigError: UnknownSetting, key))
+}
+
+/// Returns all settings with their values in effect, including the default ones
+pub fn list() -> Result<Vec<ConfigSetting>> {
+    log_info!("Listing all settings");
+    let config = data::get_config()?;
+    get_settings(&config)
+}
+
+/// Sets the setting with the provided key to the value. The value is parsed as a TOML value
+/// (e.g. `true` or `8`) and taken as a string otherwise. Errors if the value has a wrong type or
+/// is invalid for the setting
+pub fn set(key: &str, value: &str) -> Result<()> {
+    log_info!("Setting \"{}\" to {}", key, value);
+    let (section, name) = split_key(key)?;
+    let _lock = data::lock_config()?;
+    let (config_file, mut document) = read_document()?;
+
+    // unquoted strings (e.g. `deflate`) are not valid TOML values, and a string setting might be
+    // given a value which looks like another type (e.g. a directory named `123`)
+    let values = match value.parse::<Value>() {
+        Ok(parsed) if parsed.is_str() => vec![parsed],
+        Ok(parsed) => vec![parsed, Value::from(value)],
+        Err(_) => vec![Value::from(value)]
+    };
+
+    if document.get(section).is_none() {
+        document.insert(section, toml_edit::table());
+    }
+    let decor = document[section].get(name)
+        .and_then(Item::as_value)
+        .map(|value| value.decor().clone())
+        .unwrap_or_default();
+
+    // the error of the first attempt is reported, as it is the most likely meant type
+    let mut error = None;
+    for mut value in values {
+        *value.decor_mut() = decor.clone();
+        document[section][name] = Item::Value(value);
+        match DataboxerConfig::parse(&document.to_string(), config_file.clone()) {
+            Ok(_) => {
+                error = None;
+                break
+            },
+            Err(err) => {
+                error.get_or_insert(err);
+            }
+        }
+    }
+    if let Some(err) = error {
+        return Err(err)
+    }
+
+    write_private_file(&config_file, &document.to_string())?;
+    Ok(())
+}
+
+/// Resets the setting with the provided key to its default value by removing it from the config
+/// file. Removes all settings if no key is provided, keeping the comments of the file
+pub fn reset(key: Option<&str>) -> Result<()> {
+    let keys = match key {
+        Some(key) => {
+            log_info!("Resetting \"{}\"", key);
+            split_key(key)?;
+            vec![key.to_string()]
+        },
+        None => {
+            log_info!("Resetting the config");
+            DataboxerConfig::parse("", PathBuf::new())?.get_settings()?.into_iter()
+                .map(|(key, _)| key)
+                .collect()
+        }
+    };
+
+    let _lock = data::lock_config()?;
+    let (config_file, mut document) = read_document()?;
+    for key in &keys {
+        let Some((section, name)) = key.split_once('.') else { continue };
+        if let Some(table) = document.get_mut(section).and_then(Item::as_table_like_mut) {
+            table.remove(name);
+        }
+    }
+
+    write_private_file(&config_file, &document.to_string())?;
+    Ok(())
+}
+
+/// Returns the path to the config file in use
+pub fn path() -> Result<PathBuf> {
+    data::get_config_file()
+}
+
+/// Flattens the config into dotted keys together with their values formatted as TOML values
+fn get_settings(config: &DataboxerConfig) -> Result<Vec<ConfigSetting>> {
+    let settings = config.get_settings()?.into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::Null => None,
+                value => Some(value.to_string())
+            };
+            let origin = config.get_origin(&key).clone();
+            ConfigSetting { key, value, origin }
+        })
+        .collect();
+    Ok(settings)
+}
+
+/// Splits the dotted key into the section and the setting name. Errors if there is no such setting
+fn split_key(key: &str) -> Result<(&str, &str)> {
+    let config = DataboxerConfig::parse("", PathBuf::new())?;
+    if !config.get_settings()?.iter().any(|(setting, _)| setting == key) {
+        return Err(new_err!(ConfigError: UnknownSetting, key))
+    }
+
+    key.split_once('.').ok_or_else(|| new_err!(ConfigError: UnknownSetting, key))
+}
+
+/// Reads the config file in use as an editable TOML document. Returns an empty document if the file
+/// doesn't exist yet
+fn read_document() -> Result<(PathBuf, DocumentMut)> {
+    let config_file = data::get_config_file()?;
+    let file_data = match read_file(&config_file) {
+        Ok(file_data) => file_data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into())
+    };
+    log_debug!("Parsing the config file as a TOML document");
+
+    let document = file_data.parse::<DocumentMut>()
+        .map_err(|err| new_err!(ConfigError: ParseError, format!("\"{}\": {}", config_file.display(), err.message())))?;
+    Ok((config_file, document))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_keys() -> Result<()> {
+        let config = DataboxerConfig::parse("[general]\nthreads = 4\n", PathBuf::new())?;
+        let settings = get_settings(&config)?;
+
+        let value_of = |key: &str| settings.iter().find(|setting| setting.key == key).map(|setting| setting.value.clone());
+
+        assert_eq!(value_of("general.threads"), Some(Some("4".to_string())));
+        assert_eq!(value_of("encryption.compression"), Some(Some("\"none\"".to_string())));
+        assert_eq!(value_of("storage.output_dir"), Some(None));
+        assert!(split_key("encryption.cipher").is_ok());
+        assert!(split_key("encryption.unknown").is_err());
+        Ok(())
+    }
+}
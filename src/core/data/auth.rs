@@ -3,22 +3,37 @@
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use argon2::password_hash::rand_core::OsRng;
 use argon2::password_hash::{Salt, SaltString};
+use crate::core::encryption::secret::Secret;
 use crate::{Result, new_err, Key};
 
 /// Hashes the given password. Returns hashed password and key generated based on the password hash
 /// used to encrypt the stored encryption key
-pub fn hash_password(password: &str) -> Result<(String, Key)> {
+pub fn hash_password(password: &str) -> Result<(String, Secret<Key>)> {
     let salt = SaltString::generate(&mut OsRng);
-    let mut password_key = Key::default();
+    let mut password_key = Secret::new(Key::default());
 
     let argon2 = Argon2::default();
     let password_hash = argon2.hash_password(password.as_bytes(), &salt)
         .map_err(|err| new_err!(EncryptionError: HashError, err))?.to_string();
-    argon2.hash_password_into(password.as_bytes(), salt.as_str().as_bytes(), &mut password_key)
+    argon2.hash_password_into(password.as_bytes(), salt.as_str().as_bytes(), &mut *password_key)
         .map_err(|err| new_err!(EncryptionError: HashError, err))?;
     Ok((password_hash, password_key))
 }
 
+/// Generates a new random salt for deriving a key from a password
+pub fn generate_salt() -> String {
+    SaltString::generate(&mut OsRng).to_string()
+}
+
+/// Derives the key from the password and the provided salt
+pub fn derive_key(password: &str, salt: &str) -> Result<Secret<Key>> {
+    let mut key = Secret::new(Key::default());
+
+    Argon2::default().hash_password_into(password.as_bytes(), salt.as_bytes(), &mut *key)
+        .map_err(|err| new_err!(EncryptionError: HashError, err))?;
+    Ok(key)
+}
+
 /// Verifies password by comparing it to the password hash, returning password hash's Salt 
 /// if the verification is successful. Errors if the Salt is missing
 pub fn verify_password<'a>(password_hash: &'a str, password: &str) -> Result<Salt<'a>> {
@@ -36,12 +51,12 @@ pub fn verify_password<'a>(password_hash: &'a str, password: &str) -> Result<Sal
 
 /// Returns the encryption key generated based on the password if the password verification is
 /// successful
-pub fn get_password_key(password_hash: &str, password: &str) -> Result<Key> {
+pub fn get_password_key(password_hash: &str, password: &str) -> Result<Secret<Key>> {
     let salt = verify_password(password_hash, password)?;
-    let mut password_key = Key::default();
+    let mut password_key = Secret::new(Key::default());
     
     let argon2 = Argon2::default();
-    argon2.hash_password_into(password.as_bytes(), salt.as_str().as_bytes(), &mut password_key)
+    argon2.hash_password_into(password.as_bytes(), salt.as_str().as_bytes(), &mut *password_key)
         .map_err(|err| new_err!(EncryptionError: HashError, err))?;
     Ok(password_key)
 }
@@ -1,105 +1,464 @@
 //! Contains everything related to Databoxer configuration
 //! 
 //! Provides a base `DataboxerConfig` struct which contains user-defined configuration for the
-//! program, which is set to default values on initialization. It is represented as a `config.toml`
+//! program, which is set to default values on initialization. It is represented as a `databoxer.toml`
 //! file on the disk, which is located in the program's default config directory.
 //! 
 //! Each configuration category is a separate struct (e.g.: `GeneralConfig`). Each field is public
-//! for accessing configuration fields
+//! for accessing configuration fields. Every setting has a default value, so the config file only
+//! needs to contain the settings which differ from it. Unknown settings and invalid values are
+//! rejected with an error pointing at the wrong setting
+//!
+//! The configuration is layered, with every layer overriding the settings of the previous ones:
+//! built-in defaults, the global config file, a project config file (`.databoxer.toml`) found in
+//! the working directory or any of its parents, and `DATABOXER_<SECTION>_<NAME>` environment
+//! variables. Command line flags override the resulting configuration
+//!
+//! A project config file comes with the directory it's found in, which may be a repository of
+//! someone else, so it can only change the settings in `PROJECT_SETTINGS`
 
-use std::io;
+use std::collections::HashMap;
+use std::{env, fmt, io};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use crate::core::data::io::{read_file, write_file};
-use crate::{log_debug, log_info};
+use std::path::{Path, PathBuf};
+use crate::core::data::io::{read_file, write_private_file};
+use crate::{log_debug, log_info, log_warn, new_err};
 use crate::core::error::Result;
+use crate::core::logs::LogLevel;
+use crate::core::options::{Cipher, Compression, ConflictPolicy, DeletePolicy, PaddingPolicy};
 
 /// Name of the main configuration file
-const CONFIG_FILE_NAME: &str = "databoxer.toml";
+pub const CONFIG_FILE_NAME: &str = "databoxer.toml";
+/// Name of the project configuration file, which is looked up in the working directory and its
+/// parents
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".databoxer.toml";
+/// Prefix of the environment variables overriding the settings
+const ENV_VAR_PREFIX: &str = "DATABOXER_";
+/// Settings which a project config file can change. The others could redirect or overwrite the
+/// decrypted files or weaken the password policies, so they are ignored in project files
+pub const PROJECT_SETTINGS: &[&str] = &[
+    "general.threads",
+    "general.log_level",
+    "encryption.compression",
+    "encryption.padding",
+    "encryption.keep_name",
+];
+
+/// Layer of the configuration which a setting value comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigOrigin {
+    /// Built-in default value
+    Default,
+    /// Global config file
+    Global(PathBuf),
+    /// Project config file
+    Project(PathBuf),
+    /// Environment variable with the provided name
+    Environment(String),
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::Global(path) => write!(f, "global file \"{}\"", path.display()),
+            ConfigOrigin::Project(path) => write!(f, "project file \"{}\"", path.display()),
+            ConfigOrigin::Environment(name) => write!(f, "environment variable {}", name),
+        }
+    }
+}
 
 /// Struct representing a TOML Configuration file
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct DataboxerConfig {
     pub general: GeneralConfig,
     pub encryption: EncryptionConfig,
     pub storage: StorageConfig,
     #[serde(skip)]
-    file_path: PathBuf
+    file_path: PathBuf,
+    /// Layers the settings were taken from, by their dotted keys. Settings missing from it have
+    /// the default value
+    #[serde(skip)]
+    origins: HashMap<String, ConfigOrigin>,
 }
 
 /// Struct containing general configuration for the program
 #[derive(Serialize, Deserialize, Debug)]
-pub struct GeneralConfig { }
+#[serde(default, deny_unknown_fields)]
+pub struct GeneralConfig {
+    /// Number of threads used to process multiple files at once. Zero uses all available cores
+    pub threads: usize,
+    /// Amount of log messages printed by default: "quiet", "normal", "verbose" or "debug"
+    pub log_level: LogLevel,
+    /// Minimum strength score of new profile passwords, from 0 (very weak) to 4 (very strong)
+    pub min_password_score: u8,
+    /// Minimum length of new profile passwords
+    pub min_password_length: usize,
+    /// Reject the most commonly used passwords for new profiles
+    pub reject_common_passwords: bool,
+    /// Number of failed authentication attempts after which the profile is locked out. Zero
+    /// disables the lockout, leaving only the growing delay between attempts
+    pub lockout_threshold: u32,
+    /// Time in seconds the profile stays locked out after reaching the threshold
+    pub lockout_time: u64,
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        GeneralConfig {
+            threads: 0,
+            log_level: LogLevel::default(),
+            min_password_score: 2,
+            min_password_length: 8,
+            reject_common_passwords: true,
+            lockout_threshold: 0,
+            lockout_time: 900,
+        }
+    }
+}
 
 /// Struct containing encryption configuration for the program
-#[derive(Serialize, Deserialize, Debug)]
-pub struct EncryptionConfig { }
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncryptionConfig {
+    /// Cipher used to encrypt new files
+    pub cipher: Cipher,
+    /// Compression applied to files before encryption: "none" or "deflate"
+    pub compression: Compression,
+    /// Length of the padding added to files: "length", "block" or "random"
+    pub padding: PaddingPolicy,
+    /// Keep the original file name for encrypted files by default
+    pub keep_name: bool,
+}
 
 /// Struct containing storage configuration for the program
 #[derive(Serialize, Deserialize, Debug)]
-pub struct StorageConfig { }
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// Default policy for what happens with the input file after encryption or decryption
+    pub delete_policy: DeletePolicy,
+    /// Default policy for what happens if the output file already exists
+    pub conflict_policy: ConflictPolicy,
+    /// Directory to write the output files to instead of next to the input files
+    pub output_dir: Option<PathBuf>,
+    /// Restore the original file times and permissions on decryption by default
+    pub preserve_attributes: bool,
+    /// Restore the original file owner on decryption by default
+    pub preserve_owner: bool,
+    /// Seconds to wait for another process to release the profile store or the config file
+    pub lock_timeout: u64,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            delete_policy: DeletePolicy::default(),
+            conflict_policy: ConflictPolicy::default(),
+            output_dir: None,
+            preserve_attributes: false,
+            preserve_owner: false,
+            lock_timeout: 10,
+        }
+    }
+}
 
 impl DataboxerConfig {
-    /// Imports self from the stored "config.toml" file. In case of the file missing, generates a
-    /// new file with the default configuration
-    pub fn import(config_directory: PathBuf) -> Result<Self> {
+    /// Imports self by layering the provided global config file, the project config file and the
+    /// environment variables over the default values. Missing config files are skipped
+    pub fn import(
+        config_file: PathBuf,
+        project_file: Option<PathBuf>,
+        env_vars: impl IntoIterator<Item = (String, String)>
+    ) -> Result<Self> {
         log_debug!("Importing Databoxer config");
-        let config_file = config_directory.join(CONFIG_FILE_NAME);
-
-        let config = match read_file(&config_file) {
-            Ok(file_data) => {
-                let mut config: DataboxerConfig = toml::from_str(&file_data)?;
-                config.file_path = config_file;
-                config
-            },
-            Err(err) => {
-                if err.kind() == io::ErrorKind::NotFound {
-                    log_info!("\"config.toml\" file doesn't exist. Generating new default config");
-                    Self::new(config_file)
-                } else {
-                    return Err(err.into());
+
+        let mut layers = Vec::new();
+        let files = [Some(ConfigOrigin::Global(config_file.clone())), project_file.map(ConfigOrigin::Project)];
+        for origin in files.into_iter().flatten() {
+            let (ConfigOrigin::Global(path) | ConfigOrigin::Project(path)) = &origin else { continue };
+            match read_file(path) {
+                Ok(file_data) => {
+                    if let ConfigOrigin::Project(path) = &origin {
+                        log_info!("Using the project config file \"{}\"", path.display());
+                    }
+                    layers.push((origin, file_data))
+                },
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    log_info!("\"{}\" file doesn't exist. Using the default config", path.display());
+                },
+                Err(err) => return Err(err.into())
+            }
+        }
+
+        Self::from_layers(config_file, layers, env_vars.into_iter().collect())
+    }
+
+    /// Merges the contents of the config files and the environment variables in order over the
+    /// default values. Every layer is validated on its own, so errors point at the wrong layer
+    fn from_layers(
+        file_path: PathBuf,
+        layers: Vec<(ConfigOrigin, String)>,
+        env_vars: HashMap<String, String>
+    ) -> Result<Self> {
+        let mut merged = toml::Table::new();
+        let mut origins = HashMap::new();
+
+        for (origin, file_data) in layers {
+            let (ConfigOrigin::Global(path) | ConfigOrigin::Project(path)) = &origin else { continue };
+            Self::parse(&file_data, path.clone())?;
+            log_debug!("Applying config layer from {}", origin);
+
+            let table: toml::Table = toml::from_str(&file_data)?;
+            for (section, values) in table {
+                let toml::Value::Table(values) = values else { continue };
+                for (name, value) in values {
+                    let key = format!("{}.{}", section, name);
+                    if matches!(origin, ConfigOrigin::Project(_)) && !PROJECT_SETTINGS.contains(&key.as_str()) {
+                        log_warn!("Ignoring {} set in {}, as it can only be set globally", key, origin);
+                        continue
+                    }
+                    origins.insert(key, origin.clone());
+                    merged_section(&mut merged, &section).insert(name, value);
                 }
             }
-        };
+        }
+
+        for (key, default_value) in DataboxerConfig::default().get_settings()? {
+            let Some((section, name)) = key.split_once('.') else { continue };
+            let var_name = format!("{}{}_{}", ENV_VAR_PREFIX, section, name).to_uppercase();
+            let Some(raw_value) = env_vars.get(&var_name) else { continue };
+            log_debug!("Applying config override from {}", var_name);
+
+            let value = parse_env_value(&var_name, raw_value, &default_value)?;
+            let mut layer = toml::Table::new();
+            merged_section(&mut layer, section).insert(name.to_string(), value.clone());
+            toml::Value::Table(layer).try_into::<DataboxerConfig>()
+                .map_err(|err| new_err!(ConfigError: ParseError, format!("{}: {}", var_name, err.message())))?;
+
+            origins.insert(key.clone(), ConfigOrigin::Environment(var_name));
+            merged_section(&mut merged, section).insert(name.to_string(), value);
+        }
+
+        let mut config: DataboxerConfig = toml::Value::Table(merged).try_into()
+            .map_err(|err: toml::de::Error| new_err!(ConfigError: ParseError, err.message()))?;
+        config.file_path = file_path;
+        config.origins = origins;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses the config from the contents of the config file and validates it. Settings missing
+    /// from the file are set to their default values
+    pub fn parse(file_data: &str, file_path: PathBuf) -> Result<Self> {
+        let mut config: DataboxerConfig = toml::from_str(file_data).map_err(|err| {
+            let line = err.span()
+                .map(|span| file_data[..span.start].lines().count().max(1))
+                .unwrap_or(1);
+            new_err!(ConfigError: ParseError, format!("\"{}\" line {}: {}", file_path.display(), line, err.message()))
+        })?;
+        config.file_path = file_path;
 
+        config.validate()?;
         Ok(config)
     }
 
-    /// Bare-minimum constructor to use in case of file not being available to import from
-    fn new(
-        file_path: PathBuf
-    ) -> DataboxerConfig {
-        DataboxerConfig {
-            general: GeneralConfig { },
-            encryption: EncryptionConfig { },
-            storage: StorageConfig { },
-            file_path
+    /// Checks that the values of the settings are within their allowed ranges. Errors with the name
+    /// of the first invalid setting. The output directory is only checked once it's used, so a
+    /// missing one doesn't break the commands which don't write any files
+    pub fn validate(&self) -> Result<()> {
+        if self.general.min_password_score > 4 {
+            return Err(new_err!(ConfigError: InvalidValue, format!("general.min_password_score must be between 0 and 4, got {}", self.general.min_password_score)))
+        }
+        if self.general.lockout_threshold > 0 && self.general.lockout_time == 0 {
+            return Err(new_err!(ConfigError: InvalidValue, "general.lockout_time must be greater than 0 when general.lockout_threshold is set"))
+        }
+        Ok(())
+    }
+
+    /// Returns the path to the config file
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Returns the layer which the value of the setting with the provided dotted key comes from
+    pub fn get_origin(&self, key: &str) -> &ConfigOrigin {
+        self.origins.get(key).unwrap_or(&ConfigOrigin::Default)
+    }
+
+    /// Returns all settings as dotted keys (e.g. `encryption.compression`) with their values.
+    /// Settings without a value are included as `null`
+    pub fn get_settings(&self) -> Result<Vec<(String, serde_json::Value)>> {
+        // JSON is used, as unlike TOML it keeps the settings without a value
+        let serde_json::Value::Object(sections) = serde_json::to_value(self)? else {
+            return Err(new_err!(ConfigError: InvalidValue, "Config is not a table"))
+        };
+
+        let mut settings = Vec::new();
+        for (section, values) in sections {
+            let serde_json::Value::Object(values) = values else { continue };
+            for (name, value) in values {
+                settings.push((format!("{}.{}", section, name), value));
+            }
         }
+        Ok(settings)
     }
 
     /// Saves the configuration to the config file
-    #[allow(dead_code)]
     pub fn save(&self) -> Result<()> {
-        log_debug!("Saving configuration data to \"config.toml\"");
+        log_debug!("Saving configuration data to \"{}\"", self.file_path.display());
         let toml_data = toml::to_string(&self)?;
 
-        write_file(&self.file_path, &toml_data, true)?;
+        write_private_file(&self.file_path, &toml_data)?;
         Ok(())
     }
 }
 
+/// Looks for the project config file in the provided directory and all of its parents. Returns the
+/// closest one
+pub fn find_project_file(start_directory: &Path) -> Option<PathBuf> {
+    start_directory.ancestors()
+        .map(|directory| directory.join(PROJECT_CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// Returns the project config file for the current working directory, if there is one
+pub fn find_current_project_file() -> Option<PathBuf> {
+    env::current_dir().ok().and_then(|directory| find_project_file(&directory))
+}
+
+/// Returns the table of the section, creating it if it doesn't exist
+fn merged_section<'a>(table: &'a mut toml::Table, section: &str) -> &'a mut toml::Table {
+    let value = table.entry(section).or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    if !value.is_table() {
+        *value = toml::Value::Table(toml::Table::new());
+    }
+    value.as_table_mut().expect("Section was just made a table")
+}
+
+/// Parses the value of the environment variable into the type of the setting it overrides, which
+/// is determined from the default value of the setting
+fn parse_env_value(var_name: &str, raw_value: &str, default_value: &serde_json::Value) -> Result<toml::Value> {
+    let value = match default_value {
+        serde_json::Value::Bool(_) => raw_value.parse::<bool>().ok().map(toml::Value::Boolean),
+        serde_json::Value::Number(_) => raw_value.parse::<i64>().ok().map(toml::Value::Integer),
+        _ => Some(toml::Value::String(raw_value.to_string()))
+    };
+
+    value.ok_or_else(|| new_err!(ConfigError: InvalidValue, format!("{} has a value of a wrong type: \"{}\"", var_name, raw_value)))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::core::data::os;
     use super::*;
 
+    #[test]
+    fn test_partial_config() -> Result<()> {
+        let config = DataboxerConfig::parse("[encryption]\ncompression = \"deflate\"\n", PathBuf::new())?;
+        assert_eq!(config.encryption.compression, Compression::Deflate);
+        assert_eq!(config.encryption.cipher, Cipher::ChaCha20Poly1305);
+        assert_eq!(config.general.min_password_length, GeneralConfig::default().min_password_length);
+        assert_eq!(config.storage.delete_policy, DeletePolicy::Delete);
+
+        let saved_config = DataboxerConfig::parse(&toml::to_string(&config)?, PathBuf::new())?;
+        assert_eq!(saved_config.encryption.compression, Compression::Deflate);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for file_data in [
+            "[general]\nmin_password_score = 7\n",
+            "[general]\nunknown_setting = true\n",
+            "[encryption]\ncipher = \"aes\"\n",
+        ] {
+            let result = DataboxerConfig::parse(file_data, PathBuf::new());
+            assert!(matches!(result, Err(crate::Error::ConfigError(_))), "Accepted invalid config: {}", file_data);
+        }
+
+        let result = DataboxerConfig::parse("[storage]\noutput_dir = \"/nonexistent/databoxer\"\n", PathBuf::new());
+        assert!(result.is_ok(), "Missing output directory was rejected before it was used");
+    }
+
+    #[test]
+    fn test_layered_config() -> Result<()> {
+        let global = ConfigOrigin::Global(PathBuf::from("databoxer.toml"));
+        let project = ConfigOrigin::Project(PathBuf::from(".databoxer.toml"));
+        let layers = vec![
+            (global.clone(), "[encryption]\ncompression = \"deflate\"\nkeep_name = true\n".to_string()),
+            (project.clone(), "[encryption]\nkeep_name = false\n".to_string()),
+        ];
+        let env_vars = HashMap::from([
+            ("DATABOXER_GENERAL_MIN_PASSWORD_LENGTH".to_string(), "12".to_string()),
+            ("DATABOXER_UNRELATED".to_string(), "value".to_string()),
+        ]);
+
+        let config = DataboxerConfig::from_layers(PathBuf::new(), layers, env_vars)?;
+        assert_eq!(config.encryption.compression, Compression::Deflate);
+        assert!(!config.encryption.keep_name);
+        assert_eq!(config.general.min_password_length, 12);
+        assert_eq!(config.get_origin("encryption.compression"), &global);
+        assert_eq!(config.get_origin("encryption.keep_name"), &project);
+        assert_eq!(config.get_origin("general.min_password_length"), &ConfigOrigin::Environment("DATABOXER_GENERAL_MIN_PASSWORD_LENGTH".to_string()));
+        assert_eq!(config.get_origin("encryption.cipher"), &ConfigOrigin::Default);
+
+        for (name, value) in [("DATABOXER_GENERAL_MIN_PASSWORD_LENGTH", "long"), ("DATABOXER_ENCRYPTION_CIPHER", "aes")] {
+            let env_vars = HashMap::from([(name.to_string(), value.to_string())]);
+            let result = DataboxerConfig::from_layers(PathBuf::new(), Vec::new(), env_vars);
+            assert!(matches!(result, Err(crate::Error::ConfigError(_))), "Accepted invalid {}={}", name, value);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_restrictions() -> Result<()> {
+        let global = ConfigOrigin::Global(PathBuf::from("databoxer.toml"));
+        let project = ConfigOrigin::Project(PathBuf::from(".databoxer.toml"));
+        let layers = vec![
+            (global.clone(), "[general]\nmin_password_score = 3\nlockout_threshold = 5\n".to_string()),
+            (project.clone(), concat!(
+                "[general]\nmin_password_score = 0\nmin_password_length = 1\nreject_common_passwords = false\n",
+                "lockout_threshold = 0\nthreads = 2\n",
+                "[storage]\nconflict_policy = \"overwrite\"\ndelete_policy = \"keep\"\noutput_dir = \"/tmp\"\n",
+            ).to_string()),
+        ];
+
+        let config = DataboxerConfig::from_layers(PathBuf::new(), layers, HashMap::new())?;
+        assert_eq!(config.general.min_password_score, 3);
+        assert_eq!(config.general.min_password_length, GeneralConfig::default().min_password_length);
+        assert!(config.general.reject_common_passwords);
+        assert_eq!(config.general.lockout_threshold, 5);
+        assert_eq!(config.storage.conflict_policy, ConflictPolicy::default());
+        assert_eq!(config.storage.delete_policy, DeletePolicy::default());
+        assert_eq!(config.storage.output_dir, None);
+        assert_eq!(config.general.threads, 2);
+        assert_eq!(config.get_origin("general.lockout_threshold"), &global);
+        assert_eq!(config.get_origin("general.threads"), &project);
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_file_discovery() -> Result<()> {
+        let project_directory = std::env::temp_dir().join(format!("databoxer-project-{}", std::process::id()));
+        let nested_directory = project_directory.join("nested").join("deeper");
+        std::fs::create_dir_all(&nested_directory)?;
+        std::fs::write(project_directory.join(PROJECT_CONFIG_FILE_NAME), "")?;
+
+        let found = find_project_file(&nested_directory);
+        std::fs::remove_dir_all(&project_directory)?;
+        assert_eq!(found, Some(project_directory.join(PROJECT_CONFIG_FILE_NAME)));
+        Ok(())
+    }
+
     #[test]
     #[ignore]
-    /// Creates the `config.toml` file in the program configuration directory and writes the
+    /// Creates the `databoxer.toml` file in the program configuration directory and writes the
     /// default configuration to it
     fn write_default_config() {
         let config_directory = os::get_config_dir().expect("Cannot get config directory");
-        let config = DataboxerConfig::import(config_directory);
+        let config = DataboxerConfig::import(config_directory.join(CONFIG_FILE_NAME), None, []);
 
         assert!(config.is_ok())
     }
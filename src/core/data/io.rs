@@ -1,8 +1,12 @@
 //! Contains functions for basic IO operation on files
 
-use std::path::Path;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 use std::fs::{self, File};
 use std::io::{Read, Result, Write};
+#[cfg(unix)]
+use std::sync::OnceLock;
+use rand::RngCore;
 use crate::log_debug;
 
 /// Reads plain bytes from the specified file
@@ -12,7 +16,7 @@ pub fn read_bytes(path: &Path) -> Result<Vec<u8>> {
     let metadata = fs::metadata(path)?;
     let mut buffer = vec![0; metadata.len() as usize];
 
-    file.read(&mut buffer)?;
+    file.read_exact(&mut buffer)?;
     file.flush()?;
 
     log_debug!("Read {} bytes", buffer.len());
@@ -22,7 +26,7 @@ pub fn read_bytes(path: &Path) -> Result<Vec<u8>> {
 /// Reads specified file and returns its contents as string
 pub fn read_file(file_path: &Path) -> Result<String> {
     log_debug!("Reading \"{}\"", file_path.display());
-    let mut file = File::open(&file_path)?;
+    let mut file = File::open(file_path)?;
     let mut file_contents = String::new();
 
     file.read_to_string(&mut file_contents)?;
@@ -30,33 +34,218 @@ pub fn read_file(file_path: &Path) -> Result<String> {
     Ok(file_contents)
 }
 
-/// Writes plain bytes to the specified file. Creates a new one if already doesn't exist
-pub fn write_bytes(path: &Path, bytes: &[u8], truncate: bool) -> Result<()> {
+/// Writes plain bytes to the specified file. Creates a new one if already doesn't exist. The write
+/// is atomic, meaning the file will either contain the old or the new data in case of a failure
+pub fn write_bytes(path: &Path, bytes: &[u8]) -> Result<()> {
     log_debug!("Writing bytes to \"{}\"", path.display());
-    let mut file = File::options()
-        .write(true)
-        .create(true)
-        .truncate(truncate)
-        .open(&path)?;
-
-    file.write_all(bytes)?;
-    file.flush()?;
+    write_atomic(path, false, |file| file.write_all(bytes))?;
 
     log_debug!("Wrote {} bytes", bytes.len());
     Ok(())
 }
 
-/// Writes string to the specified file. Creates a new one if already doesn't exist
-pub fn write_file(path: &Path, contents: &str, truncate: bool) -> Result<()> {
-    log_debug!("Writing to \"{}\"", path.display());
-    let mut file = File::options()
-        .write(true)
-        .create(true)
-        .truncate(truncate)
-        .open(&path)?;
+/// Works like `write_bytes`, but a new file is only accessible by the owner. Used for the files
+/// holding the profiles
+pub fn write_private_bytes(path: &Path, bytes: &[u8]) -> Result<()> {
+    log_debug!("Writing bytes to private file \"{}\"", path.display());
+    write_atomic(path, true, |file| file.write_all(bytes))
+}
 
-    file.write_all(contents.as_bytes())?;
-    file.flush()?;
+/// Writes string to the specified file. Creates a new one if already doesn't exist. The write is
+/// atomic, meaning the file will either contain the old or the new data in case of a failure. A new
+/// file is only accessible by the owner, as it's used for the profile store and the config
+pub fn write_private_file(path: &Path, contents: &str) -> Result<()> {
+    log_debug!("Writing to private file \"{}\"", path.display());
+    write_atomic(path, true, |file| file.write_all(contents.as_bytes()))
+}
 
-    Ok(())
-}
\ No newline at end of file
+/// Atomically replaces the specified file with the data written by the provided function. The data
+/// is first written to a temporary file in the same directory, which is synced to the disk and
+/// then renamed over the target file. The parent directory is synced afterward, so the rename is
+/// durable once the function returns. The temporary file is removed if any of the steps fail
+///
+/// The temporary file is only accessible by the owner while it's written. An existing target keeps
+/// its permissions. A new file stays accessible by the owner only if it's private, otherwise it
+/// gets the usual permissions allowed by the umask
+pub fn write_atomic(path: &Path, private: bool, write: impl FnOnce(&mut File) -> Result<()>) -> Result<()> {
+    let temp_path = get_temp_path(path);
+    log_debug!("Writing to temporary file \"{}\"", temp_path.display());
+    let permissions = match fs::metadata(path) {
+        Ok(metadata) => Some(metadata.permissions()),
+        Err(_) if private => None,
+        Err(_) => get_default_permissions()
+    };
+
+    let mut options = File::options();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let result = options
+        .open(&temp_path)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.flush()?;
+            if let Some(permissions) = permissions {
+                file.set_permissions(permissions)?;
+            }
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path))
+        .and_then(|_| sync_parent_dir(path));
+
+    if result.is_err() && temp_path.exists() {
+        fs::remove_file(&temp_path).ok();
+    }
+    result
+}
+
+/// Returns the permissions of a newly created file according to the umask. Nothing has to be
+/// changed on systems without Unix permissions
+fn get_default_permissions() -> Option<fs::Permissions> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        Some(fs::Permissions::from_mode(0o666 & !get_umask()))
+    }
+    #[cfg(not(unix))]
+    None
+}
+
+/// Returns the file mode creation mask of the process. It can only be read by replacing it, so it
+/// is read once, and a restrictive mask is used in the meantime, which cannot expose the files
+/// created by other threads
+#[cfg(unix)]
+fn get_umask() -> u32 {
+    static UMASK: OnceLock<u32> = OnceLock::new();
+    *UMASK.get_or_init(|| {
+        // SAFETY: umask only replaces the mask of the process and cannot fail
+        let umask = unsafe { libc::umask(0o077) };
+        // SAFETY: same as above, restoring the original mask
+        unsafe { libc::umask(umask) };
+        umask as u32
+    })
+}
+
+/// Returns a unique path for a hidden temporary file located next to the specified file
+fn get_temp_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().unwrap_or(OsStr::new("databoxer")).to_string_lossy();
+    path.with_file_name(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()))
+}
+
+/// Syncs the directory containing the specified file, making its latest entry changes durable.
+/// Directories cannot be opened for syncing on Windows, so nothing is done there
+fn sync_parent_dir(path: &Path) -> Result<()> {
+    if cfg!(target_os = "windows") {
+        return Ok(())
+    }
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new(".")
+    };
+    File::open(parent)?.sync_all()
+}
+
+/// Overwrites the contents of the specified file with random bytes, syncs the changes to the disk
+/// and deletes the file. Makes it harder to recover the original data from the disk
+pub fn secure_remove(path: &Path) -> Result<()> {
+    log_debug!("Securely removing \"{}\"", path.display());
+    let mut file = File::options().write(true).open(path)?;
+    let mut remaining = file.metadata()?.len() as usize;
+    let mut buffer = vec![0u8; remaining.min(64 * 1024)];
+    let mut rng = rand::rng();
+
+    while remaining > 0 {
+        let chunk_len = remaining.min(buffer.len());
+        rng.fill_bytes(&mut buffer[..chunk_len]);
+        file.write_all(&buffer[..chunk_len])?;
+        remaining -= chunk_len;
+    }
+    file.sync_all()?;
+    drop(file);
+
+    fs::remove_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::io::{Error, ErrorKind};
+    use super::*;
+
+    /// Creates a new empty directory for the test inside the system temporary directory
+    fn create_test_dir() -> PathBuf {
+        let dir = env::temp_dir().join(format!("databoxer-io-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("Cannot create test directory");
+        dir
+    }
+
+    #[test]
+    fn test_write_replaces_file() -> Result<()> {
+        let dir = create_test_dir();
+        let path = dir.join("file.txt");
+
+        write_bytes(&path, b"old contents")?;
+        write_bytes(&path, b"new")?;
+        assert_eq!(read_file(&path)?, "new");
+        assert_eq!(fs::read_dir(&dir)?.count(), 1, "Temporary file was left behind");
+
+        fs::remove_dir_all(dir)
+    }
+
+    #[test]
+    fn test_interrupted_write() -> Result<()> {
+        let dir = create_test_dir();
+        let path = dir.join("file.txt");
+        write_bytes(&path, b"old contents")?;
+
+        let result = write_atomic(&path, false, |file| {
+            file.write_all(b"partial")?;
+            Err(Error::new(ErrorKind::Interrupted, "simulated crash"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(read_file(&path)?, "old contents", "Original file was corrupted");
+        assert_eq!(fs::read_dir(&dir)?.count(), 1, "Temporary file was left behind");
+
+        fs::remove_dir_all(dir)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_permissions() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = create_test_dir();
+        let existing_path = dir.join("existing.txt");
+        let new_path = dir.join("new.txt");
+        let private_path = dir.join("private.txt");
+
+        write_bytes(&existing_path, b"old contents")?;
+        fs::set_permissions(&existing_path, fs::Permissions::from_mode(0o640))?;
+        write_bytes(&existing_path, b"new")?;
+        write_bytes(&new_path, b"new")?;
+        write_private_file(&private_path, "new")?;
+
+        assert_eq!(fs::metadata(&existing_path)?.permissions().mode() & 0o777, 0o640, "Permissions of the replaced file changed");
+        assert_eq!(fs::metadata(&new_path)?.permissions().mode() & 0o777, 0o666 & !get_umask(), "New file ignores the umask");
+        assert_eq!(fs::metadata(&private_path)?.permissions().mode() & 0o777, 0o600, "Private file is accessible by others");
+
+        fs::remove_dir_all(dir)
+    }
+
+    #[test]
+    fn test_interrupted_new_file() -> Result<()> {
+        let dir = create_test_dir();
+        let path = dir.join("file.txt");
+
+        let result = write_atomic(&path, false, |_| {
+            Err(Error::new(ErrorKind::Interrupted, "simulated crash"))
+        });
+
+        assert!(result.is_err());
+        assert!(!path.exists(), "Partially written file was created");
+
+        fs::remove_dir_all(dir)
+    }
+}
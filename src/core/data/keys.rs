@@ -1,23 +1,21 @@
 //! Contains wrapper functions above profiles to get and set current profile's key
 
 use crate::log_debug;
+use crate::core::encryption::secret::Secret;
 use crate::{Key, Result};
 use crate::core::data;
 
 /// Gets the key for the current profile
-pub fn get_key(password: &str) -> Result<Key> {
+pub fn get_key(password: &str) -> Result<Secret<Key>> {
     log_debug!("Getting encryption key from current profile");
     let mut profiles = data::get_profiles()?;
-    let profile = profiles.get_current_profile()?;
-    let key = profile.get_key(password)?;
-    Ok(key)
+    profiles.authenticate_current(|profile| profile.get_key(password))
 }
 
 /// Sets the key for the current profile
-pub fn set_key(password: &str, new_key: Key) -> Result<()> {
+pub fn set_key(password: &str, new_key: &Key) -> Result<()> {
     log_debug!("Setting a new encryption key for current profile");
     let mut profiles = data::get_profiles()?;
-    let profile = profiles.get_current_profile()?;
-    profile.set_key(password, new_key)?;
+    profiles.authenticate_current(|profile| profile.set_key(password, new_key))?;
     profiles.save()
 }
\ No newline at end of file
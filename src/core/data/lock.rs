@@ -0,0 +1,78 @@
+//! Contains the advisory file locking used to keep concurrent Databoxer processes from overwriting
+//! each other's changes
+//!
+//! A lock is held on a separate lock file for the whole read-modify-write cycle of the data it
+//! guards. Lock files are never deleted, as removing a file another process is waiting on would
+//! let two processes hold the lock at the same time
+
+use std::fs::{File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::core::error::{Error, IOErrorKind};
+use crate::{log_debug, log_info, Result};
+
+/// Delay between the attempts to acquire a held lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Exclusive lock on a lock file, which is released when dropped
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Acquires the exclusive lock on the provided lock file, creating the file if needed. Waits
+    /// for other processes to release it for at most the provided timeout
+    pub fn acquire(path: &Path, timeout: Duration) -> Result<Self> {
+        log_debug!("Acquiring the lock \"{}\"", path.display());
+        let file = File::options().create(true).truncate(false).write(true).open(path)?;
+        let start_time = Instant::now();
+        let mut waiting = false;
+
+        loop {
+            match file.try_lock() {
+                Ok(_) => break,
+                Err(TryLockError::WouldBlock) if start_time.elapsed() < timeout => {
+                    if !waiting {
+                        log_info!("Waiting for another process to release \"{}\"", path.display());
+                        waiting = true;
+                    }
+                    thread::sleep(RETRY_INTERVAL);
+                },
+                Err(TryLockError::WouldBlock) => return Err(Error::IOError(IOErrorKind::Locked(path.to_path_buf()))),
+                Err(TryLockError::Error(err)) => return Err(err.into())
+            }
+        }
+
+        Ok(FileLock { file, path: path.to_path_buf() })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        log_debug!("Releasing the lock \"{}\"", self.path.display());
+        self.file.unlock().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_timeout() -> Result<()> {
+        let lock_path = std::env::temp_dir().join(format!("databoxer-{}.lock", uuid::Uuid::new_v4()));
+
+        let lock = FileLock::acquire(&lock_path, Duration::ZERO)?;
+        let result = FileLock::acquire(&lock_path, Duration::from_millis(200));
+        assert!(matches!(result, Err(crate::Error::IOError(_))), "Lock was acquired twice");
+
+        drop(lock);
+        assert!(FileLock::acquire(&lock_path, Duration::ZERO).is_ok(), "Released lock was not acquired");
+
+        std::fs::remove_file(&lock_path)?;
+        Ok(())
+    }
+}
@@ -1,30 +1,109 @@
 //! Contains everything related to outside data manipulations, filesystem and operating system
 //! interactions
 
-use crate::{Result, log_debug};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::io::IsTerminal;
+use std::time::Duration;
+use crate::app::{self, AppMode};
+use crate::cli::prompts;
+use crate::core::encryption::secret::Secret;
+use crate::{Result, log_debug, new_err};
 use profile::DataboxerProfiles;
 use config::DataboxerConfig;
+use lock::FileLock;
 
 pub mod profile;
 pub mod keys;
 pub mod config;
 pub mod os;
+pub mod store;
+pub mod lock;
+pub mod tracking;
 pub mod io;
 mod auth;
 
-/// Fetches the Databoxer profiles by importing it from the file on the disk. Will return an error in
-/// case of the operation failing
+/// Name of the environment variable which can hold the master password of the sealed profile store
+pub const MASTER_PASSWORD_VAR: &str = "DATABOXER_MASTER_PASSWORD";
+
+/// Path to the config file used instead of the one in the program's config directory
+static CONFIG_FILE: OnceLock<PathBuf> = OnceLock::new();
+/// Master password of the sealed profile store, either provided by the application or prompted for
+/// once per process
+static MASTER_PASSWORD: OnceLock<Secret<String>> = OnceLock::new();
+/// How long to wait for another process to release a lock, loaded from the config once per process
+static LOCK_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Fetches the Databoxer profiles by importing it from the file on the disk. The profile store stays
+/// locked for other processes until the returned profiles are dropped. Will return an error in case
+/// of the operation failing
 pub fn get_profiles() -> Result<DataboxerProfiles> {
     log_debug!("Getting Databoxer profiles");
     let data_directory = os::get_data_dir()?;
-    DataboxerProfiles::import(data_directory)
+    DataboxerProfiles::import(data_directory, get_lock_timeout())
 }
 
-/// Fetches the Databoxer config by importing it from the file on the disk. Will return an error in
-/// case of the operation failing
-#[allow(dead_code)]
+/// Fetches the Databoxer config by layering the global config file, the project config file of the
+/// working directory and the environment variables. Will return an error in case of the operation
+/// failing
 pub fn get_config() -> Result<DataboxerConfig> {
-    log_debug!("Getting Databoxer profiles");
-    let config_directory = os::get_config_dir()?;
-    DataboxerConfig::import(config_directory)
+    log_debug!("Getting Databoxer config");
+    DataboxerConfig::import(get_config_file()?, config::find_current_project_file(), std::env::vars())
+}
+
+/// Sets the config file to be used instead of the one in the program's config directory. Can only
+/// be set once, any later calls are ignored
+pub fn set_config_file(config_file: PathBuf) {
+    CONFIG_FILE.set(config_file).ok();
+}
+
+/// Sets the master password used to open the sealed profile store. Can only be set once, any later
+/// calls are ignored
+pub fn set_master_password(master_password: &str) {
+    MASTER_PASSWORD.set(Secret::new(master_password.to_string())).ok();
+}
+
+/// Returns the master password of the sealed profile store. Taken from the one set with
+/// `set_master_password`, the `DATABOXER_MASTER_PASSWORD` environment variable or prompted for when
+/// running in a terminal. Errors if the master password is not available
+pub fn get_master_password() -> Result<Secret<String>> {
+    if let Some(master_password) = MASTER_PASSWORD.get() {
+        return Ok(master_password.clone())
+    }
+    if let Ok(master_password) = std::env::var(MASTER_PASSWORD_VAR) {
+        return Ok(Secret::new(master_password))
+    }
+    if *app::get_app_mode() != AppMode::CLI || !std::io::stdin().is_terminal() {
+        return Err(new_err!(ProfileError: StoreSealed))
+    }
+
+    let master_password = prompts::prompt_password("Please enter the master password of the profile store:");
+    Ok(MASTER_PASSWORD.get_or_init(|| master_password).clone())
+}
+
+/// Locks the config file in use for other processes until the returned lock is dropped. Has to be
+/// held while the config file is modified
+pub fn lock_config() -> Result<FileLock> {
+    let config_file = get_config_file()?;
+    FileLock::acquire(&PathBuf::from(format!("{}.lock", config_file.display())), get_lock_timeout())
+}
+
+/// Returns how long to wait for another process to release a lock. The config is only loaded for
+/// the first lock of the process. Falls back to the default timeout if the config cannot be loaded,
+/// so a broken config can still be fixed
+pub fn get_lock_timeout() -> Duration {
+    *LOCK_TIMEOUT.get_or_init(|| {
+        let lock_timeout = get_config()
+            .map(|config| config.storage.lock_timeout)
+            .unwrap_or_else(|_| config::StorageConfig::default().lock_timeout);
+        Duration::from_secs(lock_timeout)
+    })
+}
+
+/// Returns the path to the config file in use
+pub fn get_config_file() -> Result<PathBuf> {
+    match CONFIG_FILE.get() {
+        Some(config_file) => Ok(config_file.clone()),
+        None => Ok(os::get_config_dir()?.join(config::CONFIG_FILE_NAME))
+    }
 }
@@ -1,22 +1,51 @@
 //! Contains functions which rely on operating system with their functionality and return values
 //! depending on it
 
-use std::path::PathBuf;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::{env, fs};
+use std::fs::{File, FileTimes, Metadata};
+use std::time::SystemTime;
 use crate::{new_err, Result};
 
-/// Returns the application data directory based on the OS. Used for storing profiles and other
-/// information for program's functionality which is not meant to be edited by the user
-pub fn get_data_dir() -> Result<PathBuf> {
-    let mut data_dir = get_env_home()?;
+/// Environment variable overriding the data directory
+pub const DATA_DIR_VAR: &str = "DATABOXER_DATA_DIR";
+/// Environment variable overriding the config directory
+pub const CONFIG_DIR_VAR: &str = "DATABOXER_CONFIG_DIR";
+/// Name of the file which enables the portable mode when placed beside the executable
+pub const PORTABLE_MARKER_NAME: &str = "databoxer.portable";
 
-    if cfg!(target_os = "windows") {
-        data_dir.push("Databoxer/Data/");
-    } else if cfg!(target_os = "macos") {
-        data_dir.push("Library/Application Support/Databoxer/");
-    } else { // Assuming Linux or other Unix-like OS
-        data_dir.push(".local/share/databoxer/");
-    }
+/// Data directory provided with the `--data-dir` flag, which takes precedence over the rest
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Sets the data directory to be used instead of the default one. Can only be set once, any later
+/// calls are ignored
+pub fn set_data_dir(data_dir: PathBuf) {
+    DATA_DIR.set(data_dir).ok();
+}
+
+/// Returns the application data directory. Used for storing profiles and other information for
+/// program's functionality which is not meant to be edited by the user
+///
+/// Looked up in order: the directory set with `set_data_dir`, the `DATABOXER_DATA_DIR`
+/// environment variable, the `data` directory beside the executable in the portable mode,
+/// `$XDG_DATA_HOME` on Linux and the default directory based on the OS
+pub fn get_data_dir() -> Result<PathBuf> {
+    let data_dir = match DATA_DIR.get() {
+        Some(data_dir) => data_dir.clone(),
+        None => resolve_dir(DATA_DIR_VAR, "data", "XDG_DATA_HOME", || {
+            let mut data_dir = get_env_home()?;
+            if cfg!(target_os = "windows") {
+                data_dir.push("Databoxer/Data/");
+            } else if cfg!(target_os = "macos") {
+                data_dir.push("Library/Application Support/Databoxer/");
+            } else { // Assuming Linux or other Unix-like OS
+                data_dir.push(".local/share/databoxer/");
+            }
+            Ok(data_dir)
+        })?
+    };
 
     if !data_dir.exists() {
         fs::create_dir_all(&data_dir)?;
@@ -24,18 +53,24 @@ pub fn get_data_dir() -> Result<PathBuf> {
     Ok(data_dir)
 }
 
-/// Returns the application config directory based on the OS. Used for storing configuration files
-/// which can be edited by user to change program's functionality
+/// Returns the application config directory. Used for storing configuration files which can be
+/// edited by user to change program's functionality
+///
+/// Looked up in order: the `DATABOXER_CONFIG_DIR` environment variable, the `config` directory
+/// beside the executable in the portable mode, `$XDG_CONFIG_HOME` on Linux and the default
+/// directory based on the OS
 pub fn get_config_dir() -> Result<PathBuf> {
-    let mut config_dir = get_env_home()?;
-    
-    if cfg!(target_os = "windows") {
-        config_dir.push("Databoxer/Config/");
-    } else if cfg!(target_os = "macos") {
-        config_dir.push("Library/Preferences/Databoxer/");
-    } else { // Assuming Linux or other Unix-like OS
-        config_dir.push(".config/databoxer/");
-    }
+    let config_dir = resolve_dir(CONFIG_DIR_VAR, "config", "XDG_CONFIG_HOME", || {
+        let mut config_dir = get_env_home()?;
+        if cfg!(target_os = "windows") {
+            config_dir.push("Databoxer/Config/");
+        } else if cfg!(target_os = "macos") {
+            config_dir.push("Library/Preferences/Databoxer/");
+        } else { // Assuming Linux or other Unix-like OS
+            config_dir.push(".config/databoxer/");
+        }
+        Ok(config_dir)
+    })?;
 
     if !config_dir.exists() {
         fs::create_dir_all(&config_dir)?;
@@ -43,6 +78,45 @@ pub fn get_config_dir() -> Result<PathBuf> {
     Ok(config_dir)
 }
 
+/// Returns the directory beside the executable if the portable mode is enabled, which is done by
+/// placing the `databoxer.portable` file there
+pub fn get_portable_dir() -> Option<PathBuf> {
+    let executable = env::current_exe().ok()?;
+    let directory = executable.parent()?;
+
+    match directory.join(PORTABLE_MARKER_NAME).is_file() {
+        true => Some(directory.to_path_buf()),
+        false => None
+    }
+}
+
+/// Picks the directory from the environment variable override, the portable directory, the XDG
+/// base directory (Linux and other Unix-like OS only) or the provided default, in that order
+fn resolve_dir(
+    override_var: &str,
+    portable_subdir: &str,
+    xdg_var: &str,
+    default: impl FnOnce() -> Result<PathBuf>
+) -> Result<PathBuf> {
+    if let Some(directory) = env::var_os(override_var).filter(|value| !value.is_empty()) {
+        return Ok(PathBuf::from(directory))
+    }
+    if let Some(portable_dir) = get_portable_dir() {
+        return Ok(portable_dir.join(portable_subdir))
+    }
+
+    let uses_xdg = !cfg!(target_os = "windows") && !cfg!(target_os = "macos");
+    if uses_xdg {
+        // relative paths are invalid according to the XDG base directory specification
+        let xdg_home = env::var_os(xdg_var).map(PathBuf::from).filter(|path| path.is_absolute());
+        if let Some(xdg_home) = xdg_home {
+            return Ok(xdg_home.join("databoxer"))
+        }
+    }
+
+    default()
+}
+
 /// Returns the "Home" environment variable based on the OS for later file storage. For windows, it
 /// is $APPDATA, for others it is $HOME
 fn get_env_home() -> Result<PathBuf> {
@@ -59,4 +133,94 @@ fn get_env_home() -> Result<PathBuf> {
 
     let config_dir = PathBuf::from(home_path);
     Ok(config_dir)
-}
\ No newline at end of file
+}
+
+/// Returns the Unix permission mode bits of the file. Always `None` on other systems
+pub fn get_permissions(metadata: &Metadata) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        Some(metadata.permissions().mode())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Returns the Unix user and group id of the file owner. Always `None` on other systems
+pub fn get_owner(metadata: &Metadata) -> Option<(u32, u32)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((metadata.uid(), metadata.gid()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Sets the Unix permission mode bits of the file. Does nothing on other systems
+pub fn set_permissions(path: &Path, mode: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+    }
+    Ok(())
+}
+
+/// Sets the Unix owner of the file. Usually requires elevated privileges. Does nothing on other
+/// systems
+pub fn set_owner(path: &Path, uid: u32, gid: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::chown(path, Some(uid), Some(gid))?;
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, uid, gid);
+    }
+    Ok(())
+}
+
+/// Sets the modify and access times of the file. Times which are `None` are left unchanged
+pub fn set_times(path: &Path, modify_time: Option<SystemTime>, access_time: Option<SystemTime>) -> Result<()> {
+    let mut times = FileTimes::new();
+    if let Some(modify_time) = modify_time {
+        times = times.set_modified(modify_time);
+    }
+    if let Some(access_time) = access_time {
+        times = times.set_accessed(access_time);
+    }
+
+    File::options().write(true).open(path)?.set_times(times)?;
+    Ok(())
+}
+
+/// Returns all extended attributes of the file as name and value pairs. On Linux this includes
+/// POSIX ACLs. Errors if the platform or the filesystem doesn't support extended attributes
+pub fn get_extended_attributes(path: &Path) -> Result<Vec<(OsString, Vec<u8>)>> {
+    let mut attributes = Vec::new();
+
+    for name in xattr::list(path)? {
+        if let Some(value) = xattr::get(path, &name)? {
+            attributes.push((name, value));
+        }
+    }
+    Ok(attributes)
+}
+
+/// Sets an extended attribute of the file. Errors if the platform or the filesystem doesn't
+/// support extended attributes or there are not enough privileges for the attribute
+pub fn set_extended_attribute(path: &Path, name: &OsStr, value: &[u8]) -> Result<()> {
+    xattr::set(path, name, value)?;
+    Ok(())
+}
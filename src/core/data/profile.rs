@@ -1,8 +1,8 @@
 //! Module containing everything related to Databoxer profile management.
 //! 
-//! Provides a base struct `DataboxerProfiles` used for holding information about user's profiles
-//! which is represented as a `profiles.toml` file on the disk, which is located in the program's 
-//! default data directory. 
+//! Provides a base struct `DataboxerProfiles` used for holding information about user's profiles,
+//! which are stored in the program's data directory by one of the backends from the `store`
+//! module.
 //! 
 //! Also contains a `Profile` struct which is used for storing information
 //! about particular user profile. Each profile consists of unique name, password and key with its
@@ -10,70 +10,58 @@
 //! time, but each has to have a unique name. `Key` is generated with the creation of the profile
 //! which it belongs to. Password is also hashed automatically on creation and stored in that form
 //! on the disk
+//!
+//! Failed authentication attempts are recorded for each profile, so repeated guessing of the
+//! password is slowed down by an exponentially growing delay and can lock the profile out for a
+//! while after reaching the threshold set in the config
+//!
+//! Each profile also keeps the registry of the boxfiles it produced (see the `tracking` module)
 
 use super::auth;
-use super::io::{read_file, write_file};
+use super::lock::FileLock;
+use super::store::{self, ProfileStore, ProfilesData};
+use super::tracking::TrackedFile;
 use crate::core::encryption::cipher;
-use crate::{log_debug, log_info, new_err, Key, Nonce, Result};
+use crate::core::encryption::secret::Secret;
+use crate::core::error::{Error, ProfileErrorKind};
+use crate::{log_debug, log_warn, new_err, Key, Nonce, Result};
 use serde::{Deserialize, Serialize};
-use std::io::{self};
+use zeroize::Zeroizing;
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 
-/// Name of the file which stores all the profile data
-const PROFILES_FILE_NAME: &str = "profiles.json";
+/// Number of failed authentication attempts allowed before the back-off delay is applied
+const FREE_ATTEMPTS: u32 = 3;
+/// Maximum delay between authentication attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
 
-/// Struct holding all the needed profile information for the program. Saved on the disk as a JSON
-/// file
-#[derive(Serialize, Deserialize, Debug)]
+/// Struct holding all the needed profile information for the program. Saved on the disk by the
+/// profile store it was imported from, which stays locked for other processes while it exists
+#[derive(Debug)]
 pub struct DataboxerProfiles {
-    current_profile: Option<String>,
-    profiles: Vec<Profile>,
-    #[serde(skip)]
-    file_path: PathBuf
+    data: ProfilesData,
+    store: Box<dyn ProfileStore>,
+    _lock: FileLock,
 }
 
 /// Object-driven approach
 impl DataboxerProfiles {
-    /// Imports self from the stored "profiles.json" file in the program's data directory. In case
-    /// of the file missing, generates a new object with default empty values
-    pub fn import(data_directory: PathBuf) -> Result<Self> {
+    /// Imports self from the profile store in the program's data directory after locking it,
+    /// waiting for at most the provided timeout for other processes to release it. In case of
+    /// nothing being stored yet, generates a new object with default empty values
+    pub fn import(data_directory: PathBuf, lock_timeout: Duration) -> Result<Self> {
         log_debug!("Importing Databoxer profiles");
-        let profiles_file = data_directory.join(PROFILES_FILE_NAME);
-
-        let profiles = match read_file(&profiles_file) {
-            Ok(file_data) => {
-                let mut profiles: DataboxerProfiles = serde_json::from_str(&file_data)?;
-                profiles.file_path = profiles_file;
-                profiles
-            },
-            Err(err) => {
-                if err.kind() == io::ErrorKind::NotFound {
-                    log_info!("\"profiles.json\" file doesn't exist. Generating new profiles data");
-                    Self::new(profiles_file)
-                } else {
-                    return Err(err.into());
-                }
-            }
-        };
-
-        Ok(profiles)
-    }
+        let lock = store::lock(&data_directory, lock_timeout)?;
+        let store = store::open(&data_directory)?;
+        let data = store.load()?;
 
-    /// Bare-minimum constructor to use in case of file not being available to import from
-    fn new(
-        file_path: PathBuf
-    ) -> Self {
-        DataboxerProfiles {
-            current_profile: None,
-            profiles: vec![],
-            file_path
-        }
+        Ok(DataboxerProfiles { data, store, _lock: lock })
     }
 
     /// Returns currently selected profile data
     pub fn get_current_profile(&mut self) -> Result<&mut Profile> {
         log_debug!("Getting current profile");
-        let current_profile = self.current_profile.clone();
+        let current_profile = self.data.current_profile.clone();
 
         let profile = match current_profile {
             None => return Err(new_err!(ProfileError: NotSelected)),
@@ -88,7 +76,54 @@ impl DataboxerProfiles {
     /// Returns a list of currently available profiles
     pub fn get_profiles(&self) -> &Vec<Profile> {
         log_debug!("Getting all available profiles");
-        &self.profiles
+        &self.data.profiles
+    }
+
+    /// Runs the provided operation, which authenticates with the password, on the profile with
+    /// provided name. Refuses to run it while the profile is throttled or locked out. Failed
+    /// authentications are recorded and saved, while a successful one resets the record
+    pub fn authenticate<T>(
+        &mut self,
+        profile_name: &str,
+        operation: impl FnOnce(&mut Profile) -> Result<T>
+    ) -> Result<T> {
+        let config = super::get_config()?.general;
+        let lockout_time = Duration::from_secs(config.lockout_time);
+
+        let profile = self.find_profile(profile_name)?;
+        if let Some(wait) = profile.get_throttle(config.lockout_threshold, lockout_time) {
+            log_warn!("Profile \"{}\" has {} failed authentication attempts", profile_name, profile.failed_attempts);
+            return Err(new_err!(ProfileError: LockedOut, format!("try again in {} seconds", wait.as_secs() + 1)))
+        }
+
+        let result = operation(profile);
+        match &result {
+            Err(Error::ProfileError(ProfileErrorKind::AuthenticationFailed)) => {
+                profile.failed_attempts += 1;
+                profile.last_failed_attempt = Some(SystemTime::now());
+                log_debug!("Recorded failed authentication attempt #{}", profile.failed_attempts);
+                self.save()?;
+            },
+            Ok(_) if profile.failed_attempts > 0 => {
+                profile.failed_attempts = 0;
+                profile.last_failed_attempt = None;
+                self.save()?;
+            },
+            _ => {}
+        }
+
+        result
+    }
+
+    /// Works like `authenticate` for the currently selected profile
+    pub fn authenticate_current<T>(&mut self, operation: impl FnOnce(&mut Profile) -> Result<T>) -> Result<T> {
+        let profile_name = self.get_current_profile()?.name.clone();
+        self.authenticate(&profile_name, operation)
+    }
+
+    /// Returns whether the profile with provided name is the currently selected one
+    pub fn is_current(&self, profile_name: &str) -> bool {
+        self.data.current_profile.as_deref() == Some(profile_name)
     }
 
     /// Sets the current profile to profile which name was supplied. Returns an error if given
@@ -96,9 +131,8 @@ impl DataboxerProfiles {
     pub fn set_current(&mut self, password: &str, profile_name: &str) -> Result<()> {
         log_debug!("Setting current profile to \"{}\"", profile_name);
 
-        let profile = self.find_profile(profile_name)?;
-        profile.verify_password(password)?;
-        self.current_profile = Some(profile_name.to_string());
+        self.authenticate(profile_name, |profile| profile.verify_password(password))?;
+        self.data.current_profile = Some(profile_name.to_string());
         self.save()?;
 
         log_debug!("Set current profile to \"{}\"", profile_name);
@@ -109,15 +143,15 @@ impl DataboxerProfiles {
     pub fn delete_profile(&mut self, profile_password: &str, profile_name: &str) -> Result<()> {
         log_debug!("Trying to delete a profile with name \"{}\"", profile_name);
 
-        for (i, profile) in self.profiles.iter().enumerate() {
+        self.authenticate(profile_name, |profile| profile.verify_password(profile_password))?;
+        for (i, profile) in self.data.profiles.iter().enumerate() {
             if profile.name == profile_name {
-                profile.verify_password(profile_password)?;
-                self.profiles.remove(i);
-                self.current_profile = {
-                    if self.profiles.is_empty() {
+                self.data.profiles.remove(i);
+                self.data.current_profile = {
+                    if self.data.profiles.is_empty() {
                         None
                     } else {
-                        Some(self.profiles.get(0).unwrap().name.clone())
+                        Some(self.data.profiles.first().unwrap().name.clone())
                     }
                 };
                 self.save()?;
@@ -129,7 +163,7 @@ impl DataboxerProfiles {
         Err(new_err!(ProfileError: NotFound, profile_name))
     }
 
-    /// Saves provided profile data to profiles file. Updates existing profile or creates a new one,
+    /// Saves provided profile data to the profile store. Updates existing profile or creates a new one,
     /// if it doesn't already exist
     #[allow(dead_code)]
     pub fn save_profile(&mut self, profile: Profile) -> Result<()> {
@@ -137,18 +171,18 @@ impl DataboxerProfiles {
 
         let profile_name = profile.name.clone();
 
-        if self.profiles.is_empty() {
-            self.profiles.push(profile);
-            self.current_profile = Some(profile_name);
+        if self.data.profiles.is_empty() {
+            self.data.profiles.push(profile);
+            self.data.current_profile = Some(profile_name);
         } else {
-            for i in 0..self.profiles.len() {
-                if self.profiles[i].name == profile_name {
-                    self.profiles.insert(i, profile);
+            for i in 0..self.data.profiles.len() {
+                if self.data.profiles[i].name == profile_name {
+                    self.data.profiles.insert(i, profile);
                     break;
                 }
 
-                if i == self.profiles.len() - 1 {
-                    self.profiles.push(profile);
+                if i == self.data.profiles.len() - 1 {
+                    self.data.profiles.push(profile);
                     break;
                 }
             }
@@ -158,7 +192,7 @@ impl DataboxerProfiles {
         Ok(())
     }
 
-    /// Adds a new profile to the profiles file. Errors if the profile already exists, as this
+    /// Adds a new profile to the profile store. Errors if the profile already exists, as this
     /// functions only accepts new profiles
     pub fn new_profile(&mut self, profile: Profile) -> Result<()> {
         log_debug!("Adding a new profile: {:?}", &profile);
@@ -167,7 +201,7 @@ impl DataboxerProfiles {
         if self.find_profile(&profile_name).is_ok() {
             return Err(new_err!(ProfileError: AlreadyExists, profile_name));
         }
-        self.profiles.push(profile);
+        self.data.profiles.push(profile);
 
         self.save()?;
         Ok(())
@@ -177,7 +211,7 @@ impl DataboxerProfiles {
     pub fn find_profile(&mut self, profile_name: &str) -> Result<&mut Profile> {
         log_debug!("Searching for profile with name \"{}\"", profile_name);
 
-        for profile in &mut self.profiles {
+        for profile in &mut self.data.profiles {
             if profile.name == profile_name {
                 return Ok(profile)
             }
@@ -186,13 +220,9 @@ impl DataboxerProfiles {
         Err(new_err!(ProfileError: NotFound, profile_name))
     }
 
-    /// Writes to the profile data file. Overwrites old data
+    /// Writes the profile data to the profile store. Overwrites old data
     pub fn save(&self) -> Result<()> {
-        log_debug!("Saving profiles data to \"profiles.json\"");
-        let json_data = serde_json::to_string_pretty(&self)?;
-
-        write_file(&self.file_path, &json_data, true)?;
-        Ok(())
+        self.store.save(&self.data)
     }
 }
 
@@ -202,11 +232,20 @@ pub struct Profile {
     /// Name of the profile
     pub name: String,
     /// Profile's password stored in a hashed form
-    password_hash: String,
+    pub(in crate::core::data) password_hash: String,
     /// Per-profile nonce used to perform encryption operations on profile's key
-    nonce: Nonce,
+    pub(in crate::core::data) nonce: Nonce,
     /// Profile's encryption key stored in an encrypted format
-    key: Vec<u8>,
+    pub(in crate::core::data) key: Vec<u8>,
+    /// Number of failed authentication attempts since the last successful one
+    #[serde(default)]
+    pub(in crate::core::data) failed_attempts: u32,
+    /// Time of the last failed authentication attempt
+    #[serde(default)]
+    pub(in crate::core::data) last_failed_attempt: Option<SystemTime>,
+    /// Boxfiles produced by the profile
+    #[serde(default)]
+    pub(in crate::core::data) tracked_files: Vec<TrackedFile>,
 }
 
 impl Profile {
@@ -215,18 +254,75 @@ impl Profile {
         password: &str
     ) -> Result<Self> {
         let (password_hash, password_key) = auth::hash_password(password)?;
-        let key = cipher::generate_key();
+        let key = Secret::new(cipher::generate_key());
         let nonce = cipher::generate_nonce();
-        let encrypted_key = cipher::encrypt(&password_key, &nonce, &key)?;
+        let encrypted_key = cipher::encrypt(&password_key, &nonce, &*key)?;
 
         Ok(Profile {
             name: name.to_string(),
             key: encrypted_key,
             nonce,
             password_hash,
+            failed_attempts: 0,
+            last_failed_attempt: None,
+            tracked_files: Vec::new(),
         })
     }
 
+    /// Returns the number of failed authentication attempts since the last successful one and the
+    /// time of the last failed attempt
+    pub fn get_failed_attempts(&self) -> (u32, Option<SystemTime>) {
+        (self.failed_attempts, self.last_failed_attempt)
+    }
+
+    /// Returns how long to wait until the next authentication attempt is allowed or `None` if it is
+    /// allowed right away. The delay doubles with every failed attempt after the first few, and the
+    /// profile is locked out for the whole lockout time after reaching the threshold (`0` disables
+    /// the lockout)
+    pub fn get_throttle(&self, lockout_threshold: u32, lockout_time: Duration) -> Option<Duration> {
+        let last_failed_attempt = self.last_failed_attempt?;
+
+        let delay = if lockout_threshold > 0 && self.failed_attempts >= lockout_threshold {
+            lockout_time
+        } else if self.failed_attempts >= FREE_ATTEMPTS {
+            let exponent = (self.failed_attempts - FREE_ATTEMPTS).min(16);
+            Duration::from_secs(1 << exponent).min(MAX_BACKOFF)
+        } else {
+            return None
+        };
+
+        let elapsed = last_failed_attempt.elapsed().unwrap_or_default();
+        delay.checked_sub(elapsed).filter(|wait| !wait.is_zero())
+    }
+
+    /// Returns the boxfiles produced by the profile
+    pub fn get_tracked_files(&self) -> &Vec<TrackedFile> {
+        &self.tracked_files
+    }
+
+    /// Adds the boxfiles to the registry. Replaces the entries of the files previously written to
+    /// the same paths
+    pub fn track_files(&mut self, files: Vec<TrackedFile>) {
+        for file in files {
+            self.tracked_files.retain(|tracked| tracked.path != file.path && tracked.id != file.id);
+            self.tracked_files.push(file);
+        }
+    }
+
+    /// Removes the boxfiles with provided ids from the registry
+    pub fn untrack_files(&mut self, ids: &[String]) {
+        self.tracked_files.retain(|tracked| !ids.contains(&tracked.id));
+    }
+
+    /// Updates the paths of the tracked boxfiles with the ids of the provided entries
+    pub fn move_files(&mut self, files: &[TrackedFile]) {
+        for file in files {
+            if let Some(tracked) = self.tracked_files.iter_mut().find(|tracked| tracked.id == file.id) {
+                tracked.path = file.path.clone();
+            }
+        }
+    }
+
     /// Checks whether the provided password is valid for the profile by verifying it with the hash
     pub fn verify_password(&self, password: &str) -> Result<()> {
         match auth::verify_password(&self.password_hash, password) {
@@ -237,9 +333,9 @@ impl Profile {
 
     /// Sets a new key for the profile. Encrypts provided Key based on password and saves it to the
     /// profile in the encrypted form
-    pub fn set_key(&mut self, password: &str, key: Key) -> Result<()> {
+    pub fn set_key(&mut self, password: &str, key: &Key) -> Result<()> {
         let password_key = auth::get_password_key(&self.password_hash, password)?;
-        let encrypted_key = cipher::encrypt(&password_key, &self.nonce, &key)?;
+        let encrypted_key = cipher::encrypt(&password_key, &self.nonce, key)?;
 
         self.key = encrypted_key;
         Ok(())
@@ -247,11 +343,11 @@ impl Profile {
 
     /// Fetches encryption key for the current profile. Decrypts contained key based on the password
     /// after verifying it and returns it
-    pub fn get_key(&self, password: &str) -> Result<Key> {
-        let encrypted_key = self.key.clone();
+    pub fn get_key(&self, password: &str) -> Result<Secret<Key>> {
         let password_key = auth::get_password_key(&self.password_hash, password)?;
-        let key = cipher::decrypt(&password_key, &self.nonce, &encrypted_key)?.try_into()
-            .map_err(|_| new_err!(InvalidData: InvalidLength, "encryption key"))?;
+        let key_bytes = Zeroizing::new(cipher::decrypt(&password_key, &self.nonce, &self.key)?);
+        let key = Secret::from_slice(&key_bytes)
+            .ok_or_else(|| new_err!(InvalidData: InvalidLength, "encryption key"))?;
 
         Ok(key)
     }
@@ -262,13 +358,35 @@ mod tests {
     use crate::core::data::os;
     use super::*;
 
+    #[test]
+    fn test_throttle() -> Result<()> {
+        let lockout_time = Duration::from_secs(60);
+        let mut profile = Profile::new("test", "my_password123")?;
+        assert!(profile.get_throttle(0, lockout_time).is_none());
+
+        profile.failed_attempts = FREE_ATTEMPTS - 1;
+        profile.last_failed_attempt = Some(SystemTime::now());
+        assert!(profile.get_throttle(0, lockout_time).is_none());
+
+        profile.failed_attempts = FREE_ATTEMPTS + 2;
+        let wait = profile.get_throttle(0, lockout_time).expect("Profile should be throttled");
+        assert!(wait <= Duration::from_secs(4));
+
+        let wait = profile.get_throttle(FREE_ATTEMPTS, lockout_time).expect("Profile should be locked out");
+        assert!(wait > Duration::from_secs(4));
+
+        profile.last_failed_attempt = Some(SystemTime::now() - lockout_time);
+        assert!(profile.get_throttle(FREE_ATTEMPTS, lockout_time).is_none());
+        Ok(())
+    }
+
     #[test]
     #[ignore]
     /// Creates the `profiles.json` file in the program data directory and fills it with default
     /// information
     fn write_default_profiles() {
         let data_directory = os::get_data_dir().expect("Cannot get data directory");
-        let config = DataboxerProfiles::import(data_directory);
+        let config = DataboxerProfiles::import(data_directory, Duration::ZERO);
 
         assert!(config.is_ok())
     }
@@ -0,0 +1,49 @@
+//! Contains the JSON backend of the profile store, which keeps everything in a single file
+
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::data::io::{read_file, write_private_file};
+use crate::{log_debug, log_info, Result};
+use super::{ProfileStore, ProfilesData, StoreKind};
+
+/// Profile store rewriting the whole JSON file on every change
+#[derive(Debug)]
+pub struct JsonStore {
+    file_path: PathBuf,
+}
+
+impl JsonStore {
+    pub fn new(file_path: PathBuf) -> Self {
+        JsonStore { file_path }
+    }
+}
+
+impl ProfileStore for JsonStore {
+    fn kind(&self) -> StoreKind {
+        StoreKind::Json
+    }
+
+    fn path(&self) -> &Path {
+        &self.file_path
+    }
+
+    fn load(&self) -> Result<ProfilesData> {
+        match read_file(&self.file_path) {
+            Ok(file_data) => Ok(serde_json::from_str(&file_data)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                log_info!("\"{}\" file doesn't exist. Generating new profiles data", self.file_path.display());
+                Ok(ProfilesData::default())
+            },
+            Err(err) => Err(err.into())
+        }
+    }
+
+    fn save(&self, data: &ProfilesData) -> Result<()> {
+        log_debug!("Saving profiles data to \"{}\"", self.file_path.display());
+        let json_data = serde_json::to_string_pretty(data)?;
+
+        // the file is replaced atomically, so it always holds either the old or the new data
+        write_private_file(&self.file_path, &json_data)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+//! Contains the storage backends of the profiles
+//!
+//! `DataboxerProfiles` keeps the profiles in memory and uses a `ProfileStore` to load and save
+//! them. The original backend is a single JSON file, which is rewritten on every change. The SQLite
+//! backend keeps the profiles in a database with a versioned schema and saves every change in a
+//! single transaction. The SQLite backend is used as soon as its database exists in the data
+//! directory, which is created by migrating the JSON file with `migrate`
+//!
+//! Whichever backend is used, the store is guarded by the `profiles.lock` file, which has to be
+//! locked for the whole read-modify-write cycle, so concurrent processes don't lose each other's
+//! changes
+//!
+//! Either backend can be sealed with a master password using `seal`, which replaces its file with
+//! the encrypted `profiles.sealed` file. The sealed store is then used transparently as long as the
+//! master password is available, until it is restored with `unseal`
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::core::data::io::secure_remove;
+use crate::core::data::lock::FileLock;
+use crate::core::data::profile::Profile;
+use crate::{log_debug, log_info, new_err, Result};
+use json::JsonStore;
+use sealed::SealedStore;
+use sqlite::SqliteStore;
+
+mod json;
+mod sqlite;
+mod sealed;
+
+/// Name of the file of the JSON backend
+pub const JSON_FILE_NAME: &str = "profiles.json";
+/// Name of the database of the SQLite backend
+pub const SQLITE_FILE_NAME: &str = "profiles.db";
+/// Name of the encrypted file of the sealed store
+pub const SEALED_FILE_NAME: &str = "profiles.sealed";
+/// Name of the lock file guarding the store
+pub const LOCK_FILE_NAME: &str = "profiles.lock";
+/// Extension added to the JSON file once it is migrated, as it is kept as a backup
+const BACKUP_EXTENSION: &str = "json.bak";
+
+/// All the data kept in a profile store
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ProfilesData {
+    /// Name of the currently selected profile
+    pub current_profile: Option<String>,
+    /// All profiles in the order they were created
+    pub profiles: Vec<Profile>,
+}
+
+/// Kind of the storage backend
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum StoreKind {
+    Json,
+    Sqlite,
+}
+
+impl fmt::Display for StoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreKind::Json => write!(f, "JSON"),
+            StoreKind::Sqlite => write!(f, "SQLite"),
+        }
+    }
+}
+
+/// Storage backend of the profiles
+pub trait ProfileStore: fmt::Debug {
+    /// Returns the kind of the backend
+    fn kind(&self) -> StoreKind;
+
+    /// Returns the path to the file holding the data
+    fn path(&self) -> &Path;
+
+    /// Loads all the stored data. Returns empty data if nothing was stored yet
+    fn load(&self) -> Result<ProfilesData>;
+
+    /// Replaces the stored data with the provided one. Either all of the changes are saved or none
+    /// of them
+    fn save(&self, data: &ProfilesData) -> Result<()>;
+}
+
+/// Locks the profile store in the provided data directory, waiting for at most the provided timeout
+/// for other processes to release it
+pub fn lock(data_directory: &Path, timeout: Duration) -> Result<FileLock> {
+    FileLock::acquire(&data_directory.join(LOCK_FILE_NAME), timeout)
+}
+
+/// Opens the profile store in the provided data directory. Uses the sealed store if it exists,
+/// which needs the master password, then the SQLite backend if its database exists, and the JSON
+/// backend otherwise
+pub fn open(data_directory: &Path) -> Result<Box<dyn ProfileStore>> {
+    let store: Box<dyn ProfileStore> = match is_sealed(data_directory) {
+        true => {
+            let master_password = super::get_master_password()?;
+            Box::new(SealedStore::open(data_directory.join(SEALED_FILE_NAME), &master_password)?)
+        },
+        false => open_plain(data_directory)
+    };
+    log_debug!("Using the {} profile store at \"{}\"", store.kind(), store.path().display());
+    Ok(store)
+}
+
+/// Returns whether the profile store in the provided data directory is sealed
+pub fn is_sealed(data_directory: &Path) -> bool {
+    data_directory.join(SEALED_FILE_NAME).exists()
+}
+
+/// Opens the unsealed backend in the provided data directory
+fn open_plain(data_directory: &Path) -> Box<dyn ProfileStore> {
+    let database_path = data_directory.join(SQLITE_FILE_NAME);
+
+    match database_path.exists() {
+        true => Box::new(SqliteStore::new(database_path)),
+        false => Box::new(JsonStore::new(data_directory.join(JSON_FILE_NAME)))
+    }
+}
+
+/// Seals the profile store in the provided data directory with the master password. The files of
+/// the unsealed backend, including the backup left by the migration, are securely removed
+pub fn seal(data_directory: &Path, master_password: &str, lock_timeout: Duration) -> Result<()> {
+    let _lock = lock(data_directory, lock_timeout)?;
+    if is_sealed(data_directory) {
+        return Err(new_err!(ProfileError: StoreSealed))
+    }
+
+    let plain_store = open_plain(data_directory);
+    let data = plain_store.load()?;
+    log_info!("Sealing the {} profile store with {} profile(s)", plain_store.kind(), data.profiles.len());
+
+    let sealed_store = SealedStore::create(data_directory.join(SEALED_FILE_NAME), plain_store.kind(), master_password)?;
+    sealed_store.save(&data)?;
+
+    let backup_path = data_directory.join(JSON_FILE_NAME).with_extension(BACKUP_EXTENSION);
+    for path in [plain_store.path(), &backup_path] {
+        if path.exists() {
+            secure_remove(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Restores the unsealed backend the profile store in the provided data directory was sealed from.
+/// Errors if the master password is invalid
+pub fn unseal(data_directory: &Path, master_password: &str, lock_timeout: Duration) -> Result<()> {
+    let _lock = lock(data_directory, lock_timeout)?;
+    if !is_sealed(data_directory) {
+        return Err(new_err!(ProfileError: StoreNotSealed))
+    }
+
+    let sealed_path = data_directory.join(SEALED_FILE_NAME);
+    let sealed_store = SealedStore::open(sealed_path.clone(), master_password)?;
+    let data = sealed_store.load()?;
+    log_info!("Restoring the {} profile store with {} profile(s)", sealed_store.kind(), data.profiles.len());
+
+    let plain_store: Box<dyn ProfileStore> = match sealed_store.kind() {
+        StoreKind::Sqlite => Box::new(SqliteStore::new(data_directory.join(SQLITE_FILE_NAME))),
+        StoreKind::Json => Box::new(JsonStore::new(data_directory.join(JSON_FILE_NAME)))
+    };
+    plain_store.save(&data)?;
+
+    fs::remove_file(&sealed_path)?;
+    Ok(())
+}
+
+/// Converts the JSON store in the provided data directory into the SQLite one. The JSON file is
+/// kept as a backup with the `.json.bak` extension. Returns the number of migrated profiles
+pub fn migrate(data_directory: &Path, lock_timeout: Duration) -> Result<usize> {
+    let _lock = lock(data_directory, lock_timeout)?;
+    if is_sealed(data_directory) {
+        return Err(new_err!(ProfileError: StoreSealed))
+    }
+    let json_path = data_directory.join(JSON_FILE_NAME);
+    let database_path = data_directory.join(SQLITE_FILE_NAME);
+    if database_path.exists() {
+        return Err(new_err!(SerializeError: DatabaseError, format!("\"{}\" already exists, profiles are already migrated", database_path.display())))
+    }
+
+    let data = JsonStore::new(json_path.clone()).load()?;
+    log_info!("Migrating {} profile(s) to the SQLite store", data.profiles.len());
+
+    // the database is moved in place only once it is complete, so an interrupted migration leaves
+    // the JSON store in use
+    let temp_path = PathBuf::from(format!("{}.tmp", database_path.display()));
+    if temp_path.exists() {
+        fs::remove_file(&temp_path)?;
+    }
+    SqliteStore::new(temp_path.clone()).save(&data)?;
+    fs::rename(&temp_path, &database_path)?;
+
+    if json_path.exists() {
+        fs::rename(&json_path, json_path.with_extension(BACKUP_EXTENSION))?;
+    }
+    Ok(data.profiles.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migration() -> Result<()> {
+        let data_directory = std::env::temp_dir().join(format!("databoxer-store-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&data_directory)?;
+
+        let json_store = open(&data_directory)?;
+        assert_eq!(json_store.kind(), StoreKind::Json);
+        json_store.save(&ProfilesData {
+            current_profile: Some("second".to_string()),
+            profiles: vec![Profile::new("first", "first-password")?, Profile::new("second", "second-password")?],
+        })?;
+
+        assert_eq!(migrate(&data_directory, Duration::ZERO)?, 2);
+        assert!(migrate(&data_directory, Duration::ZERO).is_err());
+
+        let sqlite_store = open(&data_directory)?;
+        let data = sqlite_store.load()?;
+        fs::remove_dir_all(&data_directory)?;
+
+        assert_eq!(sqlite_store.kind(), StoreKind::Sqlite);
+        assert_eq!(data.current_profile.as_deref(), Some("second"));
+        assert_eq!(data.profiles.iter().map(|profile| profile.name.as_str()).collect::<Vec<_>>(), ["first", "second"]);
+        data.profiles[0].verify_password("first-password")
+    }
+}
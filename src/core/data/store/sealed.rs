@@ -0,0 +1,144 @@
+//! Contains the sealed profile store, which keeps the data of the JSON or SQLite backend encrypted
+//! with a key derived from a master password
+//!
+//! The whole store is encrypted at once, so nothing about the profiles (not even their names) can
+//! be read from the data directory without the master password. The backend the data was sealed
+//! from is remembered, so it can be restored once the store is unsealed
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+use crate::core::data::auth;
+use crate::core::data::io::{read_bytes, write_private_bytes};
+use crate::core::encryption::cipher;
+use crate::core::encryption::secret::Secret;
+use crate::{log_debug, new_err, Key, Nonce, Result};
+use super::{ProfileStore, ProfilesData, StoreKind};
+
+/// Version of the sealed file format
+const SEALED_VERSION: u8 = 1;
+
+/// Contents of the sealed file
+#[derive(Serialize, Deserialize)]
+struct SealedFile {
+    version: u8,
+    /// Backend the data was sealed from
+    kind: StoreKind,
+    /// Salt used to derive the key from the master password
+    salt: String,
+    nonce: Nonce,
+    /// Encrypted JSON of the profiles data
+    data: Vec<u8>,
+}
+
+/// Profile store encrypting all of the data with the key derived from the master password
+#[derive(Debug)]
+pub struct SealedStore {
+    file_path: PathBuf,
+    kind: StoreKind,
+    salt: String,
+    key: Secret<Key>,
+}
+
+impl SealedStore {
+    /// Creates a new sealed store for the data of the provided backend, which is sealed with the
+    /// master password. Nothing is written until the data is saved
+    pub fn create(file_path: PathBuf, kind: StoreKind, master_password: &str) -> Result<Self> {
+        let salt = auth::generate_salt();
+        let key = auth::derive_key(master_password, &salt)?;
+
+        Ok(SealedStore { file_path, kind, salt, key })
+    }
+
+    /// Opens the existing sealed store with the master password. Errors if the master password is
+    /// invalid
+    pub fn open(file_path: PathBuf, master_password: &str) -> Result<Self> {
+        log_debug!("Opening the sealed profile store \"{}\"", file_path.display());
+        let sealed_file = Self::read_sealed(&file_path)?;
+        let key = auth::derive_key(master_password, &sealed_file.salt)?;
+
+        let store = SealedStore { file_path, kind: sealed_file.kind, salt: sealed_file.salt.clone(), key };
+        store.unseal(&sealed_file)?;
+        Ok(store)
+    }
+
+    /// Reads and deserializes the sealed file
+    fn read_sealed(file_path: &Path) -> Result<SealedFile> {
+        let sealed_file: SealedFile = bincode::deserialize(&read_bytes(file_path)?)
+            .map_err(|err| new_err!(SerializeError: DatabaseError, format!("invalid sealed store: {}", err)))?;
+        if sealed_file.version != SEALED_VERSION {
+            return Err(new_err!(SerializeError: DatabaseError, format!("unsupported sealed store version {}", sealed_file.version)))
+        }
+        Ok(sealed_file)
+    }
+
+    /// Decrypts the data of the sealed file. Fails only if the key is invalid, as the data is
+    /// authenticated
+    fn unseal(&self, sealed_file: &SealedFile) -> Result<ProfilesData> {
+        let json_data = cipher::decrypt(&self.key, &sealed_file.nonce, &sealed_file.data)
+            .map(Zeroizing::new)
+            .map_err(|_| new_err!(ProfileError: InvalidMasterPassword))?;
+        Ok(serde_json::from_slice(&json_data)?)
+    }
+}
+
+impl ProfileStore for SealedStore {
+    /// Returns the kind of the backend the data was sealed from
+    fn kind(&self) -> StoreKind {
+        self.kind
+    }
+
+    fn path(&self) -> &Path {
+        &self.file_path
+    }
+
+    fn load(&self) -> Result<ProfilesData> {
+        self.unseal(&Self::read_sealed(&self.file_path)?)
+    }
+
+    fn save(&self, data: &ProfilesData) -> Result<()> {
+        log_debug!("Sealing profiles data to \"{}\"", self.file_path.display());
+        let json_data = Zeroizing::new(serde_json::to_vec(data)?);
+        let nonce = cipher::generate_nonce();
+
+        let sealed_file = SealedFile {
+            version: SEALED_VERSION,
+            kind: self.kind,
+            salt: self.salt.clone(),
+            nonce,
+            data: cipher::encrypt(&self.key, &nonce, &json_data)?,
+        };
+        let bytes = bincode::serialize(&sealed_file)
+            .map_err(|err| new_err!(SerializeError: DatabaseError, err))?;
+
+        write_private_bytes(&self.file_path, &bytes)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data::profile::Profile;
+
+    #[test]
+    fn test_sealed_store() -> Result<()> {
+        let file_path = std::env::temp_dir().join(format!("databoxer-{}.sealed", uuid::Uuid::new_v4()));
+        let store = SealedStore::create(file_path.clone(), StoreKind::Sqlite, "master-password")?;
+        store.save(&ProfilesData {
+            current_profile: Some("test".to_string()),
+            profiles: vec![Profile::new("test", "my_password123")?],
+        })?;
+
+        let sealed_bytes = std::fs::read(&file_path)?;
+        let wrong_password = SealedStore::open(file_path.clone(), "wrong-password");
+        let data = SealedStore::open(file_path.clone(), "master-password")?.load()?;
+        std::fs::remove_file(&file_path)?;
+
+        assert!(!sealed_bytes.windows(4).any(|window| window == b"test"), "Profile name is readable");
+        assert!(matches!(wrong_password, Err(crate::Error::ProfileError(_))), "Invalid master password was accepted");
+        assert_eq!(data.current_profile.as_deref(), Some("test"));
+        assert_eq!(data.profiles[0].name, "test");
+        Ok(())
+    }
+}
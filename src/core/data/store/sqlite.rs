@@ -0,0 +1,309 @@
+//! Contains the SQLite backend of the profile store
+//!
+//! The schema is versioned with the `user_version` pragma of the database. Every entry of
+//! `MIGRATIONS` upgrades the schema by one version and all pending migrations are applied when the
+//! database is opened, so older databases are upgraded in place
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::core::data::profile::Profile;
+use crate::core::data::tracking::TrackedFile;
+use crate::{log_debug, new_err, Nonce, Result};
+use super::{ProfileStore, ProfilesData, StoreKind};
+
+/// Statements upgrading the schema, where the statement at index `N` upgrades version `N` to `N + 1`
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE profiles (
+        name TEXT PRIMARY KEY NOT NULL,
+        password_hash TEXT NOT NULL,
+        nonce BLOB NOT NULL,
+        key BLOB NOT NULL,
+        failed_attempts INTEGER NOT NULL DEFAULT 0,
+        last_failed_attempt INTEGER
+    );
+    CREATE TABLE settings (
+        name TEXT PRIMARY KEY NOT NULL,
+        value TEXT
+    );",
+    "CREATE TABLE tracked_files (
+        profile TEXT NOT NULL,
+        id TEXT NOT NULL,
+        path TEXT NOT NULL,
+        original_name TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        encrypt_time INTEGER NOT NULL,
+        key_id TEXT NOT NULL,
+        PRIMARY KEY (profile, id)
+    );",
+];
+/// Name of the setting holding the currently selected profile
+const CURRENT_PROFILE_SETTING: &str = "current_profile";
+
+/// Profile store keeping the profiles in an SQLite database
+#[derive(Debug)]
+pub struct SqliteStore {
+    file_path: PathBuf,
+}
+
+impl SqliteStore {
+    pub fn new(file_path: PathBuf) -> Self {
+        SqliteStore { file_path }
+    }
+
+    /// Opens the database and upgrades its schema to the latest version
+    fn connect(&self) -> Result<Connection> {
+        log_debug!("Opening the profile database \"{}\"", self.file_path.display());
+        let mut connection = Connection::open(&self.file_path)?;
+
+        let version: usize = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > MIGRATIONS.len() {
+            return Err(new_err!(SerializeError: DatabaseError, format!("schema version {} is newer than the supported {}", version, MIGRATIONS.len())))
+        }
+
+        if version < MIGRATIONS.len() {
+            let transaction = connection.transaction()?;
+            for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+                log_debug!("Upgrading the profile database schema to version {}", i + 1);
+                transaction.execute_batch(migration)?;
+            }
+            transaction.pragma_update(None, "user_version", MIGRATIONS.len())?;
+            transaction.commit()?;
+        }
+
+        Ok(connection)
+    }
+}
+
+impl ProfileStore for SqliteStore {
+    fn kind(&self) -> StoreKind {
+        StoreKind::Sqlite
+    }
+
+    fn path(&self) -> &Path {
+        &self.file_path
+    }
+
+    fn load(&self) -> Result<ProfilesData> {
+        let connection = self.connect()?;
+
+        let current_profile = connection.query_row(
+            "SELECT value FROM settings WHERE name = ?1",
+            params![CURRENT_PROFILE_SETTING],
+            |row| row.get::<_, Option<String>>(0)
+        ).optional()?.flatten();
+
+        let mut statement = connection.prepare(
+            "SELECT name, password_hash, nonce, key, failed_attempts, last_failed_attempt FROM profiles ORDER BY rowid"
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Vec<u8>>(2)?,
+                row.get::<_, Vec<u8>>(3)?,
+                row.get::<_, u32>(4)?,
+                row.get::<_, Option<i64>>(5)?,
+            ))
+        })?;
+
+        let mut profiles = Vec::new();
+        for row in rows {
+            let (name, password_hash, nonce, key, failed_attempts, last_failed_attempt) = row?;
+            let nonce: Nonce = nonce.try_into()
+                .map_err(|_| new_err!(InvalidData: InvalidLength, format!("nonce of profile \"{}\"", name)))?;
+
+            profiles.push(Profile {
+                name,
+                password_hash,
+                nonce,
+                key,
+                failed_attempts,
+                last_failed_attempt: last_failed_attempt.map(from_millis),
+                tracked_files: Vec::new(),
+            });
+        }
+
+        let mut statement = connection.prepare(
+            "SELECT profile, id, path, original_name, size, encrypt_time, key_id FROM tracked_files ORDER BY rowid"
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                TrackedFile {
+                    id: row.get(1)?,
+                    path: PathBuf::from(row.get::<_, String>(2)?),
+                    original_name: row.get(3)?,
+                    size: row.get(4)?,
+                    encrypt_time: from_millis(row.get(5)?),
+                    key_id: row.get(6)?,
+                }
+            ))
+        })?;
+        for row in rows {
+            let (profile_name, tracked_file) = row?;
+            if let Some(profile) = profiles.iter_mut().find(|profile| profile.name == profile_name) {
+                profile.tracked_files.push(tracked_file);
+            }
+        }
+
+        Ok(ProfilesData { current_profile, profiles })
+    }
+
+    fn save(&self, data: &ProfilesData) -> Result<()> {
+        log_debug!("Saving profiles data to \"{}\"", self.file_path.display());
+        let mut connection = self.connect()?;
+        let transaction = connection.transaction()?;
+
+        let stored_names = transaction.prepare("SELECT name FROM profiles")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        for name in stored_names {
+            if !data.profiles.iter().any(|profile| profile.name == name) {
+                transaction.execute("DELETE FROM profiles WHERE name = ?1", params![name])?;
+                transaction.execute("DELETE FROM tracked_files WHERE profile = ?1", params![name])?;
+            }
+        }
+
+        for profile in &data.profiles {
+            let last_failed_attempt = profile.last_failed_attempt.map(to_millis);
+
+            // upserting keeps the row id, which holds the order the profiles were created in
+            transaction.execute(
+                "INSERT INTO profiles (name, password_hash, nonce, key, failed_attempts, last_failed_attempt)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT (name) DO UPDATE SET
+                    password_hash = excluded.password_hash,
+                    nonce = excluded.nonce,
+                    key = excluded.key,
+                    failed_attempts = excluded.failed_attempts,
+                    last_failed_attempt = excluded.last_failed_attempt",
+                params![profile.name, profile.password_hash, profile.nonce, profile.key, profile.failed_attempts, last_failed_attempt]
+            )?;
+
+            save_tracked_files(&transaction, profile)?;
+        }
+
+        transaction.execute(
+            "INSERT INTO settings (name, value) VALUES (?1, ?2)
+            ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+            params![CURRENT_PROFILE_SETTING, data.current_profile]
+        )?;
+
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+/// Brings the tracked files of the profile in the database in line with the provided ones, only
+/// writing the rows which were added, changed or removed
+fn save_tracked_files(transaction: &rusqlite::Transaction, profile: &Profile) -> Result<()> {
+    let mut stored = transaction.prepare(
+        "SELECT id, path, original_name, size, encrypt_time, key_id FROM tracked_files WHERE profile = ?1"
+    )?
+        .query_map(params![profile.name], |row| {
+            Ok((row.get::<_, String>(0)?, (
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, u64>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, String>(5)?,
+            )))
+        })?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+
+    for file in &profile.tracked_files {
+        // lossy paths could never be matched to the real file again
+        let path = file.path.to_str()
+            .ok_or_else(|| new_err!(InvalidInput: InvalidFile, format!("Path \"{}\" is not valid UTF-8", file.path.display())))?;
+        let row = (path.to_string(), file.original_name.clone(), file.size, to_millis(file.encrypt_time), file.key_id.clone());
+
+        match stored.remove(&file.id) {
+            Some(stored_row) if stored_row == row => continue,
+            Some(_) => transaction.execute(
+                "UPDATE tracked_files SET path = ?3, original_name = ?4, size = ?5, encrypt_time = ?6, key_id = ?7
+                WHERE profile = ?1 AND id = ?2",
+                params![profile.name, file.id, row.0, row.1, row.2, row.3, row.4]
+            )?,
+            None => transaction.execute(
+                "INSERT INTO tracked_files (profile, id, path, original_name, size, encrypt_time, key_id)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![profile.name, file.id, row.0, row.1, row.2, row.3, row.4]
+            )?,
+        };
+    }
+
+    // rows left over belong to the files which are no longer tracked
+    for id in stored.keys() {
+        transaction.execute("DELETE FROM tracked_files WHERE profile = ?1 AND id = ?2", params![profile.name, id])?;
+    }
+    Ok(())
+}
+
+/// Converts the time to milliseconds since the unix epoch, as stored in the database
+fn to_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Converts milliseconds since the unix epoch stored in the database back to time
+fn from_millis(millis: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_store() -> Result<()> {
+        let database_path = std::env::temp_dir().join(format!("databoxer-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteStore::new(database_path.clone());
+        assert!(store.load()?.profiles.is_empty());
+
+        let mut profile = Profile::new("test", "my_password123")?;
+        profile.failed_attempts = 2;
+        profile.last_failed_attempt = Some(SystemTime::now());
+        profile.tracked_files.push(TrackedFile {
+            id: "0123456789ABCDEF01234567".to_string(),
+            path: PathBuf::from("/tmp/file.box"),
+            original_name: "file.txt".to_string(),
+            size: 42,
+            encrypt_time: SystemTime::now(),
+            key_id: "89ABCDEF01234567".to_string(),
+        });
+        let mut data = ProfilesData {
+            current_profile: Some("test".to_string()),
+            profiles: vec![Profile::new("other", "other_password")?, profile],
+        };
+        store.save(&data)?;
+
+        data.profiles.remove(0);
+        data.profiles[0].tracked_files[0].path = PathBuf::from("/tmp/moved/file.box");
+        store.save(&data)?;
+        let loaded = store.load()?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let mut invalid = data.profiles[0].tracked_files[0].clone();
+            invalid.id = "FEDCBA987654321076543210".to_string();
+            invalid.path = PathBuf::from(std::ffi::OsStr::from_bytes(b"/tmp/\xFF.box"));
+            data.profiles[0].tracked_files.push(invalid);
+            assert!(store.save(&data).is_err(), "Path which isn't valid UTF-8 was stored");
+        }
+        std::fs::remove_file(&database_path)?;
+
+        assert_eq!(loaded.current_profile.as_deref(), Some("test"));
+        assert_eq!(loaded.profiles.len(), 1);
+        assert_eq!(loaded.profiles[0].failed_attempts, 2);
+        assert!(loaded.profiles[0].last_failed_attempt.is_some());
+        assert_eq!(loaded.profiles[0].tracked_files.len(), 1);
+        assert_eq!(loaded.profiles[0].tracked_files[0].size, 42);
+        assert_eq!(loaded.profiles[0].tracked_files[0].path, PathBuf::from("/tmp/moved/file.box"));
+        assert!(loaded.profiles[0].get_key("my_password123").is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+//! Contains the registry of boxfiles produced by each profile
+//!
+//! Every boxfile written by a profile is recorded together with its original name, size and the
+//! key it was encrypted with, so the encrypted files can be found again later. Entries are
+//! identified by the file id from the boxfile header, which lets moved boxfiles be recognized
+//! when the registry is reconciled by scanning the filesystem
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::core::encryption::boxfile::{Boxfile, BoxfileHeader};
+use crate::core::utils;
+use crate::{log_debug, log_warn, Result};
+
+/// Single boxfile produced by a profile
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackedFile {
+    /// Unique identifier of the boxfile taken from its header
+    pub id: String,
+    /// Absolute path the boxfile was last seen at
+    pub path: PathBuf,
+    /// Original name of the encrypted file including its extension
+    pub original_name: String,
+    /// Size of the original file in bytes
+    pub size: u64,
+    /// Time the file was encrypted at
+    pub encrypt_time: SystemTime,
+    /// Fingerprint of the profile key the file was encrypted with
+    pub key_id: String,
+}
+
+impl TrackedFile {
+    /// Creates a new entry for the boxfile with provided header written to the output path
+    pub fn new(header: &BoxfileHeader, output_path: &Path, size: u64) -> Self {
+        TrackedFile {
+            id: header.file_id(),
+            path: std::path::absolute(output_path).unwrap_or_else(|_| output_path.to_path_buf()),
+            original_name: header.original_name().to_string_lossy().to_string(),
+            size,
+            encrypt_time: SystemTime::now(),
+            key_id: header.key_fingerprint().unwrap_or_default(),
+        }
+    }
+
+    /// Returns whether the boxfile is still at the recorded path
+    pub fn exists(&self) -> bool {
+        read_file_id(&self.path).is_some_and(|id| id == self.id)
+    }
+}
+
+/// Changes made to the registry by the reconciliation
+#[derive(Debug, Default)]
+pub struct Reconciliation {
+    /// Entries of the boxfiles found at a new path, with the path updated
+    pub moved: Vec<TrackedFile>,
+    /// Entries of the boxfiles which were not found anywhere
+    pub removed: Vec<TrackedFile>,
+}
+
+/// Records the newly encrypted files and removes the decrypted ones from the registry of the
+/// profile with provided name. Files with a path which isn't valid UTF-8 cannot be stored, so they
+/// are not tracked
+pub fn record(profile_name: &str, encrypted: Vec<TrackedFile>, decrypted: &[String]) -> Result<()> {
+    log_debug!("Recording {} encrypted and {} decrypted files", encrypted.len(), decrypted.len());
+    let encrypted = encrypted.into_iter()
+        .filter(|file| {
+            let valid = file.path.to_str().is_some();
+            if !valid {
+                log_warn!("Not tracking \"{}\", as its path is not valid UTF-8", file.path.display());
+            }
+            valid
+        })
+        .collect();
+    let mut profiles = super::get_profiles()?;
+    let profile = profiles.find_profile(profile_name)?;
+
+    profile.untrack_files(decrypted);
+    profile.track_files(encrypted);
+    profiles.save()
+}
+
+/// Checks whether the tracked boxfiles are still at their recorded paths. Boxfiles which are
+/// missing are searched for in the scan paths (recursively) and their path is updated if found,
+/// otherwise their entries are removed. Returns the changes to apply
+pub fn reconcile(tracked_files: &[TrackedFile], scan_paths: &[PathBuf]) -> Reconciliation {
+    let missing = tracked_files.iter()
+        .filter(|file| !file.exists())
+        .collect::<Vec<&TrackedFile>>();
+    if missing.is_empty() {
+        return Reconciliation::default()
+    }
+
+    let found = scan_boxfiles(scan_paths);
+    let mut reconciliation = Reconciliation::default();
+    for file in missing {
+        match found.get(&file.id) {
+            Some(path) => {
+                log_debug!("Tracked file \"{}\" was moved to \"{}\"", file.id, path.display());
+                reconciliation.moved.push(TrackedFile { path: path.clone(), ..file.clone() });
+            },
+            None => {
+                log_debug!("Tracked file \"{}\" was not found", file.id);
+                reconciliation.removed.push(file.clone());
+            }
+        }
+    }
+    reconciliation
+}
+
+/// Applies the changes of the reconciliation to the registry of the profile with provided name
+pub fn apply(profile_name: &str, reconciliation: &Reconciliation) -> Result<()> {
+    let mut profiles = super::get_profiles()?;
+    let profile = profiles.find_profile(profile_name)?;
+
+    let removed_ids = reconciliation.removed.iter()
+        .map(|file| file.id.clone())
+        .collect::<Vec<String>>();
+    profile.untrack_files(&removed_ids);
+    profile.move_files(&reconciliation.moved);
+    profiles.save()
+}
+
+/// Returns the ids of all boxfiles found in the scan paths mapped to their paths. Boxfiles at paths
+/// which aren't valid UTF-8 are skipped, as they cannot be tracked
+fn scan_boxfiles(scan_paths: &[PathBuf]) -> HashMap<String, PathBuf> {
+    scan_paths.iter()
+        .flat_map(|scan_path| {
+            if scan_path.is_dir() {
+                utils::path::find_boxfiles(scan_path, true)
+            } else {
+                vec![scan_path.clone()]
+            }
+        })
+        .filter_map(|path| {
+            path.to_str()?;
+            let id = read_file_id(&path)?;
+            Some((id, std::path::absolute(&path).unwrap_or(path)))
+        })
+        .collect()
+}
+
+/// Returns the file id of the boxfile at provided path or `None` if it's not a valid boxfile. Only
+/// the header is read
+fn read_file_id(path: &Path) -> Option<String> {
+    Boxfile::parse_header(path).ok().map(|header| header.file_id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::encryption::cipher;
+
+    #[test]
+    fn test_reconcile() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("databoxer-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("moved"))?;
+
+        let key = cipher::generate_key();
+        let mut tracked_files = Vec::new();
+        for name in ["kept", "moved", "deleted"] {
+            let mut boxfile = Boxfile::from_data(b"data", Path::new(&format!("{}.txt", name)))?;
+            boxfile.encrypt_data(&key)?;
+            let path = dir.join(format!("{}.box", name));
+            boxfile.save_to(&path)?;
+            tracked_files.push(TrackedFile::new(&boxfile.header, &path, 4));
+        }
+        std::fs::rename(dir.join("moved.box"), dir.join("moved").join("moved.box"))?;
+        std::fs::remove_file(dir.join("deleted.box"))?;
+
+        let reconciliation = reconcile(&tracked_files, std::slice::from_ref(&dir));
+        std::fs::remove_dir_all(&dir)?;
+
+        assert_eq!(tracked_files[0].original_name, "kept.txt");
+        assert_eq!(reconciliation.moved.len(), 1);
+        assert_eq!(reconciliation.moved[0].id, tracked_files[1].id);
+        assert!(reconciliation.moved[0].path.ends_with("moved/moved.box"));
+        assert_eq!(reconciliation.removed, vec![tracked_files[2].clone()]);
+        Ok(())
+    }
+}
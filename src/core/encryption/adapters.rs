@@ -0,0 +1,161 @@
+//! Contains reader and writer adapters which produce and consume the `boxfile` format from any
+//! `Read` or `Write` implementation, together with in-memory helper functions. These work directly
+//! with a provided `Key` and never access profiles stored on the disk
+//!
+//! *The adapters don't stream: the whole file body is encrypted as a single AEAD message, so the
+//! data is buffered in memory until the `boxfile` is finished or fully read. Memory use grows with
+//! the size of the file*
+
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+use crate::{log_debug, log_warn, Key, Result};
+use super::boxfile::{Boxfile, BoxfileHeader};
+use super::secret::Secret;
+
+/// Writer which collects all the data written to it in memory and encrypts it into a `boxfile` on
+/// `finish`. Nothing is written to the inner writer before that
+///
+/// Dropping the writer without calling `finish` discards all the written data. Both the key and the
+/// buffered data are zeroized on drop
+pub struct BoxWriter<W: Write> {
+    inner: W,
+    key: Secret<Key>,
+    file_name: PathBuf,
+    buffer: Zeroizing<Vec<u8>>,
+}
+
+impl<W: Write> BoxWriter<W> {
+    /// Creates a new writer which will write the encrypted `boxfile` to the inner writer. Provided
+    /// file name is used as the original name and extension of the file stored in the header
+    pub fn new(inner: W, key: &Key, file_name: &Path) -> Self {
+        BoxWriter {
+            inner,
+            key: Secret::new(*key),
+            file_name: file_name.to_path_buf(),
+            buffer: Zeroizing::new(Vec::new()),
+        }
+    }
+
+    /// Encrypts all the written data, writes the resulting `boxfile` to the inner writer and
+    /// returns it
+    pub fn finish(mut self) -> Result<W> {
+        log_debug!("Finishing boxfile for {:?}", self.file_name);
+        let mut boxfile = Boxfile::from_data(&self.buffer, &self.file_name)?;
+        boxfile.encrypt_data(&*self.key)?;
+        boxfile.write_to(&mut self.inner)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for BoxWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reader which reads a whole `boxfile` from the inner reader and decrypts it when created, then
+/// returns the buffered original file data on reads
+pub struct BoxReader<R: Read> {
+    inner: R,
+    header: BoxfileHeader,
+    data: Cursor<Zeroizing<Box<[u8]>>>,
+}
+
+impl<R: Read> BoxReader<R> {
+    /// Reads a single `boxfile` from the inner reader and decrypts it using the provided key.
+    /// Errors if the data is not a valid `boxfile` or the key does not match
+    pub fn new(mut inner: R, key: &Key) -> Result<Self> {
+        let mut boxfile = Boxfile::read_from(&mut inner)?;
+        boxfile.decrypt_data(key)?;
+
+        if !boxfile.verify_checksum()? {
+            log_warn!("Checksum verification failed. Data seems to be tampered with");
+        }
+
+        let data = boxfile.file_data()?;
+        Ok(BoxReader {
+            inner,
+            header: boxfile.header,
+            data: Cursor::new(data),
+        })
+    }
+
+    /// Returns the header of the read `boxfile` containing the original file metadata
+    pub fn header(&self) -> &BoxfileHeader {
+        &self.header
+    }
+
+    /// Returns the inner reader. Any unread decrypted data is discarded
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for BoxReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.data.read(buf)
+    }
+}
+
+/// Encrypts the provided data into an in-memory `boxfile` using the provided key. File name is
+/// used as the original name and extension of the file stored in the header
+pub fn encrypt_bytes(data: &[u8], file_name: &Path, key: &Key) -> Result<Vec<u8>> {
+    let mut writer = BoxWriter::new(Vec::new(), key, file_name);
+    writer.write_all(data)?;
+    writer.finish()
+}
+
+/// Decrypts the provided in-memory `boxfile` using the provided key and returns the original data
+pub fn decrypt_bytes(bytes: &[u8], key: &Key) -> Result<Vec<u8>> {
+    let mut reader = BoxReader::new(bytes, key)?;
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::encryption::cipher;
+    use super::*;
+
+    #[test]
+    fn test_adapter_round_trip() -> Result<()> {
+        let key = cipher::generate_key();
+        let text = "Hello, world!";
+
+        let mut writer = BoxWriter::new(Vec::new(), &key, Path::new("hello.txt"));
+        writer.write_all(&text.as_bytes()[..5])?;
+        writer.write_all(&text.as_bytes()[5..])?;
+        let bytes = writer.finish()?;
+
+        let mut reader = BoxReader::new(bytes.as_slice(), &key)?;
+        assert_eq!(reader.header().name, "hello");
+        assert_eq!(reader.header().extension.as_deref(), Some("txt".as_ref()));
+
+        let mut decrypted_text = String::new();
+        reader.read_to_string(&mut decrypted_text)?;
+        assert_eq!(decrypted_text, text);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytes_round_trip() -> Result<()> {
+        let key = cipher::generate_key();
+        let data = [1u8, 2, 3, 4, 5];
+
+        let bytes = encrypt_bytes(&data, Path::new("data"), &key)?;
+        assert_eq!(decrypt_bytes(&bytes, &key)?, data);
+
+        let other_key = cipher::generate_key();
+        assert!(decrypt_bytes(&bytes, &other_key).is_err());
+        Ok(())
+    }
+}
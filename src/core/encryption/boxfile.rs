@@ -1,24 +1,57 @@
 //! Contains implementation for the custom `boxfile` file format, it's header and
 //! additional information for parsing and serializing the custom file format.
+//!
+//! On the disk a `boxfile` is laid out as the magic, the length of the header (`u32`, little
+//! endian), the header itself, the length of the body (`u64`, little endian), the body and the
+//! checksum. The header is length-prefixed, so it can be read without touching the rest of the file
+//!
+//! Boxfiles of the original version 1 are still read: their header directly follows the magic
+//! without a length prefix, and their body is encrypted with the profile key itself
 
+use bincode::Options;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, Write};
 use std::path::Path;
 use std::ffi::OsString;
 use std::fs;
 use std::time::SystemTime;
-use crate::{log_debug, new_err, Checksum, Key, Nonce, Result};
-use crate::core::data::io;
+use flate2::read::{DeflateDecoder, DeflateEncoder};
+use zeroize::{Zeroize, Zeroizing};
+use crate::{log_debug, new_err, Checksum, Nonce, Result};
+use crate::core::data::{io, os};
+use crate::core::options::{Cipher, Compression, PaddingPolicy};
 use crate::core::utils;
-use super::cipher;
+use super::cipher::{self, KeyWrapper};
+use super::secret::Secret;
 
 mod header_info {
     //! Constants for the header: current file format version and unique file
     //! identifier (magic)
-    /// Version of the `boxfile` format being used for backwards compatibility
-    pub const VERSION: u8 = 1;
+    /// Version of the `boxfile` format being written. All the fields added after the original
+    /// format belong to it, and are set to their version 1 values when reading older files
+    pub const VERSION: u8 = 2;
+    /// Version of the original `boxfile` format, which can still be read
+    pub const LEGACY_VERSION: u8 = 1;
     /// Unique identifier for the `boxfile` file format
     pub const MAGIC: [u8; 4] = [b'B', b'O', b'X', VERSION];
+    /// Maximum accepted length of the serialized header, so a corrupted length cannot make the
+    /// parser allocate huge buffers
+    pub const MAX_HEADER_LEN: u32 = 1024 * 1024;
+}
+
+/// Size of the blocks the body is padded to with `PaddingPolicy::Block`
+const PADDING_BLOCK_SIZE: usize = 64;
+
+/// Settings used to build a new `boxfile`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BoxfileSettings {
+    /// Cipher used to encrypt the body
+    pub cipher: Cipher,
+    /// Compression applied to the file data before encryption
+    pub compression: Compression,
+    /// How much padding is added to the body
+    pub padding: PaddingPolicy,
 }
 
 /// Struct representing a `boxfile` structure. A "boxfile" is the custom file 
@@ -28,7 +61,6 @@ mod header_info {
 ///
 /// *The `boxfile` structure is heavily inspired by the SSH Packet structure, as it
 /// is known to be safe and efficient*
-#[derive(Serialize, Deserialize)]
 pub struct Boxfile {
     /// Custom header for the boxfile. Not encrypted unlike the body of the file and
     /// is available for reading by other processing, meaning an encryption key is
@@ -40,6 +72,9 @@ pub struct Boxfile {
     /// generated `padding`. It is the main payload for the entire `boxfile`. Can be
     /// compressed for reduced storage size
     ///
+    /// Optional extra file metadata (`BoxfileMetadata`) is placed before the file content,
+    /// so it is encrypted the same way.
+    ///
     /// `Padding` is a randomly generated array of random bytes used for encryption
     /// obfuscation. It is encrypted together with the original file so that bytes
     /// mix together and make information even more unreadable without a decryption
@@ -48,7 +83,9 @@ pub struct Boxfile {
     /// Padding should be of such a length, that the total length of any `boxfile`
     /// component (header/body/padding itself) is a multiple of the cipher block
     /// size or 8 (whichever is larger).
-    body: Box<[u8]>,
+    ///
+    /// Zeroized on drop, as it holds the original file data once decrypted
+    body: Zeroizing<Box<[u8]>>,
     /// Checksum is a hash generated from the content of the `boxfile` file body
     /// before the encryption occurs. It ensures the data's integrity by comparing
     /// it to the checksum generated after decryption of the same file.
@@ -61,53 +98,128 @@ impl Boxfile {
     /// `Nonce` for later usage in encryption. Padding is also generated during this
     /// step and added at the end of the original file's data as a part of the body.
     /// Checksum is generated at the very end from the header and body content.
-    pub fn new(file_path: &Path) -> Result<Self> {
+    pub fn new(file_path: &Path, settings: BoxfileSettings) -> Result<Self> {
         log_debug!("Initializing boxfile from {:?}", file_path);
-        let file_data = io::read_bytes(&file_path)?;
-        let padding_len: u8 = (file_data.len() as u8 / 8) + 1;
+        let file_data = Zeroizing::new(io::read_bytes(file_path)?);
+        let metadata = fs::metadata(file_path)?;
+        let extended_attributes = os::get_extended_attributes(file_path)
+            .unwrap_or_else(|err| {
+                log_debug!("Extended attributes are not available: {}", err);
+                Vec::new()
+            });
+        let file_metadata = BoxfileMetadata { extended_attributes };
+
+        Self::build(&file_data, file_path, Some(metadata), file_metadata, settings)
+    }
+
+    /// Generates a new `boxfile` from data which is already loaded into memory. Works the same as
+    /// `Boxfile::new`, but the original name and extension are taken from the provided file name
+    /// and no file metadata is stored in the header. Uses the default settings
+    pub fn from_data(file_data: &[u8], file_name: &Path) -> Result<Self> {
+        log_debug!("Initializing boxfile from in-memory data");
+        Self::build(file_data, file_name, None, BoxfileMetadata::default(), BoxfileSettings::default())
+    }
+
+    /// Shared constructor for `new` and `from_data`. Original file times are stored in the header
+    /// if the metadata is provided. Extra file metadata is only stored in the body if it's not empty
+    fn build(
+        file_data: &[u8],
+        file_name: &Path,
+        metadata: Option<fs::Metadata>,
+        file_metadata: BoxfileMetadata,
+        settings: BoxfileSettings
+    ) -> Result<Self> {
+        let metadata_bytes = Zeroizing::new(if file_metadata.is_empty() {
+            Vec::new()
+        } else {
+            bincode::serialize(&file_metadata)
+                .map_err(|err| new_err!(SerializeError: BoxfileParseError, err))?
+        });
+        let compressed_data;
+        let file_data = match settings.compression {
+            Compression::None => file_data,
+            Compression::Deflate => {
+                compressed_data = Self::compress(file_data)?;
+                log_debug!("File data compressed from {} to {} bytes", file_data.len(), compressed_data.len());
+                &compressed_data[..]
+            }
+        };
+
+        let padding_len = Self::get_padding_len(settings.padding, metadata_bytes.len() + file_data.len());
         let padding = Self::generate_padding(padding_len);
-        let body: Box<[u8]> = [file_data, padding].concat().into();
+        let metadata_len = metadata_bytes.len() as u32;
+        let body = Zeroizing::new([&metadata_bytes, file_data, &padding].concat().into_boxed_slice());
         log_debug!("Boxfile body generated");
 
-        let header = BoxfileHeader::new(
-            file_path,
+        let mut header = BoxfileHeader::from_name(
+            file_name,
             padding_len,
             cipher::generate_nonce()
-        )?;
+        );
+        header.metadata_len = metadata_len;
+        header.cipher = settings.cipher;
+        header.compression = settings.compression;
+        header.padding = settings.padding;
+        if let Some(metadata) = metadata {
+            header.create_time = metadata.created().ok();
+            header.modify_time = metadata.modified().ok();
+            header.access_time = metadata.accessed().ok();
+            header.permissions = os::get_permissions(&metadata);
+            header.owner = os::get_owner(&metadata);
+        }
         log_debug!("Boxfile header generated: {:?}", &header);
 
-        let mut hasher = Sha256::new();
-        hasher.update(&header.as_bytes()?);
-        hasher.update(&body);
-        let result = hasher.finalize();
-        let mut checksum = [0u8; 32];
-        checksum.copy_from_slice(&result);
-        log_debug!("Checksum generated: {:?}", utils::hex::bytes_to_string(&checksum));
-
-        Ok(Self {
+        let mut boxfile = Self {
             header,
             body,
-            checksum
-        })
+            checksum: [0u8; 32]
+        };
+        boxfile.update_checksum()?;
+
+        Ok(boxfile)
     }
 
     /// Parses the provided file, tries to deserialize it and returns a parsed `boxfile`.
     pub fn parse(file_path: &Path) -> Result<Self> {
         log_debug!("Parsing boxfile from {:?}", file_path);
-        if let Some(extension) = file_path.extension() {
-            if extension != "box" {
-                return Err(new_err!(InvalidInput: InvalidFile, "Not encrypted"))
-            }
-        } else {
-                return Err(new_err!(InvalidInput: InvalidFile, "Not encrypted"))
-        }
+        Self::check_extension(file_path)?;
 
         let bytes = io::read_bytes(file_path)?;
-        let boxfile: Boxfile = bincode::deserialize(&bytes)
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parses only the header of the provided file without reading the body, which is enough for
+    /// the operations that don't need the file data
+    pub fn parse_header(file_path: &Path) -> Result<BoxfileHeader> {
+        log_debug!("Parsing boxfile header from {:?}", file_path);
+        Self::check_extension(file_path)?;
+
+        let mut file = fs::File::open(file_path)?;
+        BoxfileHeader::read_from(&mut file)
+    }
+
+    /// Checks whether the file has the `.box` extension
+    fn check_extension(file_path: &Path) -> Result<()> {
+        match file_path.extension() {
+            Some(extension) if extension == "box" => Ok(()),
+            _ => Err(new_err!(InvalidInput: InvalidFile, "Not encrypted"))
+        }
+    }
+
+    /// Tries to deserialize the provided bytes and returns a parsed `boxfile`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::read_from(&mut &bytes[..])
+    }
+
+    /// Reads a single `boxfile` from the reader and deserializes it. Only the bytes belonging to
+    /// the `boxfile` are consumed
+    pub fn read_from(reader: &mut impl Read) -> Result<Self> {
+        let header = BoxfileHeader::read_from(reader)?;
+        let (body, checksum) = bincode::deserialize_from(reader)
             .map_err(|err| new_err!(SerializeError: BoxfileParseError, err))?;
         log_debug!("Boxfile deserialized");
 
-        Ok(boxfile)
+        Ok(Boxfile { header, body, checksum })
     }
 
     /// Returns the information about the file contained within the `boxfile`: original file name, 
@@ -119,14 +231,7 @@ impl Boxfile {
     /// Verifies checksum for the `boxfile` by generating new checksum for current data and
     /// comparing it to the checksum stored in the header
     pub fn verify_checksum(&self) -> Result<bool> {
-        let mut hasher = Sha256::new();
-        hasher.update(&self.header.as_bytes()?);
-        hasher.update(&self.body);
-
-        let result = hasher.finalize();
-        let mut checksum = [0u8; 32];
-
-        checksum.copy_from_slice(&result);
+        let checksum = self.generate_checksum()?;
         log_debug!("Boxfile checksum: {:?}", utils::hex::bytes_to_string(&self.checksum));
         log_debug!("Updated checksum: {:?}", utils::hex::bytes_to_string(&checksum));
         Ok(checksum == self.checksum)
@@ -135,43 +240,135 @@ impl Boxfile {
     /// Serializes self and writes to specified file
     pub fn save_to(&self, path: &Path) -> Result<()> {
         log_debug!("Serializing and saving boxfile to {:?}", path);
-        let bytes = bincode::serialize(&self)
-            .map_err(|err| new_err!(SerializeError: BoxfileParseError, err))?;
-        io::write_bytes(path, &bytes, true)?;
+        io::write_bytes(path, &self.to_bytes()?)?;
+
+        Ok(())
+    }
 
+    /// Returns self serialized as plain bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Serializes self and writes the bytes to the writer
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        self.header.write_to(writer)?;
+        bincode::serialize_into(writer, &(&self.body, &self.checksum))
+            .map_err(|err| new_err!(SerializeError: BoxfileParseError, err))?;
         Ok(())
     }
 
-    /// Encrypts the body of the `boxfile` together with randomly generated padding 
-    /// using the provided encryption key
-    pub fn encrypt_data(&mut self, key: &Key) -> Result<()> {
+    /// Encrypts the body of the `boxfile` together with randomly generated padding using a newly
+    /// generated data key. The data key is wrapped with the provided key and stored in the header.
+    /// The header is authenticated as the associated data, so it cannot be changed unnoticed
+    pub fn encrypt_data(&mut self, key: &(impl KeyWrapper + ?Sized)) -> Result<()> {
         log_debug!("Encrypting boxfile");
-        let encrypted_body = cipher::encrypt(key, &self.header.nonce, &self.body)?;
-        self.body = encrypted_body.into();
+        let data_key = Secret::new(cipher::generate_key());
+        self.header.wrapped_key = key.wrap_key(&data_key)?;
+        self.update_checksum()?;
+
+        let header_bytes = self.header.as_bytes()?;
+        let encrypted_body = match self.header.cipher {
+            Cipher::ChaCha20Poly1305 => cipher::encrypt_with_aad(&data_key, &self.header.nonce, &self.body, &header_bytes)?
+        };
+        self.body = Zeroizing::new(encrypted_body.into());
         Ok(())
     }
     
-    /// Decrypts the body of the `boxfile` (data + padding) using the provided encryption key
-    pub fn decrypt_data(&mut self, key: &Key) -> Result<()> {
+    /// Decrypts the body of the `boxfile` (data + padding) using the data key stored in the header,
+    /// which is unwrapped with the provided key. Fails if the header was changed after encryption,
+    /// except for version 1 boxfiles, whose header is only covered by the checksum
+    pub fn decrypt_data(&mut self, key: &(impl KeyWrapper + ?Sized)) -> Result<()> {
         log_debug!("Decrypting boxfile");
-        let decrypted_body = cipher::decrypt(key, &self.header.nonce, &self.body)?;
-        self.body = decrypted_body.into();
+        let (data_key, header_bytes) = match self.header.version {
+            header_info::LEGACY_VERSION => (key.legacy_key()?, Vec::new()),
+            _ => (key.unwrap_key(&self.header.wrapped_key)?, self.header.as_bytes()?)
+        };
+        let mut decrypted_body = match self.header.cipher {
+            Cipher::ChaCha20Poly1305 => cipher::decrypt_with_aad(&data_key, &self.header.nonce, &self.body, &header_bytes)?
+        };
+        self.body = Zeroizing::new(decrypted_body.as_slice().into());
+        decrypted_body.zeroize();
         Ok(())
     }
 
-    /// Removes the generated padding, returning only the actual data content of the original file
-    pub fn file_data(&self) -> Result<Box<[u8]>> {
+    /// Removes the generated padding and metadata, returning only the actual data content of the
+    /// original file. Decompresses the data if it was compressed
+    pub fn file_data(&self) -> Result<Zeroizing<Box<[u8]>>> {
         log_debug!("Retrieving file data from boxfile");
-        let padding_len = self.header.padding_len;
-        let data_len = self.body.len() as i32 - padding_len as i32;
-        if data_len < 0 {
-            return Err(new_err!(SerializeError: BoxfileParseError, "Invalid file data length"))
+        let data_start = self.header.metadata_len as usize;
+        let data_end = self.body.len().checked_sub(self.header.padding_len as usize);
+
+        let data = match data_end {
+            Some(data_end) if data_end >= data_start => &self.body[data_start..data_end],
+            _ => return Err(new_err!(SerializeError: BoxfileParseError, "Invalid file data length"))
+        };
+        match self.header.compression {
+            Compression::None => Ok(Zeroizing::new(data.into())),
+            Compression::Deflate => Self::decompress(data)
+        }
+    }
+
+    /// Returns the extra metadata of the original file stored in the body. Returns empty metadata
+    /// if the `boxfile` doesn't contain any
+    pub fn file_metadata(&self) -> Result<BoxfileMetadata> {
+        let metadata_len = self.header.metadata_len as usize;
+        if metadata_len == 0 {
+            return Ok(BoxfileMetadata::default())
+        }
+
+        let metadata_bytes = self.body.get(..metadata_len)
+            .ok_or_else(|| new_err!(SerializeError: BoxfileParseError, "Invalid metadata length"))?;
+        let metadata = bincode::deserialize(metadata_bytes)
+            .map_err(|err| new_err!(SerializeError: BoxfileParseError, err))?;
+        Ok(metadata)
+    }
+
+    /// Generates a checksum from the current header and body content
+    fn generate_checksum(&self) -> Result<Checksum> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.header.as_bytes()?);
+        hasher.update(&self.body);
+
+        let result = hasher.finalize();
+        let mut checksum = [0u8; 32];
+        checksum.copy_from_slice(&result);
+        Ok(checksum)
+    }
+
+    /// Replaces the stored checksum with one generated from the current header and body content
+    fn update_checksum(&mut self) -> Result<()> {
+        self.checksum = self.generate_checksum()?;
+        log_debug!("Checksum generated: {:?}", utils::hex::bytes_to_string(&self.checksum));
+        Ok(())
+    }
+
+    /// Compresses the data with DEFLATE
+    fn compress(data: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        let mut compressed = Zeroizing::new(Vec::new());
+        DeflateEncoder::new(data, flate2::Compression::default()).read_to_end(&mut compressed)?;
+        Ok(compressed)
+    }
+
+    /// Decompresses the data compressed with DEFLATE
+    fn decompress(data: &[u8]) -> Result<Zeroizing<Box<[u8]>>> {
+        let mut decompressed = Zeroizing::new(Vec::new());
+        DeflateDecoder::new(data).read_to_end(&mut decompressed)
+            .map_err(|err| new_err!(SerializeError: BoxfileParseError, format!("Invalid compressed data: {}", err)))?;
+        Ok(Zeroizing::new(decompressed.as_slice().into()))
+    }
+
+    /// Returns the length of the padding for a body of the provided length according to the policy
+    fn get_padding_len(policy: PaddingPolicy, body_len: usize) -> u8 {
+        match policy {
+            PaddingPolicy::Length => (body_len as u8 / 8) + 1,
+            PaddingPolicy::Block => (PADDING_BLOCK_SIZE - body_len % PADDING_BLOCK_SIZE) as u8,
+            PaddingPolicy::Random => rand::random_range(1..=u8::MAX),
         }
-        let file_data = &self.body[..data_len as usize];
-        Ok(file_data.into())
     }
 
-    // TODO: generate padding based on the length of the cipher block size and data
     /// Generates random padding of specified length
     fn generate_padding(padding_len: u8) -> Vec<u8> {
         vec![0u8; padding_len as usize]
@@ -179,12 +376,11 @@ impl Boxfile {
 }
 
 /// The header for the `boxfile`, which contains extra information about the file. This
-/// includes a unique identifier (magic), length of the generated padding, the original
-/// file name and extension and generated `Nonce` for encryption/decryption uniqueness
+/// includes length of the generated padding, the original file name and extension and
+/// generated `Nonce` for encryption/decryption uniqueness. Preceded on the disk by the unique
+/// identifier (magic) and its own length
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BoxfileHeader {
-    /// Unique identifier for the file format including the used version
-    magic: [u8; 4],
     /// The length of the generated padding
     padding_len: u8,
     /// The original name of the file
@@ -197,42 +393,402 @@ pub struct BoxfileHeader {
     pub modify_time: Option<SystemTime>,
     /// The original access time of the file
     pub access_time: Option<SystemTime>,
+    /// The original Unix permission mode bits of the file
+    pub permissions: Option<u32>,
+    /// The original Unix owner of the file as a user and group id pair
+    pub owner: Option<(u32, u32)>,
+    /// Cipher used to encrypt the body
+    pub cipher: Cipher,
+    /// Compression applied to the file data in the body
+    pub compression: Compression,
+    /// Policy the length of the padding was chosen with. Version 1 always padded by length
+    pub padding: PaddingPolicy,
+    /// The length of the extra file metadata stored at the start of the body. Zero if the body
+    /// doesn't contain any
+    metadata_len: u32,
+    /// Random data key which encrypts the body, wrapped with the profile key. Empty until the
+    /// `boxfile` is encrypted
+    wrapped_key: Vec<u8>,
     /// Randomly generated 12-byte `Nonce` used for encryption and decryption. Ensures
     /// that no ciphertext generated using one key is the same
-    nonce: Nonce
+    nonce: Nonce,
+    /// Version of the format the `boxfile` was read with. Not serialized, as it's a part of the
+    /// magic
+    #[serde(skip)]
+    version: u8,
+}
+
+/// Header of a version 1 `boxfile`, which was serialized together with the magic
+#[derive(Serialize, Deserialize)]
+struct LegacyHeader {
+    magic: [u8; 4],
+    padding_len: u8,
+    name: OsString,
+    extension: Option<OsString>,
+    create_time: Option<SystemTime>,
+    modify_time: Option<SystemTime>,
+    access_time: Option<SystemTime>,
+    nonce: Nonce,
 }
 
 impl BoxfileHeader {
-    pub fn new(
-        file_path: &Path,
+    /// Creates a new header using only the provided file name. Original file times are left
+    /// unknown
+    pub fn from_name(
+        file_name: &Path,
         padding_len: u8,
-        nonce: Nonce 
-    ) -> Result<Self> {
-        let name = match file_path.file_stem() {
+        nonce: Nonce
+    ) -> Self {
+        let name = match file_name.file_stem() {
             None => OsString::from("unknown"),
             Some(name) => OsString::from(name)
         };
-        let extension = file_path.extension().map(|ext| ext.to_os_string());
-        let metadata = fs::metadata(file_path)?;
-        
-        Ok(BoxfileHeader {
-            magic: header_info::MAGIC,
+        let extension = file_name.extension().map(|ext| ext.to_os_string());
+
+        BoxfileHeader {
             name,
             extension,
-            create_time: metadata.created().ok(),
-            modify_time: metadata.modified().ok(),
-            access_time: metadata.accessed().ok(),
+            create_time: None,
+            modify_time: None,
+            access_time: None,
+            permissions: None,
+            owner: None,
+            cipher: Cipher::default(),
+            compression: Compression::default(),
+            padding: PaddingPolicy::default(),
+            metadata_len: 0,
+            wrapped_key: Vec::new(),
             padding_len,
-            nonce
-        })
+            nonce,
+            version: header_info::VERSION,
+        }
     }
 
-    /// Returns the header serialized as plain bytes
+    /// Returns the unique identifier of the boxfile. The nonce is generated anew for every
+    /// boxfile, so its hex form is used as the identifier
+    pub fn file_id(&self) -> String {
+        utils::hex::bytes_to_string(&self.nonce)
+    }
+
+    /// Returns the hex fingerprint of the profile key the data key was wrapped with or `None` if
+    /// the `boxfile` is not encrypted yet
+    pub fn key_fingerprint(&self) -> Option<String> {
+        cipher::get_wrapping_fingerprint(&self.wrapped_key)
+            .map(|fingerprint| utils::hex::bytes_to_string(&fingerprint))
+    }
+
+    /// Returns the original file name together with its extension
+    pub fn original_name(&self) -> OsString {
+        let mut name = self.name.clone();
+        if let Some(extension) = &self.extension {
+            name.push(".");
+            name.push(extension);
+        }
+        name
+    }
+
+    /// Returns the version of the format the `boxfile` was read with
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Returns the header serialized as plain bytes. A version 1 header is serialized the way it
+    /// was stored, together with the magic
     pub fn as_bytes(&self) -> Result<Vec<u8>> {
         log_debug!("Serializing Boxfile header");
-        let bytes = bincode::serialize(&self)
+        let bytes = match self.version {
+            header_info::LEGACY_VERSION => bincode::serialize(&LegacyHeader {
+                magic: [b'B', b'O', b'X', header_info::LEGACY_VERSION],
+                padding_len: self.padding_len,
+                name: self.name.clone(),
+                extension: self.extension.clone(),
+                create_time: self.create_time,
+                modify_time: self.modify_time,
+                access_time: self.access_time,
+                nonce: self.nonce,
+            }),
+            _ => bincode::serialize(&self)
+        };
+        bytes.map_err(|err| new_err!(SerializeError: HeaderParseError, err))
+    }
+
+    /// Reads only the header from the start of a `boxfile`, consuming the magic, the header length
+    /// and the header bytes. The reader is left at the start of the body, so it's never read
+    pub fn read_from(reader: &mut impl Read) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)
+            .map_err(|_| new_err!(SerializeError: HeaderParseError, "Not a boxfile"))?;
+        if magic[..3] != header_info::MAGIC[..3] {
+            return Err(new_err!(SerializeError: HeaderParseError, "Not a boxfile"))
+        }
+
+        match magic[3] {
+            header_info::VERSION => Self::read_current(reader),
+            header_info::LEGACY_VERSION => Self::read_legacy(magic, reader),
+            version => Err(new_err!(SerializeError: HeaderParseError, format!("Unsupported boxfile version {}", version)))
+        }
+    }
+
+    /// Reads the length-prefixed header of the current version following the magic
+    fn read_current(reader: &mut impl Read) -> Result<Self> {
+        let mut header_len = [0u8; 4];
+        reader.read_exact(&mut header_len)
+            .map_err(|_| new_err!(SerializeError: HeaderParseError, "Header is truncated"))?;
+
+        let header_len = u32::from_le_bytes(header_len);
+        if header_len > header_info::MAX_HEADER_LEN {
+            return Err(new_err!(SerializeError: HeaderParseError, format!("Header is too long ({} bytes)", header_len)))
+        }
+
+        let mut header_bytes = vec![0u8; header_len as usize];
+        reader.read_exact(&mut header_bytes)
+            .map_err(|_| new_err!(SerializeError: HeaderParseError, "Header is truncated"))?;
+        let mut header: BoxfileHeader = bincode::deserialize(&header_bytes)
             .map_err(|err| new_err!(SerializeError: HeaderParseError, err))?;
-        Ok(bytes)
+        header.version = header_info::VERSION;
+        log_debug!("Boxfile header deserialized");
+
+        Ok(header)
+    }
+
+    /// Reads the version 1 header, which isn't length-prefixed, so its length is only limited.
+    /// The fields added in later versions are set to the values version 1 always used
+    fn read_legacy(magic: [u8; 4], reader: &mut impl Read) -> Result<Self> {
+        let legacy: LegacyHeader = bincode::options()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(header_info::MAX_HEADER_LEN as u64)
+            .deserialize_from((&magic[..]).chain(reader))
+            .map_err(|err| new_err!(SerializeError: HeaderParseError, err))?;
+        log_debug!("Version 1 boxfile header deserialized");
+
+        Ok(BoxfileHeader {
+            padding_len: legacy.padding_len,
+            name: legacy.name,
+            extension: legacy.extension,
+            create_time: legacy.create_time,
+            modify_time: legacy.modify_time,
+            access_time: legacy.access_time,
+            permissions: None,
+            owner: None,
+            cipher: Cipher::ChaCha20Poly1305,
+            compression: Compression::None,
+            padding: PaddingPolicy::Length,
+            metadata_len: 0,
+            wrapped_key: Vec::new(),
+            nonce: legacy.nonce,
+            version: header_info::LEGACY_VERSION,
+        })
+    }
+
+    /// Writes the magic, the header length and the header itself to the writer. Only headers of
+    /// the current version can be written
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        if self.version != header_info::VERSION {
+            return Err(new_err!(SerializeError: HeaderParseError, format!("Boxfile version {} cannot be written", self.version)))
+        }
+        let header_bytes = self.as_bytes()?;
+        writer.write_all(&header_info::MAGIC)?;
+        writer.write_all(&(header_bytes.len() as u32).to_le_bytes())?;
+        writer.write_all(&header_bytes)?;
+        Ok(())
+    }
+}
+
+/// Information about a `boxfile` and the original file it contains. Read only from the header and
+/// the length prefixes, so the body is never loaded and no key is needed
+#[derive(Serialize, Debug, Clone)]
+pub struct BoxfileInfo {
+    /// The original name of the file
+    pub name: String,
+    /// The original extension of the file
+    pub extension: Option<String>,
+    /// The original create time of the file
+    pub create_time: Option<SystemTime>,
+    /// The original modify time of the file
+    pub modify_time: Option<SystemTime>,
+    /// The original access time of the file
+    pub access_time: Option<SystemTime>,
+    /// The original Unix permission mode bits of the file
+    pub permissions: Option<u32>,
+    /// The original Unix owner of the file as a user and group id pair
+    pub owner: Option<(u32, u32)>,
+    /// Size of the whole `boxfile` in bytes
+    pub file_size: u64,
+    /// Size of the header in bytes, including the magic and its length
+    pub header_size: u64,
+    /// Size of the encrypted body in bytes, including the padding and extra metadata
+    pub body_size: u64,
+    /// Length of the padding in bytes
+    pub padding_len: u8,
+    /// Version of the `boxfile` format
+    pub format_version: u8,
+    /// Cipher used to encrypt the body
+    pub cipher: Cipher,
+    /// Compression applied to the file data
+    pub compression: Compression,
+    /// Policy the length of the padding was chosen with
+    pub padding: PaddingPolicy,
+    /// Hex fingerprint of the profile key the file was encrypted with
+    pub key_fingerprint: Option<String>,
+}
+
+impl BoxfileInfo {
+    /// Reads the information about the provided `boxfile`
+    pub fn read(file_path: &Path) -> Result<Self> {
+        log_debug!("Reading boxfile information from {:?}", file_path);
+        Boxfile::check_extension(file_path)?;
+
+        let mut file = fs::File::open(file_path)?;
+        let file_size = file.metadata()?.len();
+        let header = BoxfileHeader::read_from(&mut file)?;
+        let header_size = file.stream_position()?;
+
+        let mut body_len = [0u8; 8];
+        file.read_exact(&mut body_len)
+            .map_err(|_| new_err!(SerializeError: BoxfileParseError, "Body is truncated"))?;
+
+        Ok(BoxfileInfo {
+            key_fingerprint: header.key_fingerprint(),
+            name: header.name.to_string_lossy().to_string(),
+            extension: header.extension.map(|extension| extension.to_string_lossy().to_string()),
+            create_time: header.create_time,
+            modify_time: header.modify_time,
+            access_time: header.access_time,
+            permissions: header.permissions,
+            owner: header.owner,
+            file_size,
+            header_size,
+            body_size: u64::from_le_bytes(body_len),
+            padding_len: header.padding_len,
+            format_version: header.version,
+            cipher: header.cipher,
+            compression: header.compression,
+            padding: header.padding,
+        })
     }
 }
 
+/// Extra metadata of the original file. Unlike the header, it is stored at the start of the body,
+/// so it is encrypted together with the file data. Left out completely if empty
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BoxfileMetadata {
+    /// Extended attributes of the original file as name and value pairs. On Linux this includes
+    /// POSIX ACLs and SELinux labels, as they are stored as extended attributes
+    pub extended_attributes: Vec<(OsString, Vec<u8>)>,
+}
+
+impl BoxfileMetadata {
+    /// Returns whether there is no metadata to store
+    pub fn is_empty(&self) -> bool {
+        self.extended_attributes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Key;
+
+    #[test]
+    fn test_compression_and_padding() -> Result<()> {
+        let key = cipher::generate_key();
+        let file_data = "Hello, world! ".repeat(100).into_bytes();
+
+        for (compression, padding) in [
+            (Compression::None, PaddingPolicy::Length),
+            (Compression::Deflate, PaddingPolicy::Block),
+            (Compression::Deflate, PaddingPolicy::Random),
+        ] {
+            let settings = BoxfileSettings { compression, padding, ..Default::default() };
+            let mut boxfile = Boxfile::build(&file_data, Path::new("test.txt"), None, BoxfileMetadata::default(), settings)?;
+            if padding == PaddingPolicy::Block {
+                assert_eq!(boxfile.body.len() % PADDING_BLOCK_SIZE, 0);
+            }
+            if compression == Compression::Deflate {
+                assert!(boxfile.body.len() < file_data.len());
+            }
+
+            boxfile.encrypt_data(&key)?;
+            let mut boxfile = Boxfile::from_bytes(&boxfile.to_bytes()?)?;
+            boxfile.decrypt_data(&key)?;
+
+            assert!(boxfile.verify_checksum()?);
+            assert_eq!(**boxfile.file_data()?, file_data[..]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_only_parsing() -> Result<()> {
+        let key = cipher::generate_key();
+        let mut boxfile = Boxfile::from_data(&[7u8; 4096], Path::new("test.txt"))?;
+        boxfile.encrypt_data(&key)?;
+        let bytes = boxfile.to_bytes()?;
+
+        let mut reader = &bytes[..];
+        let header = BoxfileHeader::read_from(&mut reader)?;
+        assert_eq!(header.original_name(), "test.txt");
+        assert_eq!(header.file_id(), boxfile.header.file_id());
+        assert!(reader.len() > 4096, "Body was consumed with the header");
+
+        let mut old_version = bytes.clone();
+        old_version[3] = header_info::VERSION + 1;
+        assert!(BoxfileHeader::read_from(&mut &old_version[..]).is_err());
+        assert!(BoxfileHeader::read_from(&mut &bytes[..6]).is_err());
+        assert!(BoxfileHeader::read_from(&mut &b"not a boxfile"[..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_authentication() -> Result<()> {
+        let key = cipher::generate_key();
+        let mut boxfile = Boxfile::from_data(b"Hello, world!", Path::new("test.txt"))?;
+        boxfile.encrypt_data(&key)?;
+
+        let mut tampered = Boxfile::from_bytes(&boxfile.to_bytes()?)?;
+        tampered.header.permissions = Some(0o4777);
+        assert!(tampered.decrypt_data(&key).is_err(), "Tampered header was accepted");
+
+        let mut boxfile = Boxfile::from_bytes(&boxfile.to_bytes()?)?;
+        boxfile.decrypt_data(&key)?;
+        assert!(boxfile.verify_checksum()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_version() -> Result<()> {
+        let key: Key = core::array::from_fn(|i| i as u8);
+        let bytes = include_bytes!("../../../tests/fixtures/legacy-v1.box");
+
+        let mut boxfile = Boxfile::from_bytes(bytes)?;
+        assert_eq!(boxfile.header.version(), header_info::LEGACY_VERSION);
+        assert_eq!(boxfile.header.original_name(), "legacy.txt");
+        assert!(boxfile.header.modify_time.is_some());
+
+        boxfile.decrypt_data(&key)?;
+        assert!(boxfile.verify_checksum()?, "Checksum of the version 1 boxfile doesn't match");
+        assert_eq!(**boxfile.file_data()?, b"Written by boxfile format version 1\n"[..]);
+        assert!(boxfile.to_bytes().is_err(), "Version 1 boxfile was written");
+        Ok(())
+    }
+
+    #[test]
+    fn test_information() -> Result<()> {
+        let key = cipher::generate_key();
+        let file_path = std::env::temp_dir().join(format!("databoxer-{}.box", uuid::Uuid::new_v4()));
+        let mut boxfile = Boxfile::from_data(&[7u8; 100], Path::new("test.txt"))?;
+        boxfile.encrypt_data(&key)?;
+        boxfile.save_to(&file_path)?;
+
+        let info = BoxfileInfo::read(&file_path)?;
+        std::fs::remove_file(&file_path)?;
+
+        assert_eq!(info.name, "test");
+        assert_eq!(info.extension.as_deref(), Some("txt"));
+        assert_eq!(info.file_size, info.header_size + 8 + info.body_size + 32);
+        assert_eq!(info.format_version, header_info::VERSION);
+        assert_eq!(info.key_fingerprint, Some(utils::hex::bytes_to_string(&cipher::key_fingerprint(&key))));
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
 //! Contains methods related to encryption and decryption, key and nonce generation
 
 use chacha20poly1305::{
-    aead::{OsRng, Aead, KeyInit},
+    aead::{OsRng, Aead, KeyInit, Payload},
     AeadCore, ChaCha20Poly1305
 };
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
 use crate::{new_err, Result};
+use super::secret::Secret;
 
 /// Type representing a basic 32-byte encryption key
 pub type Key = [u8; 32];
@@ -12,6 +15,8 @@ pub type Key = [u8; 32];
 pub type Checksum = [u8; 32];
 /// Type representing a 12-byte nonce used for encryption in combination with an encryption key
 pub type Nonce = [u8; 12];
+/// Type representing an 8-byte fingerprint identifying an encryption key
+pub type Fingerprint = [u8; 8];
 
 /// Generates a new random 32-byte encryption key
 pub fn generate_key() -> Key {
@@ -23,12 +28,27 @@ pub fn generate_nonce() -> Nonce {
     ChaCha20Poly1305::generate_nonce(&mut OsRng).into()
 }
 
+/// Returns the fingerprint of the key, which is the start of its SHA-256 hash. Identifies the key
+/// without revealing anything about it
+pub fn key_fingerprint(key: &Key) -> Fingerprint {
+    let hash = Sha256::digest(key);
+    let mut fingerprint = Fingerprint::default();
+    fingerprint.copy_from_slice(&hash[..size_of::<Fingerprint>()]);
+    fingerprint
+}
+
 /// Encrypts and returns encrypted bytes with ChaCha20Ply1305 algorithm using provided `Key` and
 /// `Nonce`
 pub fn encrypt(key: &Key, nonce: &Nonce, data: &[u8]) -> Result<Vec<u8>> {
+    encrypt_with_aad(key, nonce, data, &[])
+}
+
+/// Encrypts the data like [`encrypt`], additionally authenticating the associated data, which
+/// itself stays unencrypted. The same associated data must be provided for decryption
+pub fn encrypt_with_aad(key: &Key, nonce: &Nonce, data: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
     let cipher = ChaCha20Poly1305::new(key.into());
 
-    let ciphertext = cipher.encrypt(nonce.into(), data)
+    let ciphertext = cipher.encrypt(nonce.into(), Payload { msg: data, aad })
         .map_err(|err| new_err!(EncryptionError: CipherError, err))?;
     Ok(ciphertext)
 }
@@ -37,9 +57,86 @@ pub fn encrypt(key: &Key, nonce: &Nonce, data: &[u8]) -> Result<Vec<u8>> {
 /// `Nonce`. Provided `Key` and `Nonce` should match the ones which were used to encrypt file for
 /// successful decryption
 pub fn decrypt(key: &Key, nonce: &Nonce, data: &[u8]) -> Result<Vec<u8>> {
+    decrypt_with_aad(key, nonce, data, &[])
+}
+
+/// Decrypts the data like [`decrypt`], failing if the associated data differs from the one used
+/// for encryption
+pub fn decrypt_with_aad(key: &Key, nonce: &Nonce, data: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
     let cipher = ChaCha20Poly1305::new(key.into());
 
-    let plaintext = cipher.decrypt(nonce.into(), data)
+    let plaintext = cipher.decrypt(nonce.into(), Payload { msg: data, aad })
         .map_err(|err| new_err!(EncryptionError: CipherError, err))?;
     Ok(plaintext)
 }
+
+/// Wraps and unwraps the random data keys which encrypt the body of each `boxfile`. Implemented
+/// by the profile `Key` itself and by anything which can use the profile key without exposing it,
+/// like the key agent
+pub trait KeyWrapper {
+    /// Encrypts the provided data key and returns it in a wrapped form
+    fn wrap_key(&self, data_key: &Key) -> Result<Vec<u8>>;
+    /// Decrypts the wrapped data key. Errors if it was wrapped using a different key
+    fn unwrap_key(&self, wrapped_key: &[u8]) -> Result<Secret<Key>>;
+    /// Returns the wrapping key itself, which the body of a version 1 `boxfile` is encrypted with,
+    /// as those have no data key. Errors if the key cannot be exposed
+    fn legacy_key(&self) -> Result<Secret<Key>>;
+}
+
+/// Wrapped key consists of the fingerprint of the wrapping key, a freshly generated nonce and the
+/// encrypted data key
+impl KeyWrapper for Key {
+    fn wrap_key(&self, data_key: &Key) -> Result<Vec<u8>> {
+        let nonce = generate_nonce();
+        let encrypted_key = encrypt(self, &nonce, data_key)?;
+        Ok([key_fingerprint(self).as_slice(), nonce.as_slice(), &encrypted_key].concat())
+    }
+
+    fn unwrap_key(&self, wrapped_key: &[u8]) -> Result<Secret<Key>> {
+        let fingerprint = get_wrapping_fingerprint(wrapped_key)
+            .ok_or_else(|| new_err!(InvalidData: InvalidLength, "wrapped key"))?;
+        if fingerprint != key_fingerprint(self) {
+            return Err(new_err!(EncryptionError: CipherError, "Wrapped with a different key"))
+        }
+        if wrapped_key.len() < fingerprint.len() + 12 {
+            return Err(new_err!(InvalidData: InvalidLength, "wrapped key"))
+        }
+
+        let (nonce, encrypted_key) = wrapped_key[fingerprint.len()..].split_at(12);
+        let nonce: Nonce = nonce.try_into()
+            .map_err(|_| new_err!(InvalidData: InvalidLength, "wrapped key nonce"))?;
+        let key_bytes = Zeroizing::new(decrypt(self, &nonce, encrypted_key)?);
+        Secret::from_slice(&key_bytes)
+            .ok_or_else(|| new_err!(InvalidData: InvalidLength, "wrapped key"))
+    }
+
+    fn legacy_key(&self) -> Result<Secret<Key>> {
+        Ok(Secret::new(*self))
+    }
+}
+
+/// Returns the fingerprint of the key the provided data key was wrapped with or `None` if the
+/// wrapped key is too short to contain one
+pub fn get_wrapping_fingerprint(wrapped_key: &[u8]) -> Option<Fingerprint> {
+    wrapped_key.get(..size_of::<Fingerprint>())?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_wrapping() -> Result<()> {
+        let key = generate_key();
+        let data_key = generate_key();
+
+        let wrapped_key = key.wrap_key(&data_key)?;
+        assert_eq!(*key.unwrap_key(&wrapped_key)?, data_key);
+        assert_eq!(get_wrapping_fingerprint(&wrapped_key), Some(key_fingerprint(&key)));
+
+        let other_key = generate_key();
+        assert!(other_key.unwrap_key(&wrapped_key).is_err());
+        assert!(key.unwrap_key(&wrapped_key[..8]).is_err());
+        Ok(())
+    }
+}
@@ -2,3 +2,5 @@
 
 pub mod cipher;
 pub mod boxfile;
+pub mod adapters;
+pub mod secret;
@@ -0,0 +1,131 @@
+//! Contains the `Secret` container used for holding sensitive values like encryption keys and
+//! passwords in memory
+//!
+//! The value is placed on the heap, so it doesn't leave copies behind when the container is moved,
+//! and is overwritten with zeroes on drop. On Linux the memory pages holding the value can also be
+//! locked with `mlock`, so they are never written to swap (requires the `mlock` feature)
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use zeroize::Zeroize;
+use crate::Key;
+
+/// Container for a sensitive value, which is zeroized on drop and never shown in `Debug` output
+pub struct Secret<T: Zeroize>(Box<T>);
+
+impl<T: Zeroize> Secret<T> {
+    /// Moves the value into a new secret container and locks its memory if supported
+    pub fn new(value: T) -> Self {
+        let secret = Secret(Box::new(value));
+        memory::lock(secret.0.as_ref());
+        secret
+    }
+}
+
+impl Secret<Key> {
+    /// Copies the key from the provided slice. Returns `None` if the slice is not a valid key
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        let mut secret = Secret::new(Key::default());
+        if bytes.len() != secret.len() {
+            return None
+        }
+        secret.copy_from_slice(bytes);
+        Some(secret)
+    }
+}
+
+impl<T: Zeroize> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> DerefMut for Secret<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Secret::new(self.0.as_ref().clone())
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+        memory::unlock(self.0.as_ref());
+    }
+}
+
+/// Never prints the value itself
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+mod memory {
+    //! Locking of the memory pages using `mlock`. Only the value itself is locked, so heap data
+    //! owned by the value (e.g. the contents of a `String`) is not covered
+    use crate::log_debug;
+
+    /// Locks the memory holding the value. Failure (e.g. a too low `RLIMIT_MEMLOCK`) is not fatal
+    pub fn lock<T>(value: &T) {
+        let result = unsafe {
+            libc::mlock(value as *const T as *const libc::c_void, size_of::<T>())
+        };
+        if result != 0 {
+            log_debug!("Unable to lock secret memory: {}", std::io::Error::last_os_error());
+        }
+    }
+
+    /// Unlocks the memory previously locked with `lock`
+    pub fn unlock<T>(value: &T) {
+        unsafe {
+            libc::munlock(value as *const T as *const libc::c_void, size_of::<T>());
+        }
+    }
+}
+
+#[cfg(not(all(feature = "mlock", target_os = "linux")))]
+mod memory {
+    //! Memory locking is not supported, so these do nothing
+    pub fn lock<T>(_value: &T) {}
+    pub fn unlock<T>(_value: &T) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::encryption::cipher;
+    use crate::core::utils;
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts_key() {
+        let key = cipher::generate_key();
+        let secret = Secret::new(key);
+
+        let output = format!("{:?} {:#?}", secret, secret);
+        assert!(!output.contains(&format!("{:?}", key)));
+        assert!(!output.contains(&utils::hex::bytes_to_string(&key)));
+        assert_eq!(*secret, key);
+    }
+
+    #[test]
+    fn test_debug_redacts_password() {
+        let secret = Secret::new("my_password123".to_string());
+        assert!(!format!("{:?}", secret).contains("my_password123"));
+    }
+
+    #[test]
+    fn test_key_from_slice() {
+        let key = cipher::generate_key();
+        assert_eq!(*Secret::from_slice(&key).unwrap(), key);
+        assert!(Secret::from_slice(&key[..16]).is_none());
+    }
+}
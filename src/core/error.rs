@@ -76,6 +76,20 @@ impl Error {
             Error::ConfigError(_) => 8,
         }
     }
+
+    /// Returns the name of the error type, as used in the machine-readable output
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Error::IOError(_) => "IOError",
+            Error::OSError(_) => "OSError",
+            Error::InvalidData(_) => "InvalidData",
+            Error::InvalidInput(_) => "InvalidInput",
+            Error::EncryptionError(_) => "EncryptionError",
+            Error::SerializeError(_) => "SerializeError",
+            Error::ProfileError(_) => "ProfileError",
+            Error::ConfigError(_) => "ConfigError",
+        }
+    }
 }
 
 impl Display for Error {
@@ -116,6 +130,12 @@ impl From<toml::ser::Error> for Error {
     }
 }
 
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::SerializeError(SerializeErrorKind::DatabaseError(err.to_string()))
+    }
+}
+
 impl From<toml::de::Error> for Error {
     fn from(err: toml::de::Error) -> Self {
         Error::SerializeError(SerializeErrorKind::TOMLParseError(err.to_string()))
@@ -130,6 +150,10 @@ pub enum IOErrorKind {
     NotFound(PathBuf),
     /// The privided file is not supported for operations
     NotSupported(PathBuf),
+    /// The output file already exists and cannot be overwritten
+    AlreadyExists(PathBuf),
+    /// The lock file is held by another process for longer than the timeout
+    Locked(PathBuf),
 }
 
 impl Display for IOErrorKind {
@@ -138,6 +162,8 @@ impl Display for IOErrorKind {
             IOErrorKind::StdError(e) => write!(f, "{} - {}", e.kind, e.message),
             IOErrorKind::NotFound(p) => write!(f, "File \"{}\" not found", p.display()),
             IOErrorKind::NotSupported(p) => write!(f, "File \"{}\" is not supported", p.display()),
+            IOErrorKind::AlreadyExists(p) => write!(f, "File \"{}\" already exists", p.display()),
+            IOErrorKind::Locked(p) => write!(f, "Lock \"{}\" is held by another process", p.display()),
         }
     }
 }
@@ -189,12 +215,18 @@ pub enum InvalidInputKind {
     /// already encrypted, decrypted or anything else which would mark it
     /// invalid in a given context. 
     InvalidFile(String),
+    /// The provided password doesn't satisfy the password policy
+    WeakPassword(String),
+    /// The provided glob or regex pattern cannot be parsed
+    InvalidPattern(String),
 }
 
 impl Display for InvalidInputKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             InvalidInputKind::InvalidFile(s) => write!(f, "Invalid file provided ({})", s),
+            InvalidInputKind::WeakPassword(s) => write!(f, "Password is too weak ({})", s),
+            InvalidInputKind::InvalidPattern(s) => write!(f, "Invalid pattern provided ({})", s),
         }
     }
 }
@@ -203,6 +235,10 @@ impl Display for InvalidInputKind {
 pub enum EncryptionErrorKind {
     CipherError(String),
     HashError(String),
+    /// The written output could not be verified against the original data
+    VerificationFailed(String),
+    /// The key agent could not be reached or refused the request
+    AgentError(String),
 }
 
 impl Display for EncryptionErrorKind {
@@ -210,16 +246,21 @@ impl Display for EncryptionErrorKind {
         match self {
             EncryptionErrorKind::CipherError(s) => write!(f, "Unable to apply cipher ({})", s),
             EncryptionErrorKind::HashError(s) => write!(f, "Unable to generate a hash ({})", s),
+            EncryptionErrorKind::VerificationFailed(s) => write!(f, "Unable to verify the output, original file was kept ({})", s),
+            EncryptionErrorKind::AgentError(s) => write!(f, "Key agent request failed ({})", s),
         }
     }
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum SerializeErrorKind {
     JSONParseError(String, usize, usize),
     TOMLParseError(String),
     BoxfileParseError(String),
     HeaderParseError(String),
+    /// The profile database cannot be read or written
+    DatabaseError(String),
 }
 
 impl Display for SerializeErrorKind {
@@ -229,6 +270,7 @@ impl Display for SerializeErrorKind {
             SerializeErrorKind::TOMLParseError(s) => write!(f, "Unable to parse a TOML file:\n{}", s),
             SerializeErrorKind::BoxfileParseError(s) => write!(f, "Unable to parse a boxfile:\n{}", s),
             SerializeErrorKind::HeaderParseError(s) => write!(f, "Unable to parse a boxfile header:\n{}", s),
+            SerializeErrorKind::DatabaseError(s) => write!(f, "Unable to access the profile database ({})", s),
         }
     }
 }
@@ -241,6 +283,14 @@ pub enum ProfileErrorKind {
     AlreadyExists(String),
     AuthenticationFailed,
     MismatchedProfile,
+    /// Too many failed authentication attempts were made recently
+    LockedOut(String),
+    /// The profile store is sealed and no master password was provided
+    StoreSealed,
+    /// The profile store is not sealed
+    StoreNotSealed,
+    /// The master password of the sealed profile store is invalid
+    InvalidMasterPassword,
 }
 
 impl Display for ProfileErrorKind {
@@ -252,16 +302,31 @@ impl Display for ProfileErrorKind {
             ProfileErrorKind::AlreadyExists(s) => write!(f, "Profile \"{}\" already exists", s),
             ProfileErrorKind::AuthenticationFailed => write!(f, "Authentication failed. Invalid profile password provided"),
             ProfileErrorKind::MismatchedProfile => write!(f, "Mismatched profile. File seems to be encrypted with a different one."),
+            ProfileErrorKind::LockedOut(s) => write!(f, "Profile is temporarily locked after failed authentication attempts ({})", s),
+            ProfileErrorKind::StoreSealed => write!(f, "Profile store is sealed with a master password"),
+            ProfileErrorKind::StoreNotSealed => write!(f, "Profile store is not sealed"),
+            ProfileErrorKind::InvalidMasterPassword => write!(f, "Invalid master password of the profile store provided"),
         }
     }
 }
 
 #[derive(Debug)]
-pub enum ConfigErrorKind {}
+pub enum ConfigErrorKind {
+    /// The config file is not valid TOML, contains unknown settings or values of a wrong type
+    ParseError(String),
+    /// A setting has a value outside of its allowed range
+    InvalidValue(String),
+    /// There is no setting with the provided key
+    UnknownSetting(String),
+}
 
 impl Display for ConfigErrorKind {
-    fn fmt(&self, _f: &mut Formatter<'_>) -> fmt::Result {
-        todo!()
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigErrorKind::ParseError(s) => write!(f, "Unable to parse the config ({})", s),
+            ConfigErrorKind::InvalidValue(s) => write!(f, "Invalid config value ({})", s),
+            ConfigErrorKind::UnknownSetting(s) => write!(f, "Unknown setting \"{}\"", s),
+        }
     }
 }
 
@@ -272,17 +337,45 @@ pub fn print_error(err: &Error) {
         Error::ProfileError(kind) => {
             if let ProfileErrorKind::AuthenticationFailed = kind {
                 log_warn!("Try again or use a different profile")
+            } else if let ProfileErrorKind::LockedOut(_) = kind {
+                log_warn!("Wait before trying again. Recent failures can be seen with \"databoxer profile show\"")
+            } else if let ProfileErrorKind::StoreSealed | ProfileErrorKind::InvalidMasterPassword = kind {
+                log_warn!("Provide the master password with DATABOXER_MASTER_PASSWORD or enter it when prompted")
+            } else if let ProfileErrorKind::StoreNotSealed = kind {
+                log_warn!("Profile store can be sealed with \"databoxer store lock\"")
             } else {
                 log_warn!("New profile can be created with \"databoxer profile new\"");
             }
         },
+        Error::ConfigError(ConfigErrorKind::UnknownSetting(_)) => {
+            log_warn!("All available settings can be listed with \"databoxer config list\"");
+        },
         Error::ConfigError(_) => {
             log_warn!("Please check the config file for any mistakes and try again");
-        }
+        },
+        Error::InvalidInput(InvalidInputKind::WeakPassword(_)) => {
+            log_warn!("Choose a stronger password or use \"--force-weak\" to use it anyway");
+        },
+        Error::IOError(IOErrorKind::AlreadyExists(_)) => {
+            log_warn!("Use \"--overwrite\" or \"--conflict\" to choose how existing files are handled");
+        },
+        Error::IOError(IOErrorKind::Locked(_)) => {
+            log_warn!("Wait for the other Databoxer process to finish or raise \"storage.lock_timeout\"");
+        },
         _ => {}
     }
 }
 
+/// Exits the process with the error-specific exit code. In the JSON output mode of the CLI the
+/// error is printed as the output document first
+pub fn exit_with(err: &Error) -> ! {
+    use crate::cli::output;
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "error": output::error_json(err) }));
+    }
+    std::process::exit(err.exit_code())
+}
+
 /// Macro used as a shortcut for creating a new Databoxer Error.
 /// 
 /// A new error is generated by providing the error *type* (`SomeError`) and an error *kind*
@@ -297,7 +390,7 @@ macro_rules! new_err {
         {
             use paste::paste;
             paste! {
-                use crate::core::error::{Error, [<$err Kind>]};
+                use $crate::core::error::{Error, [<$err Kind>]};
                 Error::$err([<$err Kind>]::$kind)
             }
         }
@@ -306,7 +399,7 @@ macro_rules! new_err {
         {
             use paste::paste;
             paste! {
-                use crate::core::error::{Error, [<$err Kind>]};
+                use $crate::core::error::{Error, [<$err Kind>]};
                 Error::$err([<$err Kind>]::$kind($msg.to_string()))
             }
         }
@@ -325,7 +418,7 @@ macro_rules! new_err {
 macro_rules! err_cmp {
     ($err:expr, $err_type:ident) => {
         {
-            use crate::core::error::Error;
+            use $crate::core::error::Error;
             if let Error::$err_type(_) = &$err {
                 true
             } else {
@@ -336,10 +429,10 @@ macro_rules! err_cmp {
     ($err:expr, $err_type:ident, $err_kind:ident) => {
         {
             use paste::paste;
-            use crate::core::error::Error;
+            use $crate::core::error::Error;
             if let Error::$err_type(kind) = &$err {
                 paste! {
-                    use crate::core::error::[<$err_type Kind>];
+                    use $crate::core::error::[<$err_type Kind>];
                     if let [<$err_type Kind>]::$err_kind = kind {
                         true
                     } else {
@@ -354,10 +447,10 @@ macro_rules! err_cmp {
     ($err:expr, $err_type:ident, $err_kind:ident()) => {
         {
             use paste::paste;
-            use crate::core::error::Error;
+            use $crate::core::error::Error;
             if let Error::$err_type(kind) = &$err {
                 paste! {
-                    use crate::core::error::[<$err_type Kind>];
+                    use $crate::core::error::[<$err_type Kind>];
                     if let [<$err_type Kind>]::$err_kind(_) = kind {
                         true
                     } else {
@@ -375,51 +468,51 @@ macro_rules! err_cmp {
 /// calls `error::print_error()` to log error and provide detailed information if needed
 /// 
 /// - Error kinds separated with a comma will be marked as exit-resulting: `exits_on!(err, OSError,
-/// ProfileError)`
+///   ProfileError)`
 /// - If the error kinds are seperated with a semicolon, weather they should result in an exit will
-/// be decided by the boolean expression for the kind, else will decide depending on the default
-/// value: `exits_on!(err; IOError true; ProfileError false)`
+///   be decided by the boolean expression for the kind, else will decide depending on the default
+///   value: `exits_on!(err; IOError true; ProfileError false)`
 /// - `default` keyword will specify to exit on the error based on the default value:
-/// `exits_on!(err; default)`
+///   `exits_on!(err; default)`
 /// - `all` keyword will specify to exit no matter which error kind it is: `exits_on!(err; all)`
 #[macro_export]
 macro_rules! exits_on {
     ($err:expr; default) => {
-        use crate::core::error::print_error;
+        use $crate::core::error::print_error;
         print_error(&$err);
         if $err.should_exit() {
-            std::process::exit($err.exit_code());
+            $crate::core::error::exit_with(&$err);
         }
     };
     ($err:expr; all) => {
-        use crate::core::error::print_error;
+        use $crate::core::error::print_error;
         print_error(&$err);
-        std::process::exit($err.exit_code());
+        $crate::core::error::exit_with(&$err);
     };
     ($err:expr; $($err_kind:ident),*) => {
-        use crate::core::error::{Error, print_error};
+        use $crate::core::error::{Error, print_error};
         print_error(&$err);
         match $err {
             $(
-                Error::$err_kind(_) => std::process::exit($err.exit_code());
+                Error::$err_kind(_) => $crate::core::error::exit_with(&$err);
             ),*
             _ => {}
         }
     };
     ($err:expr; $($err_kind:ident $should:expr);*) => {
-        use crate::core::error::{Error, print_error};
+        use $crate::core::error::{Error, print_error};
         print_error(&$err);
         match $err {
             $(
                 Error::$err_kind(_) => {
                     if $should {
-                        std::process::exit($err.exit_code());
+                        $crate::core::error::exit_with(&$err);
                     }
                 }
             ),*
             _ => {
                 if $err.should_exit() {
-                    std::process::exit($err.exit_code());
+                    $crate::core::error::exit_with(&$err);
                 }
             }
         }
@@ -441,10 +534,10 @@ mod tests {
         assert!(compare_kind);
         
         let failed_compare_type = err_cmp!(err, OSError);
-        assert_ne!(failed_compare_type, true);
+        assert!(!failed_compare_type);
         
         let failed_compare_kind = err_cmp!(err, ProfileError, MismatchedProfile);
-        assert_ne!(failed_compare_kind, true);
+        assert!(!failed_compare_kind);
     }
     
     #[test]
@@ -0,0 +1,128 @@
+//! Contains core logic for finding boxfiles by the original name stored in their header
+//!
+//! Only the headers of the boxfiles are read, so searching large directory trees doesn't require
+//! loading the encrypted data of every file
+
+use std::path::{Path, PathBuf};
+use regex::{Regex, RegexBuilder};
+use crate::core::encryption::boxfile::{Boxfile, BoxfileHeader};
+use crate::core::options::FindOptions;
+use crate::core::utils;
+use crate::{log_debug, log_info, new_err, Result};
+
+/// Boxfile found by its original name
+#[derive(Debug)]
+pub struct FoundBoxfile {
+    /// Path to the boxfile
+    pub path: PathBuf,
+    /// Header of the boxfile holding the original file metadata
+    pub header: BoxfileHeader,
+}
+
+/// Pattern the original names are matched against
+enum NamePattern {
+    Glob(glob::Pattern, glob::MatchOptions),
+    Regex(Regex),
+}
+
+impl NamePattern {
+    fn new(pattern: &str, options: &FindOptions) -> Result<Self> {
+        if options.regex {
+            let regex = RegexBuilder::new(pattern)
+                .case_insensitive(options.ignore_case)
+                .build()
+                .map_err(|err| new_err!(InvalidInput: InvalidPattern, err))?;
+            return Ok(NamePattern::Regex(regex))
+        }
+
+        let glob = glob::Pattern::new(pattern)
+            .map_err(|err| new_err!(InvalidInput: InvalidPattern, err))?;
+        let match_options = glob::MatchOptions {
+            case_sensitive: !options.ignore_case,
+            ..glob::MatchOptions::new()
+        };
+        Ok(NamePattern::Glob(glob, match_options))
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NamePattern::Glob(glob, match_options) => glob.matches_with(name, *match_options),
+            NamePattern::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Searches the provided paths recursively for boxfiles which original name (including the
+/// extension) matches the pattern. The pattern is a glob matching the whole name or a regular
+/// expression matching any part of it. Returns all matches sorted by their path
+pub fn find(pattern: &str, search_paths: &[PathBuf], options: &FindOptions) -> Result<Vec<FoundBoxfile>> {
+    log_info!("Searching for boxfiles matching \"{}\"", pattern);
+    let pattern = NamePattern::new(pattern, options)?;
+
+    let mut found = search_paths.iter()
+        .flat_map(|search_path| {
+            if search_path.is_dir() {
+                utils::path::find_boxfiles(search_path, true)
+            } else {
+                vec![search_path.clone()]
+            }
+        })
+        .filter_map(|path| match_boxfile(&path, &pattern))
+        .collect::<Vec<FoundBoxfile>>();
+
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    found.dedup_by(|a, b| a.path == b.path);
+    Ok(found)
+}
+
+/// Reads the header of the boxfile and returns it if the original name matches the pattern
+fn match_boxfile(path: &Path, pattern: &NamePattern) -> Option<FoundBoxfile> {
+    let header = match Boxfile::parse_header(path) {
+        Ok(header) => header,
+        Err(err) => {
+            log_debug!("Skipping \"{}\": {}", path.display(), err);
+            return None
+        }
+    };
+
+    if !pattern.matches(&header.original_name().to_string_lossy()) {
+        return None
+    }
+    Some(FoundBoxfile { path: path.to_path_buf(), header })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::encryption::cipher;
+
+    #[test]
+    fn test_find() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("databoxer-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("inner"))?;
+        std::fs::write(dir.join("no_extension"), b"not a boxfile")?;
+        std::fs::write(dir.join("invalid.box"), b"not a boxfile")?;
+
+        let key = cipher::generate_key();
+        for (name, path) in [("Report.txt", "a.box"), ("notes.txt", "inner/b.box"), ("photo.png", "inner/c.box")] {
+            let mut boxfile = Boxfile::from_data(b"data", Path::new(name))?;
+            boxfile.encrypt_data(&key)?;
+            boxfile.save_to(&dir.join(path))?;
+        }
+
+        let search_paths = [dir.clone()];
+        let glob = find("*.txt", &search_paths, &FindOptions::default())?;
+        let ignore_case = find("report.*", &search_paths, &FindOptions { ignore_case: true, ..Default::default() })?;
+        let regex = find(r"^(notes|photo)\.", &search_paths, &FindOptions { regex: true, ..Default::default() })?;
+        let invalid = find("(", &search_paths, &FindOptions { regex: true, ..Default::default() });
+        std::fs::remove_dir_all(&dir)?;
+
+        assert_eq!(glob.len(), 2);
+        assert!(glob[0].path.ends_with("a.box") && glob[1].path.ends_with("inner/b.box"));
+        assert_eq!(ignore_case.len(), 1);
+        assert_eq!(ignore_case[0].header.original_name(), "Report.txt");
+        assert_eq!(regex.len(), 2);
+        assert!(matches!(invalid, Err(crate::Error::InvalidInput(_))));
+        Ok(())
+    }
+}
@@ -1,3 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// Amount of log messages printed by default, set in the config
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    /// Don't print any log messages
+    Quiet,
+    /// Print only results, warnings and errors
+    #[default]
+    Normal,
+    /// Print all log messages
+    Verbose,
+    /// Print all log messages together with the debug information
+    Debug,
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq)]
 pub enum LogType {
     INFO,
@@ -25,10 +43,10 @@ impl LogType {
 macro_rules! log {
     ($log_type:ident: $($arg:tt)*) => {
         {
-            use crate::core::logs::LogType::*;
-            match crate::app::get_app_mode() {
+            use $crate::core::logs::LogType::*;
+            match $crate::app::get_app_mode() {
                 AppMode::CLI => {
-                    let logger = crate::cli::logger::LOGGER.lock().unwrap();
+                    let logger = $crate::cli::logger::LOGGER.lock().unwrap();
                     logger.log(LogType::$log_type, format_args!($($arg)*));
                 }
                 AppMode::GUI => {
@@ -43,8 +61,8 @@ macro_rules! log {
 macro_rules! log_info {
     ($($arg:tt)*) => {
         {
-            use crate::cli::logger::LOGGER;
-            use crate::core::logs::LogType;
+            use $crate::cli::logger::LOGGER;
+            use $crate::core::logs::LogType;
             let logger = LOGGER.lock().unwrap();
             logger.log(LogType::INFO, format_args!($($arg)*));
         }
@@ -55,8 +73,8 @@ macro_rules! log_info {
 macro_rules! log_warn {
     ($($arg:tt)*) => {
         {
-            use crate::cli::logger::LOGGER;
-            use crate::core::logs::LogType;
+            use $crate::cli::logger::LOGGER;
+            use $crate::core::logs::LogType;
             let logger = LOGGER.lock().unwrap();
             logger.log(LogType::WARN, format_args!($($arg)*));
         }
@@ -67,8 +85,8 @@ macro_rules! log_warn {
 macro_rules! log_success {
     ($($arg:tt)*) => {
         {
-            use crate::cli::logger::LOGGER;
-            use crate::core::logs::LogType;
+            use $crate::cli::logger::LOGGER;
+            use $crate::core::logs::LogType;
             let logger = LOGGER.lock().unwrap();
             logger.log(LogType::SUCCESS, format_args!($($arg)*));
         }
@@ -79,8 +97,8 @@ macro_rules! log_success {
 macro_rules! log_error {
     ($($arg:tt)*) => {
         {
-            use crate::cli::logger::LOGGER;
-            use crate::core::logs::LogType;
+            use $crate::cli::logger::LOGGER;
+            use $crate::core::logs::LogType;
             let logger = LOGGER.lock().unwrap();
             logger.log(LogType::ERROR, format_args!($($arg)*));
         }
@@ -91,8 +109,8 @@ macro_rules! log_error {
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         {
-            use crate::cli::logger::LOGGER;
-            use crate::core::logs::LogType;
+            use $crate::cli::logger::LOGGER;
+            use $crate::core::logs::LogType;
             let logger = LOGGER.lock().unwrap();
             logger.log(LogType::DEBUG, format_args!($($arg)*));
         }
@@ -1,109 +1,126 @@
 //! Contains the core functionality of the program and main subcommand logic
 
-use std::collections::VecDeque;
+use std::collections::HashSet;
+use std::ffi::OsString;
 use std::fs;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
-use chrono::{DateTime, Local};
-use crate::core::data::{io, keys};
-use crate::core::encryption::boxfile;
-use crate::{log_debug, log_info, log_warn, new_err, Result};
+use crate::core::data::{io, os};
+use crate::core::data::tracking::TrackedFile;
+use crate::core::encryption::boxfile::{self, BoxfileHeader, BoxfileInfo, BoxfileMetadata};
+use crate::core::encryption::cipher::KeyWrapper;
+use crate::app::{self, AppMode};
+use crate::cli::prompts;
+use crate::core::error::IOErrorKind;
+use crate::core::options::{ConflictPolicy, DecryptionOptions, DeletePolicy, EncryptionOptions};
+use crate::core::session::Session;
+use crate::{log_debug, log_info, log_warn, new_err, Error, Result};
 pub mod utils;
 pub mod error;
+pub mod batch;
+#[cfg(unix)]
+pub mod agent;
+pub mod session;
 pub mod data;
 pub mod encryption;
 pub mod profile;
+pub mod config;
+pub mod store;
+pub mod tracked;
+pub mod find;
 pub mod key;
 pub mod options;
 pub mod logs;
 
 /// Encrypts the file at provided path using current profile's key. Password is required to verify
 /// and get access to current profile. Additional options can be supplied to change the encryption
-/// process
+/// process. Returns the path of the written boxfile or `None` if the file was skipped
 pub fn encrypt(
-        input_path: &Path,
-        password: &str,
-        keep_original_name: bool,
-        output_paths: &mut Option<VecDeque<PathBuf>>,
-) -> Result<()> {
-    log_info!("Starting encryption...");
+    input_path: &Path,
+    password: &str,
+    options: &mut EncryptionOptions,
+) -> Result<Option<PathBuf>> {
+    Session::unlock_current(password)?.encrypt(input_path, options)
+}
+
+/// Decryption the file at provided path using current profile's key. Password is required to
+/// verify and get access to current profile. Additional options can be supplied to change the
+/// decryption process. Returns the path of the written file or `None` if the file was skipped
+pub fn decrypt(
+    input_path: &Path,
+    password: &str,
+    options: &mut DecryptionOptions,
+) -> Result<Option<PathBuf>> {
+    Session::unlock_current(password)?.decrypt(input_path, options)
+}
+
+/// Works out the output path for the file to be encrypted to, taking the next custom output path
+/// if there is one, and resolves a conflict if the path is already taken. Paths in `reserved` are
+/// treated as taken. Returns `None` if the file should be skipped
+fn plan_encryption(
+    input_path: &Path,
+    options: &mut EncryptionOptions,
+    reserved: &HashSet<PathBuf>,
+) -> Result<Option<PathBuf>> {
     if let Some(extension) = input_path.extension() {
         if extension == "box" {
             return Err(new_err!(InvalidInput: InvalidFile, "Already encrypted"))
         }
     }
 
-    let mut boxfile = boxfile::Boxfile::new(input_path)?;
-    let key = keys::get_key(password)?;
-    boxfile.encrypt_data(&key)?;
+    let custom_path = options.output_paths.as_mut().and_then(|paths| paths.pop_front());
+    let mut output_path = match custom_path {
+        Some(mut path) => {
+            log_debug!("Writing to custom output path: {:?}", path);
 
-    let mut output_path = match output_paths {
-        Some(ref mut paths) => {
-            if let Some(mut path) = paths.pop_front() {
-                log_debug!("Writing to custom output path: {:?}", path);
-
-                if path.file_name() == None {
-                    path.set_file_name(uuid::Uuid::new_v4().to_string());
-                }
-                path
-            } else {
-                input_path.to_path_buf()
+            if path.file_name().is_none() {
+                path.set_file_name(uuid::Uuid::new_v4().to_string());
             }
+            path
         },
-        None => input_path.to_path_buf()
+        None => {
+            let mut path = get_default_output_path(input_path, options.output_dir.as_deref())?;
+            if !options.keep_original_name {
+                path.set_file_name(uuid::Uuid::new_v4().to_string());
+            }
+            path
+        }
     };
-    
-    if !keep_original_name {
-        output_path.set_file_name(uuid::Uuid::new_v4().to_string());
-    }
 
     output_path.set_extension("box");
-    boxfile.save_to(&output_path)?;
-    fs::remove_file(&input_path)?;
-
-    Ok(())
+    resolve_conflict(output_path, options.conflict_policy, reserved)
 }
 
-/// Decryption the file at provided path using current profile's key. Password is required to
-/// verify and get access to current profile. Additional options can be supplied to change the
-/// decryption process
-pub fn decrypt(
+/// Works out the output path for the boxfile to be decrypted to based on its header, taking the
+/// next custom output path if there is one, and resolves a conflict if the path is already taken.
+/// Paths in `reserved` are treated as taken. Returns `None` if the file should be skipped
+fn plan_decryption(
     input_path: &Path,
-    password: &str,
-    output_paths: &mut Option<VecDeque<PathBuf>>,
-) -> Result<()> {
-    log_info!("Starting decryption...");
-    let mut boxfile = boxfile::Boxfile::parse(&input_path)?;
-    let key = keys::get_key(password)?;
-    boxfile.decrypt_data(&key)?;
-    let (original_name, original_extension) = boxfile.file_info();
-    let file_data = boxfile.file_data()?;
+    header: &BoxfileHeader,
+    options: &mut DecryptionOptions,
+    reserved: &HashSet<PathBuf>,
+) -> Result<Option<PathBuf>> {
+    let original_name = &header.name;
+    let original_extension = &header.extension;
 
-    log_info!("Validating checksum...");
-    if boxfile.verify_checksum()? {
-        log_info!("Checksum verification successful");
-    } else {
-        log_warn!("Checksum verification failed. Data seems to be tampered with");
-    }
-
-    let output_path = match output_paths {
+    let output_path = match options.output_paths {
         Some(ref mut paths) => {
             if let Some(mut path) = paths.pop_front() {
                 log_debug!("Writing to custom output path: {:?}", path);
 
-                if path.file_name() == None {
+                if path.file_name().is_none() {
                     path.set_file_name(original_name);
                     if let Some(extension) = original_extension {
                         path.set_extension(extension);
                     }
-                } else if path.extension() == None {
+                } else if path.extension().is_none() {
                     if let Some(extension) = original_extension {
                         path.set_extension(extension);
                     }
                 }
                 path
             } else {
-                let mut path = input_path.to_path_buf();
+                let mut path = get_default_output_path(input_path, options.output_dir.as_deref())?;
                 path.set_file_name(original_name);
                 if let Some(extension) = original_extension {
                     path.set_extension(extension);
@@ -112,7 +129,7 @@ pub fn decrypt(
             }
         },
         None => {
-            let mut path = input_path.to_path_buf();
+            let mut path = get_default_output_path(input_path, options.output_dir.as_deref())?;
             path.set_file_name(original_name);
             if let Some(extension) = original_extension {
                 path.set_extension(extension);
@@ -120,55 +137,224 @@ pub fn decrypt(
             path
         }
     };
-    
-    io::write_bytes(&output_path, &file_data, true)?;
-    fs::remove_file(&input_path)?;
 
-    Ok(())
+    resolve_conflict(output_path, options.conflict_policy, reserved)
 }
 
-/// Parses the provided boxfile and retrieves original metadata from the header. Returns a vector
-/// containing string with retrieved information, skipping the unknown metadata unless specified
-/// not to
-pub fn get_information(
-    input_path: &Path,
-    show_unknown: bool
-) -> Result<Vec<String>> {
-    fn format_time(system_time: SystemTime) -> String {
-        let time: DateTime<Local> = system_time.into();
-        format!("{}", time.format("%d.%m.%Y %T"))
+/// Returns the path the output is written to if no custom output path is provided: the input path
+/// itself or the input file name inside the output directory. Errors if the output directory
+/// doesn't exist
+fn get_default_output_path(input_path: &Path, output_dir: Option<&Path>) -> Result<PathBuf> {
+    match (output_dir, input_path.file_name()) {
+        (Some(output_dir), _) if !output_dir.is_dir() => {
+            Err(new_err!(InvalidInput: InvalidFile, format!("Output directory \"{}\" doesn't exist", output_dir.display())))
+        },
+        (Some(output_dir), Some(file_name)) => Ok(output_dir.join(file_name)),
+        _ => Ok(input_path.to_path_buf())
     }
+}
 
-    log_info!("Getting file information...");
-    let boxfile = boxfile::Boxfile::parse(&input_path)?;
-    let header = boxfile.header;
+/// Encrypts the file with the provided key, writes the boxfile to the output path and removes the
+/// input file according to the delete policy. Returns the registry entry of the written boxfile
+fn encrypt_file(
+    input_path: &Path,
+    output_path: &Path,
+    key: &(impl KeyWrapper + ?Sized),
+    options: &EncryptionOptions
+) -> Result<TrackedFile> {
+    let size = fs::metadata(input_path)?.len();
+    let settings = boxfile::BoxfileSettings {
+        cipher: options.cipher,
+        compression: options.compression,
+        padding: options.padding,
+    };
+    let mut boxfile = boxfile::Boxfile::new(input_path, settings)?;
+    boxfile.encrypt_data(key)?;
+    boxfile.save_to(output_path)?;
 
-    let mut file_information = Vec::new();
-    file_information.push(format!("Name: {:?}", header.name));
+    remove_input(input_path, options.delete_policy, || {
+        let mut saved_boxfile = boxfile::Boxfile::parse(output_path)?;
+        saved_boxfile.decrypt_data(key)?;
+        if !saved_boxfile.verify_checksum()? {
+            return Err(new_err!(EncryptionError: VerificationFailed, "Checksum mismatch"))
+        }
+        Ok(())
+    })?;
 
-    if let Some(extension) = header.extension {
-        file_information.push(format!("Extension: {:?}", extension));
+    Ok(TrackedFile::new(&boxfile.header, output_path, size))
+}
+
+/// Decrypts the parsed boxfile with the provided key, writes the original file to the output path
+/// restoring its attributes if needed and removes the input boxfile according to the delete policy
+fn decrypt_boxfile(
+    mut boxfile: boxfile::Boxfile,
+    input_path: &Path,
+    output_path: &Path,
+    key: &(impl KeyWrapper + ?Sized),
+    options: &DecryptionOptions,
+) -> Result<()> {
+    boxfile.decrypt_data(key)?;
+    let file_data = boxfile.file_data()?;
+
+    log_info!("Validating checksum...");
+    let verified = boxfile.verify_checksum()?;
+    if verified {
+        log_info!("Checksum verification successful");
     } else {
-        file_information.push("Extension: None".to_string());
+        log_warn!("Checksum verification failed. Data seems to be tampered with, so the original file attributes are not restored");
     }
+    
+    io::write_bytes(output_path, &file_data)?;
+    if verified {
+        let file_metadata = boxfile.file_metadata()?;
+        restore_attributes(
+            output_path,
+            &boxfile.header,
+            &file_metadata,
+            options.preserve_attributes,
+            options.preserve_owner
+        );
+    }
+
+    remove_input(input_path, options.delete_policy, || {
+        if io::read_bytes(output_path)? != **file_data {
+            return Err(new_err!(EncryptionError: VerificationFailed, "Written data differs"))
+        }
+        Ok(())
+    })
+}
 
-    if let Some(system_time) = header.create_time {
-        file_information.push(format!("Create time: {}", format_time(system_time)));
-    } else if show_unknown {
-        file_information.push("Create time: Unknown".to_string());
+/// Restores the original file attributes stored in the header to the decrypted file. Failures are
+/// not fatal, as the file data itself was already written, so they are only reported. The setuid,
+/// setgid and sticky bits are only restored together with the owner
+fn restore_attributes(
+    output_path: &Path,
+    header: &BoxfileHeader,
+    metadata: &BoxfileMetadata,
+    preserve_attributes: bool,
+    preserve_owner: bool
+) {
+    if preserve_attributes {
+        log_info!("Restoring original file attributes...");
+        if let Err(err) = os::set_times(output_path, header.modify_time, header.access_time) {
+            log_warn!("Unable to restore original file times: {}", err);
+        }
+        if let Some(mode) = header.permissions {
+            let mode = if preserve_owner { mode } else { mode & 0o777 };
+            if let Err(err) = os::set_permissions(output_path, mode) {
+                log_warn!("Unable to restore original file permissions: {}", err);
+            }
+        }
+        restore_extended_attributes(output_path, &metadata.extended_attributes);
+    }
+
+    if preserve_owner {
+        if let Some((uid, gid)) = header.owner {
+            log_info!("Restoring original file owner...");
+            if let Err(err) = os::set_owner(output_path, uid, gid) {
+                log_warn!("Unable to restore original file owner: {}", err);
+            }
+        }
+    }
+}
+
+/// Restores the original extended attributes (including ACLs) to the decrypted file, reporting
+/// which ones were restored. Stops early if the filesystem doesn't support them at all
+fn restore_extended_attributes(output_path: &Path, attributes: &[(OsString, Vec<u8>)]) {
+    if attributes.is_empty() {
+        return
     }
 
-    if let Some(system_time) = header.modify_time {
-        file_information.push(format!("Modify time: {}", format_time(system_time)));
-    } else if show_unknown {
-        file_information.push("Modify time: Unknown".to_string());
+    let mut restored = Vec::new();
+    for (name, value) in attributes {
+        match os::set_extended_attribute(output_path, name, value) {
+            Ok(_) => restored.push(name.to_string_lossy()),
+            Err(Error::IOError(IOErrorKind::StdError(err))) if err.kind == std::io::ErrorKind::Unsupported => {
+                log_warn!("Extended attributes are not supported by the filesystem, skipping them");
+                break
+            },
+            Err(err) => log_warn!("Unable to restore extended attribute {:?}: {}", name, err)
+        }
     }
 
-    if let Some(system_time) = header.access_time {
-        file_information.push(format!("Access time: {}", format_time(system_time)));
-    } else if show_unknown {
-        file_information.push("Access time: Unknown".to_string());
+    if !restored.is_empty() {
+        log_info!("Restored extended attributes: {}", restored.join(", "));
     }
+}
 
-    Ok(file_information)
-}
\ No newline at end of file
+/// Checks whether the output file already exists and resolves the conflict according to the
+/// provided policy. Returns the path which should be written to or `None` if the file should be
+/// skipped. Paths in `reserved` are treated as already existing. The user is only asked what to do
+/// if running in CLI mode from a terminal
+fn resolve_conflict(
+    output_path: PathBuf,
+    conflict_policy: ConflictPolicy,
+    reserved: &HashSet<PathBuf>
+) -> Result<Option<PathBuf>> {
+    if !output_path.exists() && !reserved.contains(&output_path) {
+        return Ok(Some(output_path))
+    }
+
+    let conflict_policy = match conflict_policy {
+        ConflictPolicy::Ask => {
+            if *app::get_app_mode() != AppMode::CLI || !std::io::stdin().is_terminal() {
+                return Err(Error::IOError(IOErrorKind::AlreadyExists(output_path)))
+            }
+            prompts::prompt_conflict(&output_path)
+        },
+        policy => policy
+    };
+
+    match conflict_policy {
+        ConflictPolicy::Overwrite => {
+            log_warn!("Overwriting existing file \"{}\"", output_path.display());
+            Ok(Some(output_path))
+        },
+        ConflictPolicy::RenameWithSuffix => {
+            let renamed_path = utils::path::get_free_path(&output_path, reserved);
+            log_info!("File \"{}\" already exists. Writing to \"{}\" instead", output_path.display(), renamed_path.display());
+            Ok(Some(renamed_path))
+        },
+        ConflictPolicy::Skip | ConflictPolicy::Ask => {
+            log_warn!("Skipping, as file \"{}\" already exists", output_path.display());
+            Ok(None)
+        },
+    }
+}
+
+/// Removes the input file according to the provided delete policy. The output verification is
+/// only run for the policies which require it and the input is kept if it fails
+fn remove_input(
+    input_path: &Path,
+    delete_policy: DeletePolicy,
+    verify_output: impl FnOnce() -> Result<()>
+) -> Result<()> {
+    match delete_policy {
+        DeletePolicy::Keep => {
+            log_debug!("Keeping the original file");
+        },
+        DeletePolicy::Delete => {
+            fs::remove_file(input_path)?;
+        },
+        DeletePolicy::Verify => {
+            log_info!("Verifying the output...");
+            verify_output()?;
+            fs::remove_file(input_path)?;
+        },
+        DeletePolicy::Secure => {
+            log_info!("Verifying the output...");
+            verify_output()?;
+            log_info!("Securely removing the original file...");
+            io::secure_remove(input_path)?;
+        },
+    }
+
+    Ok(())
+}
+
+/// Reads the information about the provided boxfile and the original file it contains from its
+/// header, without reading the encrypted body
+pub fn get_information(input_path: &Path) -> Result<BoxfileInfo> {
+    log_info!("Getting file information...");
+    BoxfileInfo::read(input_path)
+}
@@ -1,62 +1,138 @@
 //! Provides structs which hold optional parameters for the API functions for easier option supply
 
 use std::{collections::VecDeque, path::PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// Policy for what happens with the input file after a successful encryption or decryption
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletePolicy {
+    /// Delete the input file right after the output is written
+    #[default]
+    Delete,
+    /// Keep the input file untouched
+    Keep,
+    /// Delete the input file only after the written output was read back and verified
+    Verify,
+    /// Verify the output like `Verify` and overwrite the input file with random data before
+    /// deleting it
+    Secure,
+}
+
+/// Policy for resolving a conflict when the output file already exists
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// Ask the user what to do. Results in an error if the user cannot be prompted
+    #[default]
+    Ask,
+    /// Overwrite the existing file
+    Overwrite,
+    /// Skip the file, leaving both the input and the existing file untouched
+    Skip,
+    /// Add a numbered suffix to the output file name (e.g. `file (1).txt`)
+    RenameWithSuffix,
+}
+
+/// Cipher used to encrypt the body of the boxfile
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub enum Cipher {
+    /// ChaCha20 stream cipher with the Poly1305 authenticator
+    #[default]
+    #[serde(rename = "chacha20-poly1305")]
+    ChaCha20Poly1305,
+}
+
+/// Compression applied to the file data before it is encrypted
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// Store the data as is
+    #[default]
+    None,
+    /// Compress the data with DEFLATE
+    Deflate,
+}
+
+/// Policy for the length of the padding added to the file data before encryption
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PaddingPolicy {
+    /// Padding grows with the length of the data, up to 32 bytes
+    #[default]
+    Length,
+    /// Pad the data to a multiple of 64 bytes, so only the approximate length is revealed
+    Block,
+    /// Padding of random length between 1 and 255 bytes
+    Random,
+}
 
 /// Options for encryption
+#[derive(Default)]
 pub struct EncryptionOptions {
     /// Don't replace the name with a random UUID for the encrypted file
     pub keep_original_name: bool,
     /// Contains an output path for each file
-    pub output_paths: Option<VecDeque<PathBuf>>
-}
-
-impl Default for EncryptionOptions {
-    fn default() -> Self {
-        EncryptionOptions {
-            keep_original_name: false,
-            output_paths: None,
-        }
-    }
+    pub output_paths: Option<VecDeque<PathBuf>>,
+    /// Directory to write the output files to instead of next to the input files. Custom output
+    /// paths take precedence
+    pub output_dir: Option<PathBuf>,
+    /// Cipher used to encrypt the files
+    pub cipher: Cipher,
+    /// Compression applied to the files before encryption
+    pub compression: Compression,
+    /// How much padding is added to the files before encryption
+    pub padding: PaddingPolicy,
+    /// What to do with the input file after it was processed
+    pub delete_policy: DeletePolicy,
+    /// What to do if the output file already exists
+    pub conflict_policy: ConflictPolicy,
 }
 
 /// Options for decryption
+#[derive(Default)]
 pub struct DecryptionOptions {
     /// Contains an output path for each file
-    pub output_paths: Option<VecDeque<PathBuf>>
+    pub output_paths: Option<VecDeque<PathBuf>>,
+    /// Directory to write the output files to instead of next to the input files. Custom output
+    /// paths take precedence
+    pub output_dir: Option<PathBuf>,
+    /// What to do with the input file after it was processed
+    pub delete_policy: DeletePolicy,
+    /// What to do if the output file already exists
+    pub conflict_policy: ConflictPolicy,
+    /// Restore the original modify and access times and permissions of the file
+    pub preserve_attributes: bool,
+    /// Restore the original owner of the file (usually requires elevated privileges)
+    pub preserve_owner: bool,
 }
 
-impl Default for DecryptionOptions {
-    fn default() -> Self {
-        DecryptionOptions {
-            output_paths: None,
-        }
-    }
+/// Options for finding boxfiles by their original name
+#[derive(Default)]
+pub struct FindOptions {
+    /// Treat the pattern as a regular expression instead of a glob
+    pub regex: bool,
+    /// Match the original names regardless of their case
+    pub ignore_case: bool,
 }
 
-/// Options for file information
-pub struct InformationOptions {
-    /// Show unknown metadata with the rest
-    pub show_unknown: bool,
+/// Options for profile creation
+#[derive(Default)]
+pub struct ProfileCreateOptions {
+    /// Create the profile even if the password doesn't satisfy the password policy
+    pub force_weak: bool,
 }
 
-impl Default for InformationOptions {
-    fn default() -> Self {
-        InformationOptions {
-            show_unknown: false,
-        }
-    }
+/// Options for sealing the profile store
+#[derive(Default)]
+pub struct StoreLockOptions {
+    /// Seal the store even if the master password doesn't satisfy the password policy
+    pub force_weak: bool,
 }
 
 /// Options for key retrieval
+#[derive(Default)]
 pub struct KeyGetOptions {
     /// Format encryption key as list of bytes
     pub as_byte_array: bool
 }
-
-impl Default for KeyGetOptions {
-    fn default() -> Self {
-        KeyGetOptions {
-            as_byte_array: false,
-        }
-    }
-}
\ No newline at end of file
@@ -1,13 +1,38 @@
 //! Contains core logic for profile manipulation subcommands
 
+use std::time::{Duration, SystemTime};
 use crate::core::data;
 use crate::core::data::profile::Profile;
-use crate::{log_info, new_err};
+use crate::core::utils::password;
+use crate::{log_info, log_warn, new_err};
 
-pub fn create(password: &str, profile_name: &str) -> crate::Result<()> {
+/// Information about a profile and its recent failed authentication attempts
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileInfo {
+    pub name: String,
+    /// Whether the profile is currently selected
+    pub current: bool,
+    /// Number of failed authentication attempts since the last successful one
+    pub failed_attempts: u32,
+    pub last_failed_attempt: Option<SystemTime>,
+    /// Time left until the profile can be unlocked again, if it's locked out
+    pub locked_for: Option<Duration>,
+}
+
+pub fn create(password: &str, profile_name: &str, force_weak: bool) -> crate::Result<()> {
     log_info!("Creating a new profile with name \"{}\"", profile_name);
+    let config = data::get_config()?;
+    if let Err(err) = password::check_strength(password, &config.general) {
+        if !force_weak {
+            return Err(err)
+        }
+        log_warn!("Using a weak password for profile \"{}\" as forced: {}", profile_name, err);
+    }
+
+    // the password is hashed before locking the profile store, as it takes a while
+    let profile = Profile::new(profile_name, password)?;
     let mut profiles = data::get_profiles()?;
-    profiles.new_profile(Profile::new(profile_name, password)?)?;
+    profiles.new_profile(profile)?;
     Ok(())
 }
 
@@ -40,12 +65,41 @@ pub fn get_current() -> crate::Result<String> {
     Ok(profile.name.to_string())
 }
 
+pub fn show(profile_name: Option<&str>) -> crate::Result<ProfileInfo> {
+    log_info!("Getting profile information");
+    let config = data::get_config()?.general;
+    let mut profiles = data::get_profiles()?;
+    let profile = match profile_name {
+        Some(name) => profiles.find_profile(name)?,
+        None => profiles.get_current_profile()?
+    };
+
+    let (failed_attempts, last_failed_attempt) = profile.get_failed_attempts();
+    let locked_for = profile.get_throttle(config.lockout_threshold, Duration::from_secs(config.lockout_time));
+    let name = profile.name.clone();
+
+    Ok(ProfileInfo {
+        current: profiles.is_current(&name),
+        name,
+        failed_attempts,
+        last_failed_attempt,
+        locked_for,
+    })
+}
+
 pub fn get_all() -> crate::Result<Vec<String>> {
     log_info!("Listing all available profiles");
 
     let profiles = data::get_profiles()?;
-    let profile_list = profiles.get_profiles().into_iter()
+    let profile_list = profiles.get_profiles().iter()
         .map(|p| p.name.to_string())
         .collect::<Vec<String>>();
     Ok(profile_list)
-}
\ No newline at end of file
+}
+
+/// Converts the JSON profile store into the SQLite one. Returns the number of migrated profiles
+pub fn migrate_store() -> crate::Result<usize> {
+    log_info!("Migrating the profile store to SQLite");
+    let data_directory = data::os::get_data_dir()?;
+    data::store::migrate(&data_directory, data::get_lock_timeout())
+}
@@ -0,0 +1,217 @@
+//! Contains the `Session` struct, which holds the key of an unlocked profile in memory. The profile
+//! password is verified and the key is derived only once when the session is created, so any
+//! number of files can then be encrypted and decrypted without running the password hashing again
+//!
+//! The key is kept in a `Secret`, so it is zeroized as soon as the session is dropped. A session
+//! can also be connected to a running key agent instead, in which case the key is never held by
+//! the session at all
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::core::batch::{self, BatchResult};
+use crate::core::data;
+use crate::core::data::tracking::TrackedFile;
+use crate::core::encryption::boxfile::{Boxfile, BoxfileInfo};
+use crate::core::encryption::cipher::KeyWrapper;
+use crate::core::encryption::secret::Secret;
+use crate::core::options::{DecryptionOptions, EncryptionOptions};
+use crate::{log_debug, log_info, log_warn, Key, Result};
+
+/// Unlocked profile holding its decrypted encryption key or a connection to the agent holding it
+pub struct Session {
+    profile_name: String,
+    key: SessionKey,
+}
+
+/// Source of the profile key used by the session
+enum SessionKey {
+    /// Key decrypted by this process
+    Local(Secret<Key>),
+    /// Key held by a running agent
+    #[cfg(unix)]
+    Agent(super::agent::AgentClient),
+}
+
+impl Session {
+    /// Unlocks the profile with the provided name. Errors if the profile doesn't exist or the
+    /// password is invalid
+    pub fn unlock(profile_name: &str, password: &str) -> Result<Self> {
+        log_debug!("Unlocking profile \"{}\"", profile_name);
+        let mut profiles = data::get_profiles()?;
+        let key = profiles.authenticate(profile_name, |profile| profile.get_key(password))?;
+
+        Ok(Session {
+            profile_name: profile_name.to_string(),
+            key: SessionKey::Local(key),
+        })
+    }
+
+    /// Unlocks the currently selected profile. Errors if no profile is selected or the password is
+    /// invalid
+    pub fn unlock_current(password: &str) -> Result<Self> {
+        log_debug!("Unlocking current profile");
+        let mut profiles = data::get_profiles()?;
+        let profile_name = profiles.get_current_profile()?.name.clone();
+        let key = profiles.authenticate(&profile_name, |profile| profile.get_key(password))?;
+
+        Ok(Session {
+            profile_name,
+            key: SessionKey::Local(key),
+        })
+    }
+
+    /// Connects to the key agent listening at the provided socket. The session uses the profile
+    /// unlocked by the agent without ever receiving its key
+    #[cfg(unix)]
+    pub fn connect_agent(socket_path: &Path) -> Result<Self> {
+        let (client, profile_name) = super::agent::AgentClient::connect(socket_path)?;
+        log_debug!("Using profile \"{}\" unlocked by the agent", profile_name);
+
+        Ok(Session {
+            profile_name,
+            key: SessionKey::Agent(client),
+        })
+    }
+
+    /// Returns the name of the unlocked profile
+    pub fn profile_name(&self) -> &str {
+        &self.profile_name
+    }
+
+    /// Encrypts the file at provided path using the session key. Returns the path of the written
+    /// boxfile or `None` if the file was skipped
+    pub fn encrypt(&self, input_path: &Path, options: &mut EncryptionOptions) -> Result<Option<PathBuf>> {
+        log_info!("Starting encryption...");
+        let output_path = match super::plan_encryption(input_path, options, &HashSet::new())? {
+            Some(path) => path,
+            None => return Ok(None)
+        };
+
+        let tracked_file = super::encrypt_file(input_path, &output_path, self, options)?;
+        self.track(vec![tracked_file], Vec::new());
+        Ok(Some(output_path))
+    }
+
+    /// Decrypts the boxfile at provided path using the session key. Returns the path of the written
+    /// file or `None` if the file was skipped
+    pub fn decrypt(&self, input_path: &Path, options: &mut DecryptionOptions) -> Result<Option<PathBuf>> {
+        log_info!("Starting decryption...");
+        let boxfile = Boxfile::parse(input_path)?;
+        let output_path = match super::plan_decryption(input_path, &boxfile.header, options, &HashSet::new())? {
+            Some(path) => path,
+            None => return Ok(None)
+        };
+
+        let file_id = boxfile.header.file_id();
+        super::decrypt_boxfile(boxfile, input_path, &output_path, self, options)?;
+        if !input_path.exists() {
+            self.track(Vec::new(), vec![file_id]);
+        }
+        Ok(Some(output_path))
+    }
+
+    /// Encrypts all provided files concurrently using the session key. Returns the results for
+    /// each file in the order of the input
+    pub fn encrypt_batch(
+        &self,
+        input_paths: &[PathBuf],
+        options: &mut EncryptionOptions,
+        threads: usize
+    ) -> Vec<BatchResult> {
+        let (results, tracked_files) = batch::encrypt_batch(input_paths, self, options, threads);
+        self.track(tracked_files, Vec::new());
+        results
+    }
+
+    /// Decrypts all provided files concurrently using the session key. Returns the results for
+    /// each file in the order of the input
+    pub fn decrypt_batch(
+        &self,
+        input_paths: &[PathBuf],
+        options: &mut DecryptionOptions,
+        threads: usize
+    ) -> Vec<BatchResult> {
+        let (results, removed_ids) = batch::decrypt_batch(input_paths, self, options, threads);
+        self.track(Vec::new(), removed_ids);
+        results
+    }
+
+    /// Records the written boxfiles and removes the decrypted ones from the registry of the
+    /// profile.
+    /// Failures are only reported, as the files themselves were already processed
+    fn track(&self, encrypted: Vec<TrackedFile>, decrypted: Vec<String>) {
+        if encrypted.is_empty() && decrypted.is_empty() {
+            return
+        }
+        if let Err(err) = data::tracking::record(&self.profile_name, encrypted, &decrypted) {
+            log_warn!("Unable to update the tracked files of profile \"{}\": {}", self.profile_name, err);
+        }
+    }
+
+    /// Retrieves original metadata from the header of the provided boxfile
+    pub fn information(&self, input_path: &Path) -> Result<BoxfileInfo> {
+        super::get_information(input_path)
+    }
+}
+
+impl KeyWrapper for Session {
+    fn wrap_key(&self, data_key: &Key) -> Result<Vec<u8>> {
+        match &self.key {
+            SessionKey::Local(key) => key.wrap_key(data_key),
+            #[cfg(unix)]
+            SessionKey::Agent(client) => client.wrap_key(data_key),
+        }
+    }
+
+    fn unwrap_key(&self, wrapped_key: &[u8]) -> Result<Secret<Key>> {
+        match &self.key {
+            SessionKey::Local(key) => key.unwrap_key(wrapped_key),
+            #[cfg(unix)]
+            SessionKey::Agent(client) => client.unwrap_key(wrapped_key),
+        }
+    }
+
+    fn legacy_key(&self) -> Result<Secret<Key>> {
+        match &self.key {
+            SessionKey::Local(key) => key.legacy_key(),
+            #[cfg(unix)]
+            SessionKey::Agent(client) => client.legacy_key(),
+        }
+    }
+}
+
+/// Never prints the key itself
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match &self.key {
+            SessionKey::Local(_) => "<redacted>",
+            #[cfg(unix)]
+            SessionKey::Agent(_) => "<agent>",
+        };
+
+        f.debug_struct("Session")
+            .field("profile_name", &self.profile_name)
+            .field("key", &key)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::encryption::cipher;
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts_key() {
+        let key = cipher::generate_key();
+        let session = Session {
+            profile_name: "test".to_string(),
+            key: SessionKey::Local(Secret::new(key)),
+        };
+
+        let output = format!("{:?}", session);
+        assert!(output.contains("test"));
+        assert!(!output.contains(&format!("{:?}", key)));
+    }
+}
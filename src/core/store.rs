@@ -0,0 +1,30 @@
+//! Contains core logic for profile store manipulation subcommands
+
+use crate::core::data;
+use crate::core::utils::password;
+use crate::{log_info, log_warn};
+
+pub fn lock(master_password: &str, force_weak: bool) -> crate::Result<()> {
+    log_info!("Sealing the profile store with a master password");
+    let config = data::get_config()?;
+    if let Err(err) = password::check_strength(master_password, &config.general) {
+        if !force_weak {
+            return Err(err)
+        }
+        log_warn!("Using a weak master password as forced: {}", err);
+    }
+
+    let data_directory = data::os::get_data_dir()?;
+    data::store::seal(&data_directory, master_password, data::get_lock_timeout())
+}
+
+pub fn unlock(master_password: &str) -> crate::Result<()> {
+    log_info!("Unsealing the profile store");
+    let data_directory = data::os::get_data_dir()?;
+    data::store::unseal(&data_directory, master_password, data::get_lock_timeout())
+}
+
+pub fn is_sealed() -> crate::Result<bool> {
+    let data_directory = data::os::get_data_dir()?;
+    Ok(data::store::is_sealed(&data_directory))
+}
@@ -0,0 +1,37 @@
+//! Contains core logic for tracked file subcommands
+
+use std::path::PathBuf;
+use crate::core::data;
+use crate::core::data::tracking::{self, Reconciliation, TrackedFile};
+use crate::log_info;
+
+pub fn list() -> crate::Result<Vec<TrackedFile>> {
+    log_info!("Listing the tracked files of the current profile");
+    let mut profiles = data::get_profiles()?;
+    let profile = profiles.get_current_profile()?;
+    Ok(profile.get_tracked_files().clone())
+}
+
+pub fn find(original_name: &str) -> crate::Result<Vec<TrackedFile>> {
+    log_info!("Searching for tracked files named \"{}\"", original_name);
+    let tracked_files = list()?.into_iter()
+        .filter(|file| file.original_name == original_name)
+        .collect();
+    Ok(tracked_files)
+}
+
+pub fn prune(scan_paths: &[PathBuf]) -> crate::Result<Reconciliation> {
+    log_info!("Reconciling the tracked files of the current profile");
+    let (profile_name, tracked_files) = {
+        let mut profiles = data::get_profiles()?;
+        let profile = profiles.get_current_profile()?;
+        (profile.name.clone(), profile.get_tracked_files().clone())
+    };
+
+    // the filesystem is scanned without holding the profile store lock, as it can take a while
+    let reconciliation = tracking::reconcile(&tracked_files, scan_paths);
+    if !reconciliation.moved.is_empty() || !reconciliation.removed.is_empty() {
+        tracking::apply(&profile_name, &reconciliation)?;
+    }
+    Ok(reconciliation)
+}
@@ -22,7 +22,7 @@ pub fn string_to_bytes(hex_string: &str) -> Result<Vec<u8>> {
     const HEX_CHARS: &str = "0123456789ABCDEF";
     let mut hex_bytes = vec![0u8; hex_string.len() / 2];
 
-    if hex_string.len() % 2 != 0 {
+    if !hex_string.len().is_multiple_of(2) {
         return Err(new_err!(InvalidData: InvalidHex, "Length is not a multiple of 2"));
     }
     
@@ -0,0 +1,231 @@
+//! Contains the password strength estimation used to enforce the password policy for profiles
+//!
+//! The estimation is a simplified version of the `zxcvbn` approach: the password is split into the
+//! segments which are cheapest to guess (dictionary words, also with capitals and common "leet"
+//! substitutions, keyboard and alphabet sequences, repeated characters and years), and every other
+//! character costs the full entropy of the used character classes. The entropy of the cheapest
+//! split determines the score, and passwords from a list of the most common ones are always rated
+//! weakest
+
+use crate::core::data::config::GeneralConfig;
+use crate::{log_debug, new_err, Result};
+
+/// Most commonly used passwords, which are guessed first by any attacker. Compared in lowercase and
+/// with trailing digits removed
+const COMMON_PASSWORDS: &[&str] = &[
+    "", "password", "passw0rd", "qwerty", "qwertyuiop", "asdfgh", "asdfghjkl", "zxcvbnm", "azerty",
+    "abc", "abcd", "abcdef", "abcdefg", "abcdefgh", "letmein", "welcome", "admin", "administrator",
+    "root", "toor", "login", "master", "secret", "monkey", "dragon", "football", "baseball",
+    "soccer", "hockey", "batman", "superman", "iloveyou", "trustno", "sunshine", "princess",
+    "shadow", "michael", "jennifer", "jordan", "hunter", "ranger", "buster", "harley", "starwars",
+    "whatever", "freedom", "computer", "internet", "changeme", "default", "guest", "test",
+    "testing", "hello", "hellothere", "charlie", "donald", "pokemon", "mustang", "access",
+    "flower", "cookie", "cheese", "summer", "winter", "spring", "autumn", "pass", "passwd",
+    "databoxer",
+];
+
+/// Common words, which are tried along with the common passwords when guessing parts of a password
+const COMMON_WORDS: &[&str] = &[
+    "the", "and", "you", "love", "god", "money", "word", "key", "lock", "box", "data", "file",
+    "user", "name", "home", "house", "family", "friend", "baby", "angel", "star", "blue", "red",
+    "green", "black", "white", "happy", "lucky", "magic", "power", "king", "queen", "prince",
+    "girl", "boy", "man", "life", "time", "day", "night", "sun", "moon", "fire", "water", "music",
+    "rock", "game", "player", "common", "private", "public", "secure", "security", "safe", "store",
+    "open", "new", "old", "good", "best", "cool", "super", "mine", "my", "your", "one", "two",
+    "first", "last", "work", "school", "office", "company", "system", "server", "backup", "cloud",
+    "phone", "email", "mail", "apple", "google", "windows", "linux", "dog", "cat", "tiger",
+    "lion", "bear", "wolf", "eagle", "orange", "banana", "chocolate", "coffee", "beer", "party",
+];
+
+/// Rows of characters whose runs are guessed as sequences (forwards and backwards)
+const SEQUENCES: &[&str] = &[
+    "abcdefghijklmnopqrstuvwxyz", "0123456789", "qwertyuiop", "asdfghjkl", "zxcvbnm", "qwertzuiop",
+    "yxcvbnm", "azertyuiop", "qsdfghjklm", "wxcvbn",
+];
+
+/// Common "leet" substitutions, which are undone before looking for dictionary words
+const SUBSTITUTIONS: &[(char, char)] = &[
+    ('0', 'o'), ('1', 'i'), ('3', 'e'), ('4', 'a'), ('@', 'a'), ('5', 's'), ('$', 's'), ('7', 't'),
+    ('!', 'i'), ('|', 'l'), ('+', 't'),
+];
+
+/// Minimum length of repeated and sequential runs of characters counted as a single segment
+const MIN_RUN_LENGTH: usize = 3;
+
+/// Names of the strength scores from the weakest to the strongest
+const SCORE_NAMES: [&str; 5] = ["very weak", "weak", "fair", "strong", "very strong"];
+
+/// Estimated strength of a password
+#[derive(Debug)]
+pub struct PasswordStrength {
+    /// Score from 0 (very weak) to 4 (very strong)
+    pub score: u8,
+    /// Estimated entropy of the password in bits
+    pub entropy: f64,
+    /// Whether the password is one of the most common passwords
+    pub common: bool,
+}
+
+impl PasswordStrength {
+    /// Returns the human-readable name of the score
+    pub fn score_name(&self) -> &'static str {
+        SCORE_NAMES[self.score as usize]
+    }
+}
+
+/// Estimates the strength of the provided password
+pub fn estimate_strength(password: &str) -> PasswordStrength {
+    let chars = password.chars().collect::<Vec<char>>();
+
+    let mut pool_size = 0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) { pool_size += 26 }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) { pool_size += 26 }
+    if chars.iter().any(|c| c.is_ascii_digit()) { pool_size += 10 }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') { pool_size += 33 }
+    if chars.iter().any(|c| !c.is_ascii()) { pool_size += 100 }
+    let char_entropy = match pool_size {
+        0 => 0.0,
+        _ => (pool_size as f64).log2()
+    };
+
+    // the cheapest way to guess the first `i` characters, where every character costs the full
+    // entropy unless it's part of a segment which is cheaper to guess as a whole
+    let segments = find_segments(&chars, char_entropy);
+    let mut entropies = vec![f64::INFINITY; chars.len() + 1];
+    entropies[0] = 0.0;
+    for i in 0..chars.len() {
+        entropies[i + 1] = entropies[i + 1].min(entropies[i] + char_entropy);
+        for &(start, end, entropy) in segments.iter().filter(|segment| segment.0 == i) {
+            entropies[end] = entropies[end].min(entropies[start] + entropy);
+        }
+    }
+    let entropy = entropies[chars.len()];
+
+    let normalized = password.to_lowercase();
+    let common = COMMON_PASSWORDS.contains(&normalized.as_str())
+        || COMMON_PASSWORDS.contains(&normalized.trim_end_matches(|c: char| c.is_ascii_digit()));
+
+    let score = match entropy {
+        _ if common => 0,
+        e if e < 28.0 => 0,
+        e if e < 36.0 => 1,
+        e if e < 60.0 => 2,
+        e if e < 80.0 => 3,
+        _ => 4
+    };
+
+    PasswordStrength { score, entropy, common }
+}
+
+/// Finds the parts of the password which are easy to guess as a whole. Returns their start, end and
+/// estimated entropy
+fn find_segments(chars: &[char], char_entropy: f64) -> Vec<(usize, usize, f64)> {
+    let lowercase = chars.iter().map(|c| c.to_ascii_lowercase()).collect::<Vec<char>>();
+    let unsubstituted = lowercase.iter()
+        .map(|c| SUBSTITUTIONS.iter().find(|(from, _)| from == c).map_or(*c, |(_, to)| *to))
+        .collect::<Vec<char>>();
+    let dictionary_entropy = ((COMMON_PASSWORDS.len() + COMMON_WORDS.len()) as f64).log2();
+
+    let mut segments = Vec::new();
+    for start in 0..chars.len() {
+        for word in COMMON_PASSWORDS.iter().chain(COMMON_WORDS).filter(|word| word.len() >= 2) {
+            let word = word.chars().collect::<Vec<char>>();
+            let end = start + word.len();
+            if end > chars.len() || unsubstituted[start..end] != word[..] {
+                continue
+            }
+            // capitalization and substitutions only add a few guesses per word
+            let capitalized = chars[start..end].iter().any(|c| c.is_ascii_uppercase());
+            let substituted = lowercase[start..end] != word[..];
+            let entropy = dictionary_entropy + capitalized as u8 as f64 + substituted as u8 as f64;
+            segments.push((start, end, entropy));
+        }
+
+        let repeated = lowercase[start..].iter().take_while(|c| **c == lowercase[start]).count();
+        if repeated >= MIN_RUN_LENGTH {
+            segments.push((start, start + repeated, char_entropy + (repeated as f64).log2()));
+        }
+
+        let sequential = sequence_length(&lowercase[start..]);
+        if sequential >= MIN_RUN_LENGTH {
+            let entropy = ((SEQUENCES.len() * 2 * 26) as f64).log2() + (sequential as f64).log2();
+            segments.push((start, start + sequential, entropy));
+        }
+
+        let year = lowercase.get(start..start + 4)
+            .map(|digits| digits.iter().collect::<String>())
+            .and_then(|digits| digits.parse::<u32>().ok())
+            .is_some_and(|year| (1900..2100).contains(&year));
+        if year {
+            segments.push((start, start + 4, 200f64.log2()));
+        }
+    }
+
+    segments
+}
+
+/// Returns the length of the sequence (e.g. "abc", "4321" or "qwerty") at the start of the
+/// characters
+fn sequence_length(chars: &[char]) -> usize {
+    SEQUENCES.iter()
+        .flat_map(|sequence| [sequence.chars().collect::<Vec<char>>(), sequence.chars().rev().collect()])
+        .map(|sequence| {
+            let Some(offset) = sequence.iter().position(|c| Some(c) == chars.first()) else { return 0 };
+            chars.iter().zip(&sequence[offset..]).take_while(|(a, b)| a == b).count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Checks the password against the password policy from the config. Errors with the reason if the
+/// password is too weak
+pub fn check_strength(password: &str, config: &GeneralConfig) -> Result<()> {
+    let length = password.chars().count();
+    if length < config.min_password_length {
+        return Err(new_err!(InvalidInput: WeakPassword, format!("at least {} characters required", config.min_password_length)))
+    }
+
+    let strength = estimate_strength(password);
+    log_debug!("Estimated password entropy is {:.1} bits", strength.entropy);
+    if strength.common && config.reject_common_passwords {
+        return Err(new_err!(InvalidInput: WeakPassword, "commonly used password"))
+    }
+    if strength.score < config.min_password_score {
+        let required = SCORE_NAMES[(config.min_password_score as usize).min(SCORE_NAMES.len() - 1)];
+        return Err(new_err!(InvalidInput: WeakPassword, format!("rated {}, at least {} required", strength.score_name(), required)))
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strength_estimation() {
+        assert_eq!(estimate_strength("").score, 0);
+        assert_eq!(estimate_strength("aaaaaaaaaa").score, 0);
+        assert_eq!(estimate_strength("abcdefghijkl").score, 0);
+        assert!(estimate_strength("Password123").common);
+        assert!(estimate_strength("my_password123").score <= 1);
+        assert!(estimate_strength("Sunshine2024!").score <= 1);
+        assert!(estimate_strength("P@ssw0rd-qwerty").score <= 1);
+        assert!(estimate_strength("zyxwvutsrqpo").score == 0);
+        assert!(estimate_strength("mK8#vQ2!xL9p").score >= 3);
+        assert_eq!(estimate_strength("correct horse battery staple").score, 4);
+    }
+
+    #[test]
+    fn test_strength_policy() {
+        let config = GeneralConfig::default();
+
+        assert!(check_strength("", &config).is_err());
+        assert!(check_strength("qwerty2024", &config).is_err());
+        assert!(check_strength("tR7#kq", &config).is_err());
+        assert!(check_strength("tR7#kq!9Lm", &config).is_ok());
+
+        let config = GeneralConfig { reject_common_passwords: false, min_password_score: 0, ..config };
+        assert!(check_strength("qwerty2024", &config).is_ok());
+    }
+}
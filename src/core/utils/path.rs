@@ -1,10 +1,11 @@
 //! Contains functions for path manipulation
 
-use std::{ffi::OsString, fs};
+use std::collections::HashSet;
+use std::{ffi::OsStr, fs};
 use std::path::{Path, PathBuf};
 use crate::core::encryption::boxfile;
 use crate::core::error::IOErrorKind;
-use crate::{log_error, log_info, log_warn, Error, Result};
+use crate::{log_debug, log_error, log_info, log_warn, Error, Result};
 
 /// Opens and parses provided path, returning a flattened list of all found paths. Verifies if the
 /// given paths exists. In case of a directory being provided returns all paths inside of it. Can
@@ -21,9 +22,16 @@ pub fn parse_paths(input_paths: Vec<PathBuf>, recursive: bool) -> Vec<PathBuf> {
         } else if path.is_file() {
             file_paths.push(path);
         } else if !path.exists() {
-            let target_name = path.file_stem().unwrap().to_os_string();
+            let Some(target_name) = path.file_stem() else {
+                log_error!("Unable to find \"{}\"", path.display());
+                continue;
+            };
+            let dir_path = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new(".")
+            };
 
-            match search_for_original(path.parent().unwrap(), target_name) {
+            match search_for_original(dir_path, target_name) {
                 Ok(box_path) => file_paths.push(box_path),
                 Err(err) => {
                     log_error!("Unable to find \"{}\" ({})", path.display(), err);
@@ -49,17 +57,48 @@ fn read_dir(dir_path: &Path, file_paths: &mut Vec<PathBuf>, recursive: bool) ->
     Ok(())
 }
 
-/// Searches `.box` files within a directory for one which matches its original name with provided
-fn search_for_original(dir_path: &Path, target_name: OsString) -> Result<PathBuf> {
-    for entry in fs::read_dir(dir_path)? {
-        let path = entry?.path();
+/// Returns all `.box` files within the provided directory, optionally searching all inner
+/// directories too. Symbolic links to directories are not followed and directories which cannot
+/// be read are skipped with a warning
+pub fn find_boxfiles(dir_path: &Path, recursive: bool) -> Vec<PathBuf> {
+    let mut boxfile_paths = Vec::new();
+    let entries = match fs::read_dir(dir_path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log_warn!("Unable to read directory \"{}\": {}", dir_path.display(), err);
+            return boxfile_paths
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else { continue };
+
+        if file_type.is_dir() {
+            if recursive {
+                boxfile_paths.extend(find_boxfiles(&path, true));
+            }
+        } else if path.is_file() && path.extension().is_some_and(|extension| extension == "box") {
+            boxfile_paths.push(path);
+        }
+    }
 
-        if !path.is_file() || path.extension().unwrap() != "box" { continue; }
+    boxfile_paths
+}
 
-        let boxfile = boxfile::Boxfile::parse(&path)?;
-        let (original_name, _) = boxfile.file_info();
+/// Searches `.box` files within a directory for one which matches its original name with provided.
+/// Only the headers of the boxfiles are read
+fn search_for_original(dir_path: &Path, target_name: &OsStr) -> Result<PathBuf> {
+    for path in find_boxfiles(dir_path, false) {
+        let header = match boxfile::Boxfile::parse_header(&path) {
+            Ok(header) => header,
+            Err(err) => {
+                log_debug!("Skipping \"{}\": {}", path.display(), err);
+                continue;
+            }
+        };
 
-        if target_name.eq(original_name) {
+        if header.name == target_name {
             log_info!("Found an encrypted (.box) file with the same original name: {}", path.display());
             return Ok(path)
         }
@@ -67,3 +106,25 @@ fn search_for_original(dir_path: &Path, target_name: OsString) -> Result<PathBuf
 
     Err(Error::IOError(IOErrorKind::NotFound(target_name.into())))
 }
+
+/// Returns the first path which doesn't exist yet by adding a numbered suffix to the file name of
+/// the provided path (e.g. `file.txt` turns into `file (1).txt`). Paths in `reserved` are treated
+/// as already existing
+pub fn get_free_path(path: &Path, reserved: &HashSet<PathBuf>) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|ext| ext.to_string_lossy());
+
+    let mut i: u32 = 1;
+    loop {
+        let file_name = match &extension {
+            Some(extension) => format!("{} ({}).{}", stem, i, extension),
+            None => format!("{} ({})", stem, i)
+        };
+        let free_path = path.with_file_name(file_name);
+
+        if !free_path.exists() && !reserved.contains(&free_path) {
+            return free_path
+        }
+        i += 1;
+    }
+}
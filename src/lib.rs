@@ -6,15 +6,72 @@
 
 pub use core::error::{Error, Result};
 pub use core::options;
-pub use core::encryption::{boxfile::Boxfile, cipher::{Checksum, Key, Nonce}};
-use self::core::{key, profile};
+pub use core::encryption::{boxfile::{Boxfile, BoxfileHeader, BoxfileInfo}, cipher::{Checksum, Key, Nonce}};
+pub use core::encryption::adapters::{BoxReader, BoxWriter};
+pub use core::batch::BatchResult;
+pub use core::session::Session;
+pub use core::encryption::secret::Secret;
+pub use core::config::ConfigSetting;
+pub use core::data::config::ConfigOrigin;
+pub use core::data::tracking::{Reconciliation, TrackedFile};
+pub use core::find::FoundBoxfile;
+pub use core::profile::ProfileInfo;
+use std::path::{Path, PathBuf};
+use self::core::{config, data, key, profile, store, tracked};
 
 pub mod cli;
 mod core;
 pub mod app;
 
+/// Unlocks the profile with the provided name and returns a `Session` holding its encryption key.
+/// The password is verified only once, so the session can then be used to encrypt and decrypt any
+/// number of files without authenticating again
+///
+/// The key is kept in memory only for the lifetime of the session and is zeroized on drop
+pub fn unlock(profile_name: &str, password: &str) -> Result<Session> {
+    Session::unlock(profile_name, password)
+}
+
+/// Unlocks the currently selected profile. Works like the `unlock` function
+pub fn unlock_current(password: &str) -> Result<Session> {
+    Session::unlock_current(password)
+}
+
+/// Connects to the key agent listening at the provided socket and returns a `Session` which uses
+/// the profile unlocked by the agent. The profile key never leaves the agent, as it only wraps and
+/// unwraps the data keys of the boxfiles
+///
+/// No password needed, as the agent has already authenticated the user
+#[cfg(unix)]
+pub fn connect_agent(socket_path: &std::path::Path) -> Result<Session> {
+    Session::connect_agent(socket_path)
+}
+
+/// Runs the key agent for the unlocked session, blocking the current thread while serving key
+/// operations over a Unix socket at the provided path (or the default one in the data directory).
+/// Returns once the agent is stopped or has been idle for longer than the timeout
+#[cfg(unix)]
+pub fn run_agent(session: Session, socket_path: Option<&std::path::Path>, idle_timeout: std::time::Duration) -> Result<()> {
+    let socket_path = match socket_path {
+        Some(path) => path.to_path_buf(),
+        None => core::agent::get_default_socket()?
+    };
+    core::agent::serve(session, &socket_path, idle_timeout)
+}
+
+/// Stops the key agent listening at the provided socket
+#[cfg(unix)]
+pub fn stop_agent(socket_path: &std::path::Path) -> Result<()> {
+    let (client, _) = core::agent::AgentClient::connect(socket_path)?;
+    client.stop()
+}
+
 /// Encrypts the file at the given path. Extra options can be provided to control the process
 ///
+/// The original file is deleted after encryption unless a different `DeletePolicy` is supplied in
+/// the options. Returns the path to the encrypted file or `None` if it was skipped because of an
+/// already existing output file
+///
 /// Requires the password for the current profile in order to authenticate user and start the
 /// encryption process
 ///
@@ -22,13 +79,17 @@ pub mod app;
 /// Most errors can be safely handled without an unsuccessful exit (e.g. file can just be skipped).
 /// Although it is better to exit on errors related with user authentication and profiles, as the
 /// program will simply not work without a user profile
-pub fn encrypt(file_path: &std::path::Path, password: &str, options: &mut options::EncryptionOptions) -> Result<()> {
-    core::encrypt(file_path, password, options.keep_original_name, &mut options.output_paths)
+pub fn encrypt(file_path: &std::path::Path, password: &str, options: &mut options::EncryptionOptions) -> Result<Option<std::path::PathBuf>> {
+    core::encrypt(file_path, password, options)
 }
 
 /// Decrypts the file at the given path. Extra options can be provided to control the process.
 /// Works similarly to the `encrypt` function just the other way around
 ///
+/// The boxfile is deleted after decryption unless a different `DeletePolicy` is supplied in the
+/// options. Returns the path to the decrypted file or `None` if it was skipped because of an
+/// already existing output file
+///
 /// Requires the password for the current profile in order to authenticate user and start the
 /// decryption process
 ///
@@ -36,26 +97,86 @@ pub fn encrypt(file_path: &std::path::Path, password: &str, options: &mut option
 /// Most errors can be safely handled without an unsuccessful exit (e.g. file can just be skipped).
 /// Although it is better to exit on errors related with user authentication and profiles, as the
 /// program will simply not work without a user profile
-pub fn decrypt(file_path: &std::path::Path, password: &str, options: &mut options::DecryptionOptions) -> Result<()> {
-    core::decrypt(file_path, password, &mut options.output_paths)
+pub fn decrypt(file_path: &std::path::Path, password: &str, options: &mut options::DecryptionOptions) -> Result<Option<std::path::PathBuf>> {
+    core::decrypt(file_path, password, options)
+}
+
+/// Encrypts all the files at the given paths. Works like the `encrypt` function, but the profile
+/// key is unlocked only once and the files are encrypted concurrently using the specified number of
+/// threads (`0` uses all available cores). Custom output paths are assigned in the order of the
+/// input
+///
+/// Returns the result for each file in the order of the input
+///
+/// # Errors
+/// Errors only if the user cannot be authenticated. Errors of the individual files are returned in
+/// their results instead
+pub fn encrypt_batch(file_paths: &[std::path::PathBuf], password: &str, options: &mut options::EncryptionOptions, threads: usize) -> Result<Vec<BatchResult>> {
+    Ok(Session::unlock_current(password)?.encrypt_batch(file_paths, options, threads))
+}
+
+/// Decrypts all the files at the given paths. Works like the `decrypt` function, but the profile
+/// key is unlocked only once and the files are decrypted concurrently using the specified number of
+/// threads (`0` uses all available cores). Custom output paths are assigned in the order of the
+/// input
+///
+/// Returns the result for each file in the order of the input
+///
+/// # Errors
+/// Errors only if the user cannot be authenticated. Errors of the individual files are returned in
+/// their results instead
+pub fn decrypt_batch(file_paths: &[std::path::PathBuf], password: &str, options: &mut options::DecryptionOptions, threads: usize) -> Result<Vec<BatchResult>> {
+    Ok(Session::unlock_current(password)?.decrypt_batch(file_paths, options, threads))
+}
+
+/// Encrypts the provided data into an in-memory boxfile using the provided encryption key. The
+/// file name is stored in the boxfile header as the original name and extension
+///
+/// No profile is accessed, as the key is supplied directly. For producing boxfiles from any
+/// `Write` implementation use `BoxWriter`, which buffers the whole file the same way
+pub fn encrypt_bytes(data: &[u8], file_name: &std::path::Path, key: &Key) -> Result<Vec<u8>> {
+    core::encryption::adapters::encrypt_bytes(data, file_name, key)
 }
 
+/// Decrypts the provided in-memory boxfile using the provided encryption key and returns the
+/// original data
+///
+/// No profile is accessed, as the key is supplied directly. For consuming boxfiles from any
+/// `Read` implementation use `BoxReader`, which decrypts the whole file up front
+pub fn decrypt_bytes(bytes: &[u8], key: &Key) -> Result<Vec<u8>> {
+    core::encryption::adapters::decrypt_bytes(bytes, key)
+}
 
-/// Parses the provided boxfile and retrieves original metadata from the header.
-/// 
-/// Returns a vector which contains strings with retrieved information about original file name,
-/// extension, create, modify and access time. Will skip the unknown metadata unless optionally 
-/// specified not to
-pub fn information(file_path: &std::path::Path, options: options::InformationOptions) -> Result<Vec<String>> {
-    core::get_information(file_path, options.show_unknown)
+/// Reads the information about the provided boxfile from its header.
+///
+/// Returns the original file name, extension, times and attributes together with the sizes, format
+/// version, cipher, compression, padding policy and the fingerprint of the key the file was
+/// encrypted with. Unknown metadata is left as `None`
+///
+/// No authentication needed, as the header is not encrypted and the body is never read
+pub fn information(file_path: &std::path::Path) -> Result<BoxfileInfo> {
+    core::get_information(file_path)
+}
+
+/// Searches the provided paths (recursively) for boxfiles which original name, including the
+/// extension, matches the pattern. The pattern is a glob matching the whole name or, if set in the
+/// options, a regular expression matching any part of it
+///
+/// Returns all matches with the metadata from their headers. Only the headers are read and no
+/// authentication is needed, as the headers are not encrypted
+pub fn find(pattern: &str, search_paths: &[PathBuf], options: options::FindOptions) -> Result<Vec<FoundBoxfile>> {
+    core::find::find(pattern, search_paths, &options)
 }
 
 /// Creates a new profile with the provided password and profile name. Will **not** automatically
 /// switch to the new profile
 ///
+/// The password has to satisfy the password policy from the config (minimum length, strength
+/// score and not being a common password), unless forced in the options
+///
 /// No user authentication needed, as it just creates a new profile
-pub fn create_profile(profile_name: &str, password: &str) -> Result<()> {
-    profile::create(password, profile_name)
+pub fn create_profile(profile_name: &str, password: &str, options: options::ProfileCreateOptions) -> Result<()> {
+    profile::create(password, profile_name, options.force_weak)
 }
 
 /// Deletes the profile with the corresponding name. After deletion will switch back to the first
@@ -80,6 +201,14 @@ pub fn get_profile() -> Result<String> {
     profile::get_current()
 }
 
+/// Returns information about the profile with the provided name or the current one if no name is
+/// provided, including its recent failed authentication attempts
+///
+/// No authentication needed, as it doesn't access the profile's key
+pub fn show_profile(profile_name: Option<&str>) -> Result<ProfileInfo> {
+    profile::show(profile_name)
+}
+
 /// Returns the names of all currently available profiles
 ///
 /// No authentication needed, as it just returns the names
@@ -87,6 +216,57 @@ pub fn get_profiles() -> Result<Vec<String>> {
     profile::get_all()
 }
 
+/// Seals the profile store with the master password, so nothing about the profiles can be read
+/// from the data directory without it. The unsealed files are securely removed
+///
+/// The master password has to satisfy the password policy from the config, unless forced
+pub fn lock_profile_store(master_password: &str, options: options::StoreLockOptions) -> Result<()> {
+    store::lock(master_password, options.force_weak)
+}
+
+/// Restores the profile store sealed with the master password to its unsealed form
+pub fn unlock_profile_store(master_password: &str) -> Result<()> {
+    store::unlock(master_password)
+}
+
+/// Returns whether the profile store is sealed with a master password
+pub fn is_profile_store_sealed() -> Result<bool> {
+    store::is_sealed()
+}
+
+/// Sets the master password used to open the sealed profile store transparently. Without it, the
+/// master password is taken from the `DATABOXER_MASTER_PASSWORD` environment variable or prompted
+/// for in the CLI. Can only be set once
+pub fn set_master_password(master_password: &str) {
+    data::set_master_password(master_password)
+}
+
+/// Converts the JSON profile store into the SQLite one, which is used from then on. The JSON file
+/// is kept as a backup. Returns the number of migrated profiles
+pub fn migrate_profile_store() -> Result<usize> {
+    profile::migrate_store()
+}
+
+/// Returns the boxfiles produced by the current profile, as recorded when they were encrypted
+///
+/// No authentication needed, as it doesn't access the profile's key
+pub fn get_tracked_files() -> Result<Vec<TrackedFile>> {
+    tracked::list()
+}
+
+/// Returns the boxfiles produced by the current profile which were encrypted from a file with the
+/// provided original name (including its extension)
+pub fn find_tracked_files(original_name: &str) -> Result<Vec<TrackedFile>> {
+    tracked::find(original_name)
+}
+
+/// Reconciles the tracked files of the current profile with the filesystem. Boxfiles missing from
+/// their recorded paths are searched for in the provided scan paths and their path is updated if
+/// found, otherwise they are removed from the registry. Returns the changes which were made
+pub fn prune_tracked_files(scan_paths: &[PathBuf]) -> Result<Reconciliation> {
+    tracked::prune(scan_paths)
+}
+
 /// Generates a new encryption key for the current profile
 ///
 /// **Warning:** this will replace the current encryption key, meaning that currently encrypted
@@ -111,4 +291,44 @@ pub fn get_key(password: &str, options: options::KeyGetOptions) -> Result<String
 /// Needs the current profile's password to authenticate
 pub fn set_key(new_key: &str, password: &str) -> Result<()> {
     key::set(password, new_key)
-}
\ No newline at end of file
+}
+
+/// Uses the provided config file instead of the one in the program's config directory. Should be
+/// called before any other function, as it can only be set once
+pub fn set_config_file(config_file: &Path) {
+    data::set_config_file(config_file.to_path_buf())
+}
+
+/// Uses the provided directory for profiles and other program data instead of the default one.
+/// Should be called before any other function, as it can only be set once
+pub fn set_data_dir(data_dir: &Path) {
+    data::os::set_data_dir(data_dir.to_path_buf())
+}
+
+/// Returns the path to the config file in use
+pub fn get_config_path() -> Result<PathBuf> {
+    config::path()
+}
+
+/// Returns the value in effect of the setting with the provided dotted key (e.g.
+/// `encryption.compression`) formatted as a TOML value. Returns `None` if the setting has no value
+pub fn get_config_value(key: &str) -> Result<Option<String>> {
+    config::get(key)
+}
+
+/// Sets the setting with the provided dotted key to the value in the config file. Errors if the
+/// value has a wrong type or is invalid for the setting
+pub fn set_config_value(key: &str, value: &str) -> Result<()> {
+    config::set(key, value)
+}
+
+/// Returns all settings together with their values in effect, including the default ones
+pub fn list_config() -> Result<Vec<ConfigSetting>> {
+    config::list()
+}
+
+/// Resets the setting with the provided dotted key to its default value. Resets the whole config
+/// if no key is provided
+pub fn reset_config(key: Option<&str>) -> Result<()> {
+    config::reset(key)
+}
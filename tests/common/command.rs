@@ -2,9 +2,9 @@
 //! returned Output should be used to determine if the test was successful or not
 
 use std::ffi::OsStr;
-use std::process::{Command, Output};
+use std::process::{Child, Command, Output};
 use std::path::PathBuf;
-use crate::common::PASSWORD;
+use crate::common::{test_root, PASSWORD};
 
 /// Represents the `databoxer [arg]...` command. Acts like a wrapper for the `Command` type
 pub struct DataboxerCommand {
@@ -26,6 +26,13 @@ impl DataboxerCommand {
         command
             .arg("--verbose")
             .args(subcommand.split_ascii_whitespace());
+
+        // keeps the profiles and the config of every test separate from the others and the user
+        if let Some(test_root) = test_root() {
+            command
+                .env("DATABOXER_DATA_DIR", test_root.join("data"))
+                .env("DATABOXER_CONFIG_DIR", test_root.join("config"));
+        }
         
         if needs_password {
             command.arg("--password").arg(PASSWORD);
@@ -40,6 +47,21 @@ impl DataboxerCommand {
         self
     }
 
+    /// Sets an environment variable for the command. Can be chained
+    pub fn env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        self.command.env(key, value);
+        self
+    }
+
+    /// Starts the command in the background without waiting for it to finish
+    pub fn spawn(mut self) -> Child {
+        println!("Spawning: {:?}", &self.command);
+        match self.command.spawn() {
+            Ok(child) => child,
+            Err(err) => panic!("Failed to spawn {:?}: {:?}", &self.command, err)
+        }
+    }
+
     /// Executes the command and fetches the result. Will panic and fail test if failed to execute
     pub fn execute(mut self) -> Output {
         println!("Executing: {:?}", &self.command);
@@ -56,11 +78,11 @@ pub fn print_output(output: &Output) {
 
     let stdout = &output.stdout;
     if !stdout.is_empty() {
-        println!("Stdout:\n{}", std::str::from_utf8(&stdout).unwrap());
+        println!("Stdout:\n{}", std::str::from_utf8(stdout).unwrap());
     }
 
     let stderr = &output.stderr;
     if !stderr.is_empty() {
-        println!("Stderr:\n{}", std::str::from_utf8(&stderr).unwrap());
+        println!("Stderr:\n{}", std::str::from_utf8(stderr).unwrap());
     }
 }
\ No newline at end of file
@@ -1,53 +1,69 @@
 //! Contains common functions and constants for running tests.
+//!
+//! Every test runs in its own temporary directory holding the program data, the config and the
+//! test files, so tests never touch the real profiles of the user and can run in parallel
 
-use std::{fs, io};
-use std::path::Path;
-use databoxer::Error;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::{env, fs, io};
+use command::{DataboxerCommand, print_output};
 
 pub mod command;
 
 pub const PROFILE_NAME: &str = "common-test-profile";
-pub const PASSWORD: &str = "common-test-password";
+pub const PASSWORD: &str = "v7Qm#2xLr!9kTw";
 pub const ORIGINAL_DIR: &str = "files/original";
-pub const TEST_DIR: &str = "files/test";
+
+thread_local! {
+    /// Root directory of the environment of the running test. Tests are run on separate threads,
+    /// so each of them gets its own
+    static TEST_ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
 
 /// Global test environment setup (must be run before each test)
 pub fn setup() {
-    databoxer::create_profile(PROFILE_NAME, PASSWORD)
-        .unwrap_or_else(|err| match err {
-            Error::ProfileError(_) => println!("{}", err),
-            _ => panic!("Unable to create test profile: {}", err)
-        });
-
-    databoxer::select_profile(PROFILE_NAME, PASSWORD)
-        .unwrap_or_else(|err| match err {
-            Error::ProfileError(_) => println!("{}", err),
-            _ => panic!("Unable to select test profile: {}", err)
-        });
+    let test_root = env::temp_dir().join(format!("databoxer-test-{}", uuid::Uuid::new_v4()));
+    TEST_ROOT.with(|root| *root.borrow_mut() = Some(test_root));
 
     copy_original_files()
         .unwrap_or_else(|err| panic!("Unable to copy original test files: {}", err));
+
+    for subcommand in [format!("profile new {}", PROFILE_NAME), format!("profile set {}", PROFILE_NAME)] {
+        let output = DataboxerCommand::new(&subcommand, true).execute();
+        if !output.status.success() {
+            print_output(&output);
+            panic!("Unable to prepare test profile with \"{}\"", subcommand);
+        }
+    }
 }
 
 /// Global test environment cleanup (must be run after each test)
 pub fn cleanup() {
-    databoxer::delete_profile(PROFILE_NAME, PASSWORD)
-        .unwrap_or_else(|err| match err {
-            Error::ProfileError(_) => println!("{}", err),
-            _ => panic!("Unable to delete test profile: {}", err)
-        });
-
-    delete_test_files()
-        .unwrap_or_else(|err| panic!("Unable to delete test files: {}", err));
+    if let Some(test_root) = TEST_ROOT.with(|root| root.borrow_mut().take()) {
+        fs::remove_dir_all(test_root)
+            .unwrap_or_else(|err| panic!("Unable to delete test environment: {}", err));
+    }
+}
+
+/// Returns the root directory of the environment of the running test, if it was set up
+pub fn test_root() -> Option<PathBuf> {
+    TEST_ROOT.with(|root| root.borrow().clone())
+}
+
+/// Returns the directory with the test files of the running test
+pub fn test_dir() -> PathBuf {
+    test_root().expect("Test environment is not set up").join("files")
+}
+
+/// Returns the program data directory of the running test
+pub fn data_dir() -> PathBuf {
+    test_root().expect("Test environment is not set up").join("data")
 }
 
 /// Copies original test files for use in tests
 fn copy_original_files() -> io::Result<()> {
-    let test_dir = Path::new(TEST_DIR);
-
-    if !test_dir.exists() {
-        fs::create_dir(test_dir)?;
-    }
+    let test_dir = test_dir();
+    fs::create_dir_all(&test_dir)?;
 
     for entry in fs::read_dir(ORIGINAL_DIR)? {
         let original_file = entry?.path();
@@ -62,14 +78,3 @@ fn copy_original_files() -> io::Result<()> {
 
     Ok(())
 }
-
-/// Deletes and cleans up test files
-fn delete_test_files() -> io::Result<()> {
-    let test_dir = Path::new(TEST_DIR);
-
-    if test_dir.exists() {
-        fs::remove_dir_all(test_dir)?;
-    }
-
-    Ok(())
-}
\ No newline at end of file
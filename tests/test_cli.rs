@@ -1,14 +1,14 @@
 //! Tests to test how the CLI client performs with different flags and inputs
 
+use std::ffi::OsStr;
+use std::fs;
 use std::iter;
-use std::path::Path;
 use rand::Rng;
 
 mod common;
 
 /// Local test environment setup
 fn setup() {
-    common::cleanup();
     common::setup();
 }
 
@@ -68,7 +68,7 @@ macro_rules! databoxer_cmd {
 fn test_text_encryption() {
     setup();
 
-    let test_dir = Path::new(common::TEST_DIR);
+    let test_dir = common::test_dir();
     let test_file = test_dir.join("text.txt");
 
     let output = databoxer_cmd!(p "box"; &test_file);
@@ -84,7 +84,7 @@ fn test_text_encryption() {
 fn test_image_encryption() {
     setup();
 
-    let test_dir = Path::new(common::TEST_DIR);
+    let test_dir = common::test_dir();
     let test_file = test_dir.join("image.png");
 
     let output = databoxer_cmd!(p "box"; &test_file);
@@ -96,11 +96,29 @@ fn test_image_encryption() {
     cleanup();
 }
 
+#[test]
+fn test_compressed_encryption() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let test_file = test_dir.join("text.txt");
+    let original_data = fs::read(&test_file).unwrap();
+
+    let output = databoxer_cmd!(p "box --keep-name --compression deflate"; &test_file);
+    assert!(output.status.success(), "Compressed encryption failed");
+
+    let output = databoxer_cmd!(p "unbox"; test_file.with_extension("box"));
+    assert!(output.status.success(), "Compressed decryption failed");
+    assert_eq!(fs::read(&test_file).unwrap(), original_data, "Decompressed data differs");
+
+    cleanup();
+}
+
 #[test]
 fn test_directory_encryption() {
     setup();
 
-    let test_dir = Path::new(common::TEST_DIR);
+    let test_dir = common::test_dir();
 
     let output = databoxer_cmd!(p "box"; &test_dir);
     assert!(output.status.success(), "Directory encryption failed");
@@ -115,7 +133,7 @@ fn test_directory_encryption() {
 fn test_recursive_encryption() {
     setup();
 
-    let test_dir = Path::new(common::TEST_DIR);
+    let test_dir = common::test_dir();
 
     let output = databoxer_cmd!(p "box"; &test_dir);
     assert!(output.status.success(), "Recursive encryption failed");
@@ -126,12 +144,344 @@ fn test_recursive_encryption() {
     cleanup();
 }
 
+#[test]
+fn test_parallel_encryption() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let text_file = test_dir.join("text.txt");
+    let image_file = test_dir.join("image.png");
+    let text_output = test_dir.join("first.box");
+    let image_output = test_dir.join("second.box");
+
+    let output = databoxer_cmd!(p "box --jobs 2"; text_file.as_os_str(), image_file.as_os_str(),
+        OsStr::new("-o"), text_output.as_os_str(), OsStr::new("-o"), image_output.as_os_str());
+    assert!(output.status.success(), "Parallel encryption failed");
+    assert!(text_output.exists() && image_output.exists(), "Output paths were not assigned");
+
+    let output = databoxer_cmd!(p "unbox --jobs 2"; &text_output, &image_output);
+    assert!(output.status.success(), "Parallel decryption failed");
+    assert!(text_file.exists(), "Text file was not restored");
+    assert!(image_file.exists(), "Image file was not restored");
+
+    cleanup();
+}
+
+#[test]
+fn test_keep_original() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let test_file = test_dir.join("text.txt");
+
+    let output = databoxer_cmd!(p "box --keep-name --keep-original"; &test_file);
+    assert!(output.status.success(), "Encryption with kept original failed");
+    assert!(test_file.exists(), "Original file was deleted");
+    assert!(test_file.with_extension("box").exists(), "Encrypted file was not created");
+
+    cleanup();
+}
+
+#[test]
+fn test_secure_delete() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let test_file = test_dir.join("text.txt");
+
+    let output = databoxer_cmd!(p "box --secure-delete"; &test_file);
+    assert!(output.status.success(), "Encryption with secure deletion failed");
+    assert!(!test_file.exists(), "Original file was not deleted");
+
+    let output = databoxer_cmd!(p "unbox --verify"; &test_file);
+    assert!(output.status.success(), "Decryption with verification failed");
+    assert!(test_file.exists(), "Decrypted file was not created");
+
+    cleanup();
+}
+
+#[test]
+fn test_output_conflict() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let test_file = test_dir.join("text.txt");
+    let box_file = test_dir.join("text.box");
+
+    let output = databoxer_cmd!(p "box --keep-name --keep-original"; &test_file);
+    assert!(output.status.success(), "Text encryption failed");
+
+    let output = databoxer_cmd!(p "box --keep-name --keep-original"; &test_file);
+    assert!(!output.status.success(), "Existing encrypted file was overwritten");
+
+    let output = databoxer_cmd!(p "box --keep-name --keep-original --conflict rename-with-suffix"; &test_file);
+    assert!(output.status.success(), "Encryption with renamed output failed");
+    assert!(test_dir.join("text (1).box").exists(), "Renamed encrypted file was not created");
+
+    let output = databoxer_cmd!(p "unbox --keep-original"; &box_file);
+    assert!(!output.status.success(), "Existing original file was overwritten");
+
+    let output = databoxer_cmd!(p "unbox --overwrite"; &box_file);
+    assert!(output.status.success(), "Decryption with overwrite failed");
+    assert!(!box_file.exists(), "Encrypted file was not deleted");
+
+    cleanup();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_preserve_attributes() {
+    use std::fs::{self, File, FileTimes, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, SystemTime};
+
+    setup();
+
+    let test_dir = common::test_dir();
+    let test_file = test_dir.join("text.txt");
+    let modify_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+    fs::set_permissions(&test_file, Permissions::from_mode(0o640)).unwrap();
+    File::options().write(true).open(&test_file).unwrap()
+        .set_times(FileTimes::new().set_modified(modify_time)).unwrap();
+
+    let output = databoxer_cmd!(p "box"; &test_file);
+    assert!(output.status.success(), "Text encryption failed");
+
+    let output = databoxer_cmd!(p "unbox --preserve-attributes"; &test_file);
+    assert!(output.status.success(), "Text decryption failed");
+
+    let metadata = fs::metadata(&test_file).unwrap();
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o640, "Permissions were not restored");
+    assert_eq!(metadata.modified().unwrap(), modify_time, "Modify time was not restored");
+
+    cleanup();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_preserve_extended_attributes() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let test_file = test_dir.join("text.txt");
+
+    if xattr::set(&test_file, "user.databoxer-test", b"tag").is_err() {
+        println!("Extended attributes are not supported by the filesystem, skipping");
+        cleanup();
+        return;
+    }
+
+    let output = databoxer_cmd!(p "box"; &test_file);
+    assert!(output.status.success(), "Text encryption failed");
+
+    let output = databoxer_cmd!(p "unbox --preserve-attributes"; &test_file);
+    assert!(output.status.success(), "Text decryption failed");
+
+    let value = xattr::get(&test_file, "user.databoxer-test").unwrap();
+    assert_eq!(value.as_deref(), Some(&b"tag"[..]), "Extended attribute was not restored");
+
+    cleanup();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_agent() {
+    use std::{env, thread};
+    use std::time::Duration;
+    use common::command::{DataboxerCommand, print_output};
+
+    setup();
+
+    let test_dir = common::test_dir();
+    let test_file = test_dir.join("text.txt");
+    let socket_path = env::temp_dir().join(format!("databoxer-test-{}.sock", uuid::Uuid::new_v4()));
+
+    let mut command = DataboxerCommand::new("agent --timeout 60", true);
+    command.arg("--socket").arg(&socket_path);
+    let mut agent = command.spawn();
+
+    for _ in 0..100 {
+        if socket_path.exists() {
+            break
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert!(socket_path.exists(), "Agent socket was not created");
+    let mode = std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&socket_path).unwrap().permissions());
+    assert_eq!(mode & 0o777, 0o600, "Agent socket is accessible by other users");
+
+    for subcommand in ["box --keep-name", "unbox"] {
+        let mut command = DataboxerCommand::new(subcommand, false);
+        command.env("DATABOXER_AGENT_SOCK", &socket_path).arg(&test_file);
+        let output = command.execute();
+        print_output(&output);
+        assert!(output.status.success(), "\"{}\" using the agent failed", subcommand);
+    }
+    assert!(test_file.exists(), "Decrypted file was not created");
+
+    let output = databoxer_cmd!("agent --stop --socket"; &socket_path);
+    assert!(output.status.success(), "Unable to stop the agent");
+    assert!(agent.wait().unwrap().success(), "Agent exited with an error");
+    assert!(!socket_path.exists(), "Agent socket was not removed");
+
+    cleanup();
+}
+
+#[test]
+fn test_password_sources() {
+    use std::fs;
+    use common::command::{DataboxerCommand, print_output};
+
+    setup();
+
+    let test_dir = common::test_dir();
+    let test_file = test_dir.join("text.txt");
+    let password_file = test_dir.join("password");
+    fs::write(&password_file, format!("{}\n", common::PASSWORD)).unwrap();
+
+    let output = databoxer_cmd!("box --keep-name --password-file"; &password_file, &test_file);
+    assert!(output.status.success(), "Encryption with password file failed");
+
+    let mut command = DataboxerCommand::new("unbox", false);
+    command.env("DATABOXER_PASSWORD", common::PASSWORD).arg(&test_file);
+    let output = command.execute();
+    print_output(&output);
+    assert!(output.status.success(), "Decryption with password from environment failed");
+
+    let mut command = DataboxerCommand::new("box", false);
+    command.env("DATABOXER_PASSWORD", "wrong-password").arg(&test_file);
+    let output = command.execute();
+    print_output(&output);
+    assert!(!output.status.success(), "Encryption with wrong password succeeded");
+
+    for fd in ["2", "99"] {
+        let output = databoxer_cmd!("box --password-fd"; fd, test_file.to_str().unwrap());
+        assert!(!output.status.success(), "Password was read from descriptor {}", fd);
+    }
+
+    cleanup();
+}
+
+#[test]
+fn test_profile_store_migration() {
+    setup();
+
+    let test_file = common::test_dir().join("text.txt");
+    let data_dir = common::data_dir();
+
+    let output = databoxer_cmd!(p "box --keep-name"; &test_file);
+    assert!(output.status.success(), "Encryption before migration failed");
+
+    let output = databoxer_cmd!("migrate-store");
+    assert!(output.status.success(), "Profile store migration failed");
+    assert!(data_dir.join("profiles.db").exists(), "Profile database was not created");
+    assert!(data_dir.join("profiles.json.bak").exists(), "JSON store was not kept as a backup");
+
+    let output = databoxer_cmd!("migrate-store");
+    assert!(!output.status.success(), "Profile store was migrated twice");
+
+    let output = databoxer_cmd!(p "unbox"; test_file.with_extension("box"));
+    assert!(output.status.success(), "Decryption after migration failed");
+
+    let output = databoxer_cmd!("profile list");
+    assert!(String::from_utf8_lossy(&output.stdout).contains(common::PROFILE_NAME), "Profile was not migrated");
+
+    cleanup();
+}
+
+#[test]
+fn test_sealed_profile_store() {
+    use common::command::{DataboxerCommand, print_output};
+
+    setup();
+
+    let master_password = "Ht4@wZ8p!qN3sK";
+    let test_file = common::test_dir().join("text.txt");
+    let data_dir = common::data_dir();
+
+    let output = databoxer_cmd!("store lock --password"; master_password);
+    assert!(output.status.success(), "Sealing the profile store failed");
+    assert!(data_dir.join("profiles.sealed").exists(), "Sealed store was not created");
+    assert!(!data_dir.join("profiles.json").exists(), "Unsealed store was not removed");
+
+    let output = databoxer_cmd!("profile list");
+    assert!(!output.status.success(), "Sealed store was opened without the master password");
+
+    let mut command = DataboxerCommand::new("profile list", false);
+    command.env("DATABOXER_MASTER_PASSWORD", "wrong-master-password");
+    let output = command.execute();
+    print_output(&output);
+    assert!(!output.status.success(), "Sealed store was opened with a wrong master password");
+
+    for subcommand in ["box --keep-name", "unbox"] {
+        let mut command = DataboxerCommand::new(subcommand, true);
+        command.env("DATABOXER_MASTER_PASSWORD", master_password).arg(&test_file);
+        let output = command.execute();
+        print_output(&output);
+        assert!(output.status.success(), "\"{}\" with the sealed store failed", subcommand);
+    }
+
+    let output = databoxer_cmd!("store unlock --password"; master_password);
+    assert!(output.status.success(), "Unsealing the profile store failed");
+    assert!(!data_dir.join("profiles.sealed").exists(), "Sealed store was not removed");
+
+    let output = databoxer_cmd!("profile list");
+    assert!(String::from_utf8_lossy(&output.stdout).contains(common::PROFILE_NAME), "Profile was not restored");
+
+    cleanup();
+}
+
+#[test]
+fn test_concurrent_profile_creation() {
+    use common::command::DataboxerCommand;
+
+    setup();
+
+    let profile_names = (0..12).map(|i| format!("concurrent-profile-{}", i)).collect::<Vec<String>>();
+    let processes = profile_names.iter()
+        .map(|name| {
+            let mut command = DataboxerCommand::new(&format!("profile new {}", name), true);
+            command.env("DATABOXER_STORAGE_LOCK_TIMEOUT", "120");
+            command.spawn()
+        })
+        .collect::<Vec<_>>();
+    for mut process in processes {
+        assert!(process.wait().unwrap().success(), "Concurrent profile creation failed");
+    }
+
+    let output = databoxer_cmd!("profile list");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for name in &profile_names {
+        assert!(stdout.contains(name.as_str()), "Profile \"{}\" was lost", name);
+    }
+    assert!(stdout.contains(common::PROFILE_NAME), "Existing profile was lost");
+
+    cleanup();
+}
+
 #[test]
 fn test_profile_manipulation() {
     setup();
 
     let profile_name: &str = "TEST PROFILE NAME";
 
+    let output = databoxer_cmd!("profile new --password"; "qwerty", "WEAK PROFILE");
+    assert!(!output.status.success(), "Profile with a weak password was created");
+
+    let output = databoxer_cmd!("profile new --force-weak --password"; "qwerty", "WEAK PROFILE");
+    assert!(output.status.success(), "Profile creation with forced weak password failed");
+
+    let output = databoxer_cmd!("profile set --password"; "wrong-password", "WEAK PROFILE");
+    assert!(!output.status.success(), "Profile selection with wrong password succeeded");
+
+    let output = databoxer_cmd!("profile show"; "WEAK PROFILE");
+    assert!(output.status.success(), "Profile information retrieval failed");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Failed attempts: 1"), "Failed attempt was not recorded");
+
+    let output = databoxer_cmd!("profile delete --password"; "qwerty", "WEAK PROFILE");
+    assert!(output.status.success(), "Weak profile deletion failed");
+
     let output = databoxer_cmd!(p "profile new"; profile_name);
     assert!(output.status.success(), "Profile creation failed");
 
@@ -140,11 +490,11 @@ fn test_profile_manipulation() {
 
     let output = databoxer_cmd!("profile get");
     assert!(output.status.success(), "Profile name retrieval failed");
-    assert!(output.stdout.len() > 0, "Invalid output for current profile");
+    assert!(!output.stdout.is_empty(), "Invalid output for current profile");
 
     let output = databoxer_cmd!("profile list");
     assert!(output.status.success(), "Profiles list retrieval failed");
-    assert!(output.stdout.len() > 0, "Invalid output for the list of existing profiles");
+    assert!(!output.stdout.is_empty(), "Invalid output for the list of existing profiles");
 
     let output = databoxer_cmd!(p "profile delete"; profile_name);
     assert!(output.status.success(), "Profile deletion failed");
@@ -152,11 +502,107 @@ fn test_profile_manipulation() {
     cleanup();
 }
 
+#[test]
+fn test_config_manipulation() {
+    use common::command::{DataboxerCommand, print_output};
+
+    setup();
+
+    let config_file = common::test_dir().join("config.toml");
+    fs::write(&config_file, "# custom settings\n[general]\nthreads = 2 # two cores\n").unwrap();
+    let config_arg = format!("--config {}", config_file.display());
+
+    let output = databoxer_cmd!(&format!("{} config set", config_arg); "general.threads", "4");
+    assert!(output.status.success(), "Setting a value failed");
+
+    let output = databoxer_cmd!(&format!("{} config set", config_arg); "encryption.compression", "deflate");
+    assert!(output.status.success(), "Setting a value in a new section failed");
+
+    let output = databoxer_cmd!(&format!("{} config set", config_arg); "general.threads", "many");
+    assert!(!output.status.success(), "Value of a wrong type was accepted");
+
+    let output = databoxer_cmd!(&format!("{} config set", config_arg); "general.unknown", "1");
+    assert!(!output.status.success(), "Unknown setting was accepted");
+
+    let config_data = fs::read_to_string(&config_file).unwrap();
+    assert!(config_data.contains("# custom settings"), "Comments were not preserved");
+    assert!(config_data.contains("threads = 4 # two cores"), "Value was not set in place");
+
+    let output = databoxer_cmd!(&format!("{} config get", config_arg); "encryption.compression");
+    assert!(output.status.success(), "Getting a value failed");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"deflate\""), "Invalid value returned");
+
+    let output = databoxer_cmd!(&format!("{} config reset", config_arg); "general.threads");
+    assert!(output.status.success(), "Resetting a value failed");
+
+    let output = databoxer_cmd!(&format!("{} config list", config_arg));
+    assert!(output.status.success(), "Listing the settings failed");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("general.threads = 0"), "Value was not reset");
+
+    let mut command = DataboxerCommand::new(&format!("{} config list --origin", config_arg), false);
+    command.env("DATABOXER_GENERAL_THREADS", "3");
+    let output = command.execute();
+    print_output(&output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("general.threads = 3 (environment variable DATABOXER_GENERAL_THREADS)"), "Environment variable was not applied");
+    assert!(stdout.contains("encryption.compression = \"deflate\" (global file"), "Invalid origin of a value");
+
+    let mut command = DataboxerCommand::new(&format!("{} config list", config_arg), false);
+    command.env("DATABOXER_GENERAL_THREADS", "many");
+    let output = command.execute();
+    assert!(!output.status.success(), "Environment variable of a wrong type was accepted");
+
+    let output = databoxer_cmd!(&format!("{} config reset", config_arg));
+    assert!(output.status.success(), "Resetting the config failed");
+    let config_data = fs::read_to_string(&config_file).unwrap();
+    assert!(config_data.contains("# custom settings"), "Comments were not preserved by the reset");
+    assert!(!config_data.contains("compression") && !config_data.contains("min_password_length"), "Settings were not removed by the reset");
+
+    let output = databoxer_cmd!(&format!("{} config path", config_arg));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("config.toml"), "Invalid config path returned");
+
+    let output = databoxer_cmd!(&format!("config path {} --data-dir", config_arg); &common::data_dir());
+    assert!(output.status.success(), "Global flags were rejected after the subcommand");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("config.toml"), "Config flag after the subcommand was ignored");
+
+    cleanup();
+}
+
+#[test]
+fn test_config_flag_overrides() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let test_file = test_dir.join("text.txt");
+    let output_dir = test_dir.join("output");
+    let config_file = test_dir.join("config.toml");
+    let config_data = format!("[encryption]\nkeep_name = true\n[storage]\noutput_dir = {:?}\n", output_dir.display().to_string());
+    fs::write(&config_file, config_data).unwrap();
+    let config_arg = format!("--config {}", config_file.display());
+
+    let output = databoxer_cmd!(&format!("{} config list", config_arg));
+    assert!(output.status.success(), "Missing output directory broke a command which doesn't use it");
+
+    let box_cmd = format!("{} box", config_arg);
+    let output = databoxer_cmd!(p box_cmd.as_str(); &test_file);
+    assert!(!output.status.success(), "Encrypted into a missing output directory");
+
+    fs::create_dir(&output_dir).unwrap();
+    let box_cmd = format!("{} box --no-keep-name", config_arg);
+    let output = databoxer_cmd!(p box_cmd.as_str(); &test_file);
+    assert!(output.status.success(), "Encryption into the output directory failed");
+    let boxfiles = fs::read_dir(&output_dir).unwrap().collect::<Vec<_>>();
+    assert_eq!(boxfiles.len(), 1, "Boxfile was not written to the output directory");
+    assert_ne!(boxfiles[0].as_ref().unwrap().file_name(), "text.box", "Name was kept despite --no-keep-name");
+
+    cleanup();
+}
+
 #[test]
 fn test_key_generation() {
     setup();
 
-    let test_dir = Path::new(common::TEST_DIR);
+    let test_dir = common::test_dir();
     let test_file = test_dir.join("text.txt");
 
     let output = databoxer_cmd!(p "key new");
@@ -175,7 +621,7 @@ fn test_key_generation() {
 fn test_key_setting() {
     setup();
 
-    let test_dir = Path::new(common::TEST_DIR);
+    let test_dir = common::test_dir();
     let test_file = test_dir.join("text.txt");
     
     // Generate a random 64-byte HEX string
@@ -205,7 +651,7 @@ fn test_key_setting() {
 fn test_file_information() {
     setup();
     
-    let test_dir = Path::new(common::TEST_DIR);
+    let test_dir = common::test_dir();
     let test_file = test_dir.join("text.txt");
 
     let output = databoxer_cmd!(p "box"; &test_file);
@@ -213,6 +659,149 @@ fn test_file_information() {
     
     let output = databoxer_cmd!("information"; &test_file);
     assert!(output.status.success(), "Information retrieval failed");
-    
+
+    let output = databoxer_cmd!("information --json --time-format unix"; &test_file);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "JSON information retrieval failed");
+    assert!(stdout.contains("\"name\": \"text\"") && stdout.contains("\"key_fingerprint\""), "JSON information is incomplete");
+
+    let output = databoxer_cmd!("information --time-format %Q"; &test_file);
+    assert!(!output.status.success(), "Invalid time format was accepted");
+
+    cleanup();
+}
+
+#[test]
+fn test_legacy_boxfile() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let boxfile = test_dir.join("legacy.box");
+    fs::copy("tests/fixtures/legacy-v1.box", &boxfile).expect("Unable to copy the version 1 boxfile");
+
+    // The fixture was encrypted with the key made of the bytes 0 to 31
+    let key = (0..32u8).map(|byte| format!("{:02X}", byte)).collect::<String>();
+    let output = databoxer_cmd!(p "key set"; &key);
+    assert!(output.status.success(), "Key set failed");
+
+    let output = databoxer_cmd!("information --json"; &boxfile);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "Version 1 information retrieval failed");
+    assert!(stdout.contains("\"format_version\": 1"), "Format version is not reported");
+
+    let output = databoxer_cmd!(p "unbox"; &boxfile);
+    assert!(output.status.success(), "Version 1 decryption failed");
+    let contents = fs::read(test_dir.join("legacy.txt")).expect("Decrypted file is missing");
+    assert_eq!(contents, b"Written by boxfile format version 1\n", "Version 1 data changed");
+
+    cleanup();
+}
+#[test]
+fn test_tracked_files() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let moved_dir = test_dir.join("moved");
+    fs::create_dir(&moved_dir).expect("Unable to create a directory for the moved boxfile");
+
+    let output = databoxer_cmd!(p "box --keep-name"; test_dir.join("text.txt"), test_dir.join("image.png"));
+    assert!(output.status.success(), "Encryption failed");
+
+    let output = databoxer_cmd!("tracked list");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("text.txt") && stdout.contains("image.png"), "Encrypted files were not tracked");
+
+    fs::rename(test_dir.join("text.box"), moved_dir.join("text.box")).expect("Unable to move the boxfile");
+    fs::remove_file(test_dir.join("image.box")).expect("Unable to delete the boxfile");
+
+    let output = databoxer_cmd!("tracked prune"; &test_dir);
+    assert!(output.status.success(), "Pruning the tracked files failed");
+
+    let output = databoxer_cmd!("tracked find"; "text.txt");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("moved"), "Moved boxfile was not updated");
+
+    let output = databoxer_cmd!("tracked find"; "image.png");
+    assert!(!output.status.success(), "Deleted boxfile was not removed");
+
+    let output = databoxer_cmd!("tracked find --json"; "image.png");
+    assert!(output.status.success(), "Empty JSON search result is reported as an error");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"tracked_files\": []"), "Search result is not an empty document");
+
+    let output = databoxer_cmd!(p "unbox"; moved_dir.join("text.box"));
+    assert!(output.status.success(), "Decryption failed");
+
+    let output = databoxer_cmd!("tracked find"; "text.txt");
+    assert!(!output.status.success(), "Decrypted boxfile is still tracked");
+
     cleanup();
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_find_boxfiles() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let inner_dir = test_dir.join("inner");
+    fs::create_dir(&inner_dir).expect("Unable to create an inner directory");
+    fs::rename(test_dir.join("image.png"), inner_dir.join("image.png")).expect("Unable to move the test file");
+    fs::write(test_dir.join("no_extension"), b"plain file").expect("Unable to create a file without extension");
+
+    let output = databoxer_cmd!(p "box"; test_dir.join("text.txt"), inner_dir.join("image.png"));
+    assert!(output.status.success(), "Encryption failed");
+
+    let search_dir = test_dir.to_str().expect("Test directory is not valid UTF-8");
+
+    let output = databoxer_cmd!("find"; "*.png", search_dir);
+    assert!(output.status.success(), "Boxfile in an inner directory was not found");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("inner"), "Wrong boxfile was found");
+
+    let output = databoxer_cmd!("find --regex"; r"^(text|image)\.", search_dir);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Found 2"), "Not all matching boxfiles were found");
+
+    let output = databoxer_cmd!("find --ignore-case"; "TEXT.TXT", search_dir);
+    assert!(output.status.success(), "Case insensitive search failed");
+
+    let output = databoxer_cmd!("find"; "missing.txt", search_dir);
+    assert!(!output.status.success(), "Missing file was found");
+
+    let output = databoxer_cmd!("find --json"; "missing.txt", search_dir);
+    assert!(output.status.success(), "Empty JSON search result is reported as an error");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"boxfiles\": []"), "Search result is not an empty document");
+
+    let output = databoxer_cmd!("find --regex"; "(", search_dir);
+    assert!(!output.status.success(), "Invalid regex was accepted");
+
+    let output = databoxer_cmd!(p "unbox"; test_dir.join("missing"));
+    assert!(!output.status.success(), "Missing extensionless file was decrypted");
+
+    cleanup();
+}
+
+#[test]
+fn test_json_output() {
+    setup();
+
+    let test_dir = common::test_dir();
+    let parse_json = |stdout: &[u8]| -> serde_json::Value {
+        serde_json::from_slice(stdout).expect("Output is not a single JSON document")
+    };
+
+    let output = databoxer_cmd!(p "--json box"; test_dir.join("text.txt"), test_dir.join("image.png"));
+    assert!(output.status.success(), "Encryption failed");
+    let document = parse_json(&output.stdout);
+    assert_eq!(document["succeeded"], 2, "Wrong number of encrypted files");
+    assert!(document["files"][0]["output"].is_string(), "Output path is missing");
+
+    let output = databoxer_cmd!("profile list --json");
+    let document = parse_json(&output.stdout);
+    assert!(document["profiles"].as_array().is_some_and(|profiles| profiles.contains(&common::PROFILE_NAME.into())), "Profile is not listed");
+
+    let output = databoxer_cmd!("profile show --json");
+    assert_eq!(parse_json(&output.stdout)["current"], true, "Profile information is incomplete");
+
+    let output = databoxer_cmd!(p "--json key set"; "invalid-key");
+    assert!(!output.status.success(), "Invalid key was accepted");
+    assert!(parse_json(&output.stdout)["error"]["kind"].is_string(), "Error kind is missing");
+
+    cleanup();
+}